@@ -0,0 +1,275 @@
+use std::io::{self, Write};
+use serde::Serialize;
+
+use freebj::card::Card;
+use freebj::analyzer::{Analyzer, ActionEvs};
+use freebj::dealer_probabilities::{DealerProbabilities, DealerOutcomes,
+                                   CardCounts, shoe_counts};
+use freebj::game_rules::{GameRules, Soft17};
+
+/// The format the exact analyses are written in.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum AnalysisFormat {
+    Text,
+    Json,
+}
+
+/// The outcomes of the dealer's hand from an upcard, as written in JSON.
+#[derive(Serialize)]
+struct UpcardOutcomes {
+    upcard: Card,
+    /// Whether the outcomes are once the dealer peeked for a natural
+    peeked: bool,
+    #[serde(flatten)]
+    outcomes: DealerOutcomes,
+}
+
+/// The expected values of a hand's actions, as written in JSON.
+#[derive(Serialize)]
+struct HandEvs<'a> {
+    hand:   &'a [Card],
+    upcard: Card,
+    #[serde(flatten)]
+    evs:    ActionEvs,
+    /// The name of the best action
+    best:   &'static str,
+}
+
+/// Returns the cards of the rules' shoe once `removed` are dealt from it.
+pub fn cards_left(rules: &GameRules, removed: &[Card])
+    -> Result<CardCounts, String> {
+    let mut cards = shoe_counts(rules.decks);
+    for card in removed.iter() {
+        let left = &mut cards[card.0 as usize - 1];
+        if *left == 0 {
+            return Err(format!("no {} left to remove from the shoe", card));
+        }
+        *left -= 1;
+    }
+    Ok(cards)
+}
+
+/// Writes the exact probabilities of the dealer's final totals, blackjack
+/// and busting from each upcard, drawing from `removed` dealt from the
+/// shoe; the upcards `removed` exhausted are skipped.
+pub fn write_dealer_outcomes(output: &mut dyn Write,
+                             rules: &GameRules,
+                             removed: &[Card],
+                             format: AnalysisFormat) -> io::Result<()> {
+    let cards = cards_left(rules, removed)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let probabilities = DealerProbabilities::new(&rules.soft17);
+    let upcards: Vec<UpcardOutcomes> = (1..=10).filter_map(|up| {
+        let mut cards = cards;
+        cards[up as usize - 1] = cards[up as usize - 1].checked_sub(1)?;
+        let peeked = rules.dealer_peeks(Card(up));
+        Some(UpcardOutcomes {
+            upcard: Card(up),
+            peeked,
+            outcomes: probabilities.outcomes(&cards, Card(up), peeked),
+        })
+    }).collect();
+
+    if format == AnalysisFormat::Json {
+        serde_json::to_writer_pretty(&mut *output, &upcards)?;
+        return writeln!(output);
+    }
+
+    writeln!(output, "Dealer outcomes: {}", describe(rules, removed))?;
+    writeln!(output)?;
+    writeln!(output, "{:<4}{:>9}{:>9}{:>9}{:>9}{:>9}{:>9}{:>9}",
+             "Up", "17", "18", "19", "20", "21", "BJ", "Bust")?;
+    for upcard in upcards.iter() {
+        write!(output, "{:<4}", upcard.upcard.to_string())?;
+        for p in upcard.outcomes.totals.iter() {
+            write!(output, "{:>8.3}%", 100.0 * p)?;
+        }
+        if upcard.peeked {
+            write!(output, "{:>9}", "-")?;
+        } else {
+            write!(output, "{:>8.3}%", 100.0 * upcard.outcomes.blackjack)?;
+        }
+        writeln!(output, "{:>8.3}%", 100.0 * upcard.outcomes.bust)?;
+    }
+    if upcards.iter().any(|upcard| upcard.peeked) {
+        writeln!(output)?;
+        writeln!(output, "-: the dealer peeked, the totals are once they \
+                          have no natural.")?;
+    }
+
+    Ok(())
+}
+
+/// Writes the exact expected values of the actions of a player hand against
+/// an upcard, drawing from the shoe once `removed`, the hand and the upcard
+/// are dealt from it.
+pub fn write_hand_evs(output: &mut dyn Write,
+                      rules: &GameRules,
+                      hand: &[Card],
+                      upcard: Card,
+                      removed: &[Card],
+                      format: AnalysisFormat) -> io::Result<()> {
+    // The analyzer deals the hand and the upcard itself
+    let dealt: Vec<Card> = removed.iter().chain(hand).chain(Some(&upcard))
+        .copied()
+        .collect();
+    let mut cards = cards_left(rules, &dealt)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    for card in hand.iter().chain(Some(&upcard)) {
+        cards[card.0 as usize - 1] += 1;
+    }
+    let evs = Analyzer::new(rules).with_cards(cards).hand_evs(hand, upcard);
+
+    let actions = [
+        ("stand", Some(evs.stand)),
+        ("hit", evs.hit),
+        ("double", evs.double),
+        ("split", evs.split),
+        ("surrender", evs.surrender),
+    ];
+    let best = actions.iter()
+        .find(|(_, ev)| *ev == Some(evs.best()))
+        .map_or("stand", |&(name, _)| name);
+
+    if format == AnalysisFormat::Json {
+        let hand_evs = HandEvs { hand, upcard, evs, best };
+        serde_json::to_writer_pretty(&mut *output, &hand_evs)?;
+        return writeln!(output);
+    }
+
+    let cards: Vec<String> = hand.iter().map(|card| card.to_string())
+        .collect();
+    writeln!(output, "{} vs {}: {}", cards.join("-"), upcard,
+             describe(rules, removed))?;
+    writeln!(output)?;
+    for &(name, ev) in actions.iter() {
+        if let Some(ev) = ev {
+            write!(output, "{:<10}{:>+10.6}", name, ev)?;
+            if name == best {
+                write!(output, "  best")?;
+            }
+            writeln!(output)?;
+        }
+    }
+    if rules.dealer_peeks(upcard) {
+        writeln!(output)?;
+        writeln!(output, "Once the dealer has no natural.")?;
+    }
+
+    Ok(())
+}
+
+/// Returns a short description of the rules the dealer's hand depends on.
+fn describe(rules: &GameRules, removed: &[Card]) -> String {
+    let mut text = vec![
+        if rules.decks == 1 {
+            String::from("1 deck")
+        } else {
+            format!("{} decks", rules.decks)
+        },
+        String::from(if rules.soft17 == Soft17::H17 { "H17" } else { "S17" }),
+    ];
+    let peeked: Vec<&str> = [(Card(1), "A"), (Card(10), "10")].iter()
+        .filter(|(card, _)| rules.dealer_peeks(*card))
+        .map(|&(_, name)| name)
+        .collect();
+    text.push(if peeked.is_empty() {
+        String::from("no peek")
+    } else {
+        format!("peek under {}", peeked.join(" and "))
+    });
+    if !removed.is_empty() {
+        let cards: Vec<String> = removed.iter()
+            .map(|card| card.to_string())
+            .collect();
+        text.push(format!("less {}", cards.join(", ")));
+    }
+    text.join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::analysis::{write_dealer_outcomes, write_hand_evs, cards_left,
+                          AnalysisFormat};
+    use freebj::game_rules::{GameRules, GameType};
+    use freebj::card::Card;
+
+    #[test]
+    fn it_writes_the_dealer_outcomes() {
+        let rules = GameRules::default();
+        let mut output = Vec::new();
+        write_dealer_outcomes(&mut output, &rules, &[], AnalysisFormat::Text)
+            .unwrap();
+        let text = String::from_utf8(output).unwrap();
+
+        assert!(text.starts_with("Dealer outcomes: 6 decks, S17, \
+                                  peek under A and 10\n"));
+        assert!(text.contains("\nA   "));
+        assert!(text.contains("        -"));
+        assert_eq!(text.lines().filter(|line| line.ends_with('%')).count(),
+                   10);
+
+        let rules = GameRules { game_type: GameType::Enhc, ..rules };
+        let mut output = Vec::new();
+        write_dealer_outcomes(&mut output, &rules, &[Card(10), Card(6)],
+                              AnalysisFormat::Json).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+        let ten = &json[9];
+        assert_eq!(ten["upcard"], 10);
+        assert_eq!(ten["peeked"], false);
+        let sum = ten["totals"].as_array().unwrap().iter()
+            .map(|p| p.as_f64().unwrap())
+            .sum::<f64>()
+            + ten["blackjack"].as_f64().unwrap()
+            + ten["bust"].as_f64().unwrap();
+        assert!((sum - 1.0).abs() < 1e-12);
+
+        // No fives are left to be the upcard
+        let rules = GameRules { decks: 1, ..rules };
+        let mut output = Vec::new();
+        write_dealer_outcomes(&mut output, &rules, &[Card(5); 4],
+                              AnalysisFormat::Json).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+        let upcards: Vec<_> = json.as_array().unwrap().iter()
+            .map(|upcard| upcard["upcard"].as_u64().unwrap())
+            .collect();
+        assert_eq!(upcards, [1, 2, 3, 4, 6, 7, 8, 9, 10]);
+    }
+
+    #[test]
+    fn it_writes_the_hand_evs() {
+        let rules = GameRules { decks: 1, ..GameRules::default() };
+        let mut output = Vec::new();
+        write_hand_evs(&mut output, &rules, &[Card(10), Card(6)], Card(10),
+                       &[], AnalysisFormat::Text).unwrap();
+        let text = String::from_utf8(output).unwrap();
+
+        assert!(text.starts_with("10-6 vs 10: 1 deck, S17, \
+                                  peek under A and 10\n"));
+        assert!(text.contains("\nstand      -0.542952\n"));
+        assert!(text.contains("\nhit        -0.506929  best\n"));
+        assert!(!text.contains("split"));
+
+        let mut output = Vec::new();
+        write_hand_evs(&mut output, &rules, &[Card(8), Card(8)], Card(1),
+                       &[], AnalysisFormat::Json).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+        assert_eq!(json["best"], "split");
+        assert_eq!(json["surrender"], serde_json::Value::Null);
+        assert!((json["split"].as_f64().unwrap() + 0.321019309014045).abs()
+                < 1e-12);
+
+        let mut output = Vec::new();
+        assert!(write_hand_evs(&mut output, &rules, &[Card(10), Card(6)],
+                               Card(10), &[Card(6); 4],
+                               AnalysisFormat::Text).is_err());
+    }
+
+    #[test]
+    fn it_removes_cards_from_the_shoe() {
+        let rules = GameRules { decks: 1, ..GameRules::default() };
+        let cards = cards_left(&rules, &[Card(10), Card(1), Card(1)]).unwrap();
+        assert_eq!(cards, [2, 4, 4, 4, 4, 4, 4, 4, 4, 15]);
+        assert!(cards_left(&rules, &[Card(5); 5]).is_err());
+    }
+}
@@ -0,0 +1,554 @@
+use std::collections::HashMap;
+use serde::Serialize;
+
+use crate::card::Card;
+use crate::hand::Hand;
+use crate::hand_logic::may_double;
+use crate::game_rules::GameRules;
+use crate::game_rules::SurrenderPolicy::{NoSurrender, EarlySurrenderTens};
+use crate::strategy_file::StrategyFile;
+use crate::dealer_probabilities::{DealerProbabilities, DealerFinals,
+                                  CardCounts, shoe_counts, natural_holecard,
+                                  natural_probability, value_of};
+
+/// A player hand being hit: the cards left, the sum of its cards with aces
+/// as 1, whether it holds an ace, its number of cards, and whether it was
+/// split.
+type HitState = (CardCounts, u8, bool, u8, bool);
+
+/// The expected values of the actions of a player hand against an upcard,
+/// per unit of the original bet; the actions the rules do not allow are
+/// `None`.
+///
+/// When the dealer peeks under the upcard, they are the expected values
+/// once the dealer has no natural; otherwise they count the dealer's
+/// naturals. The surrender's is then the loss of half the bet, or what it
+/// is worth once the dealer has no natural when surrendering early.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+pub struct ActionEvs {
+    pub stand:          f64,
+    pub hit:            Option<f64>,
+    pub double:         Option<f64>,
+    pub split:          Option<f64>,
+    pub surrender:      Option<f64>,
+}
+
+impl ActionEvs {
+    /// Returns the expected value of the best action.
+    pub fn best(&self) -> f64 {
+        [self.hit, self.double, self.split, self.surrender].iter()
+            .flatten()
+            .fold(self.stand, |best, &ev| best.max(ev))
+    }
+
+    /// Returns the expected value of the best action but splitting.
+    fn best_unsplit(&self) -> f64 {
+        ActionEvs { split: None, ..*self }.best()
+    }
+}
+
+/// A combinatorial analyzer of the game: the exact expected values of
+/// standing, hitting, doubling down, splitting and surrendering a player
+/// hand against an upcard, for a set of game rules and the cards of
+/// `rules.decks` decks.
+///
+/// Hands are drawn to from the cards left once the player's and the dealer's
+/// cards are removed, the dealer's holecard being anything but a natural.
+/// Hands are doubled down on their first cards only. Pairs are resplit up to
+/// `rules.max_splits` hands, but aces unless played; each split hand is
+/// played as if the others took no cards.
+pub struct Analyzer<'a> {
+    rules:  &'a GameRules,
+    shoe:   CardCounts,
+    dealer: DealerProbabilities,
+    /// The dealer's final totals by cards left and upcard
+    finals: HashMap<(CardCounts, u8), DealerFinals>,
+    /// The expected value of hitting by hand and upcard, playing on best
+    hits:   HashMap<(HitState, u8), f64>,
+}
+
+impl<'a> Analyzer<'a> {
+    pub fn new(rules: &'a GameRules) -> Analyzer<'a> {
+        Analyzer {
+            rules,
+            shoe: shoe_counts(rules.decks),
+            dealer: DealerProbabilities::new(&rules.soft17),
+            finals: HashMap::new(),
+            hits: HashMap::new(),
+        }
+    }
+
+    /// Draws the hands from `cards` rather than the rules' full shoe.
+    pub fn with_cards(mut self, cards: CardCounts) -> Self {
+        self.shoe = cards;
+        self
+    }
+
+    /// Returns the expected values of the actions of a player hand that is
+    /// not a natural against an upcard.
+    pub fn hand_evs(&mut self, cards: &[Card], upcard: Card) -> ActionEvs {
+        let mut shoe = self.shoe;
+        for card in cards.iter().chain(Some(&upcard)) {
+            shoe[card.0 as usize - 1] -= 1;
+        }
+
+        let hand = Hand::from(cards);
+        let (sum, ace) = sum_of(cards);
+        let up = upcard.0;
+        let count = cards.len() as u8;
+
+        let mut stand = self.stand(&shoe, up, value_of(sum, ace));
+        let mut hit = if hand.value() < 21 {
+            Some(self.hit(&(shoe, sum, ace, count, false), up))
+        } else {
+            None
+        };
+        let mut double = if may_double(self.rules, &hand) {
+            Some(2.0 * self.double(&(shoe, sum, ace, count, false), up))
+        } else {
+            None
+        };
+        let mut split = if count == 2 && cards[0] == cards[1]
+                           && self.rules.max_splits > 1 {
+            Some(self.split(&shoe, cards[0].0, up))
+        } else {
+            None
+        };
+
+        let natural = natural_probability(&shoe, upcard);
+        let peeks = self.rules.dealer_peeks(upcard);
+        if !peeks {
+            let lost = if self.rules.original_bets_only { 1.0 } else { 2.0 };
+            let with_naturals = |ev: f64, lost: f64| {
+                (1.0 - natural) * ev - natural * lost
+            };
+            stand = with_naturals(stand, 1.0);
+            hit = hit.map(|ev| with_naturals(ev, 1.0));
+            double = double.map(|ev| with_naturals(ev, lost));
+            split = split.map(|ev| with_naturals(ev, lost));
+        }
+
+        let early = self.rules.surrenders_early(upcard);
+        let surrender = if self.rules.surrender == NoSurrender
+                           || (self.rules.surrender == EarlySurrenderTens
+                               && !early)
+                           || (count > 2 && !self.rules.surrender_any_cards) {
+            None
+        } else if early && peeks {
+            Some((natural - 0.5) / (1.0 - natural))
+        } else {
+            Some(-0.5)
+        };
+
+        ActionEvs { stand, hit, double, split, surrender }
+    }
+
+    /// Returns the optimal total-dependent strategy for the rules, as a
+    /// strategy file of tables only.
+    ///
+    /// Each decision of a total is the best by the expected values of all
+    /// the two-card hands of that total, weighted by their probability; a
+    /// pair is split when splitting is best for it. Soft hands are never
+    /// surrendered, as in `BasicStrategy`.
+    pub fn strategy(&mut self) -> StrategyFile {
+        let mut file = StrategyFile {
+            deviations: Vec::new(),
+            composition: Vec::new(),
+            ..StrategyFile::default()
+        };
+
+        for up in 1..=10u8 {
+            let mut shoe = self.shoe;
+            shoe[up as usize - 1] -= 1;
+
+            let mut hard = [Totals::default(); 17];
+            let mut soft = [Totals::default(); 10];
+            for first in 1..=10u8 {
+                for second in first..=10u8 {
+                    if first == 1 && second == 10 {
+                        continue;
+                    }
+                    let (i, j) = (first as usize - 1, second as usize - 1);
+                    let weight = if first == second {
+                        shoe[i] as f64 * (shoe[i] as f64 - 1.0)
+                    } else {
+                        2.0 * shoe[i] as f64 * shoe[j] as f64
+                    };
+                    if weight == 0.0 {
+                        continue;
+                    }
+
+                    let evs = self.hand_evs(&[Card(first), Card(second)],
+                                            Card(up));
+                    if first == 1 {
+                        let row = if second == 1 { 9 } else { 10 - second };
+                        soft[row as usize].add(weight, &evs);
+                    } else {
+                        hard[20 - (first + second) as usize].add(weight, &evs);
+                    }
+
+                    if first == second {
+                        let row = 10 - first as usize;
+                        let split = evs.split
+                            .filter(|&ev| ev > evs.best_unsplit());
+                        file.pairs[row][up as usize] =
+                            if split.is_some() { b'V' } else { b' ' };
+                    }
+                }
+            }
+
+            for (row, totals) in hard.iter().enumerate() {
+                file.hard[row][up as usize] = totals.decision(true);
+            }
+            for (row, totals) in soft.iter().enumerate() {
+                file.soft[row][up as usize] = if row == 0 {
+                    b'='
+                } else {
+                    totals.decision(false)
+                };
+            }
+        }
+
+        file
+    }
+
+    /// Returns the expected value of standing on `value` against the dealer
+    /// with no natural.
+    fn stand(&mut self, shoe: &CardCounts, up: u8, value: u8) -> f64 {
+        let push = self.rules.dealer_push.map(|push| push.total);
+        let finals = self.dealer_finals(shoe, up);
+
+        finals.iter().enumerate().map(|(i, p)| {
+            let total = 17 + i as u8;
+            p * if total > 21 {
+                if push == Some(total) { 0.0 } else { 1.0 }
+            } else if value > total {
+                1.0
+            } else if value < total {
+                -1.0
+            } else {
+                0.0
+            }
+        }).sum()
+    }
+
+    /// Returns the expected value of drawing a card to a hand then playing it
+    /// on best, standing or hitting again.
+    fn hit(&mut self, state: &HitState, up: u8) -> f64 {
+        if let Some(&ev) = self.hits.get(&(*state, up)) {
+            return ev;
+        }
+
+        let ev = self.draw(state, up, |this, state| {
+            let value = value_of(state.1, state.2);
+            let stand = this.stand(&state.0, up, value);
+            if value < 21 {
+                stand.max(this.hit(state, up))
+            } else {
+                stand
+            }
+        });
+
+        self.hits.insert((*state, up), ev);
+        ev
+    }
+
+    /// Returns the expected value of drawing a single card to a hand, per
+    /// unit of its bet before doubling down.
+    fn double(&mut self, state: &HitState, up: u8) -> f64 {
+        self.draw(state, up, |this, state| {
+            this.stand(&state.0, up, value_of(state.1, state.2))
+        })
+    }
+
+    /// Returns the expected value of drawing a card to a hand, then of `then`
+    /// for the new hand; busting loses and Charlies win.
+    fn draw<F>(&mut self, state: &HitState, up: u8, then: F) -> f64
+        where F: Fn(&mut Self, &HitState) -> f64 {
+        let (shoe, sum, ace, count, split) = *state;
+        let charlie = self.rules.charlie
+            .filter(|charlie| charlie.split_hands || !split);
+
+        next_card_probabilities(&shoe, up).iter().enumerate()
+            .filter(|(_, &p)| p > 0.0)
+            .map(|(i, p)| {
+                let mut shoe = shoe;
+                shoe[i] -= 1;
+                let next = (shoe, sum + i as u8 + 1, ace || i == 0, count + 1,
+                            split);
+                p * if next.1 > 21 {
+                    -1.0
+                } else if charlie.is_some_and(|c| next.3 >= c.cards) {
+                    charlie.unwrap().pays
+                } else {
+                    then(self, &next)
+                }
+            })
+            .sum()
+    }
+
+    /// Returns the expected value of splitting a pair of `card`, from the
+    /// cards left but the pair's.
+    fn split(&mut self, shoe: &CardCounts, card: u8, up: u8) -> f64 {
+        let state = (*shoe, card, card == 1, 1, true);
+        let pair_drawn = next_card_probabilities(shoe, up)[card as usize - 1];
+        let unpaired = self.draw(&state, up, |this, state| {
+            if state.1 == 2 * card {
+                0.0
+            } else {
+                this.split_hand(state, card, up)
+            }
+        });
+        let paired = self.draw(&state, up, |this, state| {
+            if state.1 == 2 * card {
+                this.split_hand(state, card, up)
+            } else {
+                0.0
+            }
+        });
+
+        let max_hands = if card != 1 || self.rules.play_ace_pairs {
+            self.rules.max_splits
+        } else {
+            2
+        };
+        let mut resplits = Resplits {
+            max_hands,
+            pair_drawn,
+            unpaired,
+            paired,
+            evs: HashMap::new(),
+        };
+        resplits.ev(2, 2)
+    }
+
+    /// Returns the expected value of playing a hand split from a pair of
+    /// `card` once dealt its second card.
+    fn split_hand(&mut self, state: &HitState, card: u8, up: u8) -> f64 {
+        let (shoe, sum, ace, _, _) = *state;
+        let value = value_of(sum, ace);
+        let stand = self.stand(&shoe, up, value);
+        if card == 1 && !self.rules.play_ace_pairs || value == 21 {
+            return stand;
+        }
+
+        let mut hand = Hand::from(&[Card(card), Card(card)][..]);
+        let mut split = hand.split();
+        split.add(Card(sum - card));
+        let mut best = stand.max(self.hit(state, up));
+        if may_double(self.rules, &split) {
+            best = best.max(2.0 * self.double(state, up));
+        }
+        best
+    }
+
+    /// Returns the probabilities of the dealer's final totals from an upcard
+    /// and a holecard that does not make a natural.
+    fn dealer_finals(&mut self, shoe: &CardCounts, up: u8) -> DealerFinals {
+        if let Some(finals) = self.finals.get(&(*shoe, up)) {
+            return *finals;
+        }
+
+        let finals = self.dealer.finals(shoe, Card(up));
+        self.finals.insert((*shoe, up), finals);
+        finals
+    }
+}
+
+/// The expected values of the hands of a total, summed by weight.
+#[derive(Default, Clone, Copy)]
+struct Totals {
+    stand:      f64,
+    hit:        f64,
+    double:     Option<f64>,
+    surrender:  Option<f64>,
+}
+
+impl Totals {
+    fn add(&mut self, weight: f64, evs: &ActionEvs) {
+        self.stand += weight * evs.stand;
+        self.hit += weight * evs.hit.unwrap_or(evs.stand);
+        if let Some(ev) = evs.double {
+            self.double = Some(self.double.unwrap_or(0.0) + weight * ev);
+        }
+        if let Some(ev) = evs.surrender {
+            self.surrender = Some(self.surrender.unwrap_or(0.0) + weight * ev);
+        }
+    }
+
+    /// Returns the decision code of the total, surrendering only when
+    /// `may_surrender`.
+    fn decision(&self, may_surrender: bool) -> u8 {
+        let hits = self.hit > self.stand;
+        let play = self.stand.max(self.hit);
+        let double = self.double.unwrap_or(f64::NEG_INFINITY);
+        let surrender = self.surrender.filter(|_| may_surrender)
+            .unwrap_or(f64::NEG_INFINITY);
+
+        if surrender > play && surrender > double {
+            if hits { b'S' } else { b's' }
+        } else if double > play {
+            if hits { b'D' } else { b'd' }
+        } else if hits {
+            b'+'
+        } else {
+            b'='
+        }
+    }
+}
+
+/// The expected values of split hands, resplitting the pairs they are dealt.
+struct Resplits {
+    max_hands:  u32,
+    /// The probability of a split hand being dealt a pair
+    pair_drawn: f64,
+    /// The expected value of a split hand not dealt a pair, times its
+    /// probability
+    unpaired:   f64,
+    /// The expected value of a split hand dealt a pair and not resplit,
+    /// times its probability
+    paired:     f64,
+    /// The expected values by hands to deal to and hands split
+    evs:        HashMap<(u32, u32), f64>,
+}
+
+impl Resplits {
+    /// Returns the expected value of `pending` split hands still to be dealt
+    /// their second card, out of `hands` split hands, resplitting pairs when
+    /// better.
+    fn ev(&mut self, pending: u32, hands: u32) -> f64 {
+        if pending == 0 {
+            return 0.0;
+        }
+        if let Some(&ev) = self.evs.get(&(pending, hands)) {
+            return ev;
+        }
+
+        let others = self.ev(pending - 1, hands);
+        let mut paired = self.paired + self.pair_drawn * others;
+        if hands < self.max_hands && self.pair_drawn > 0.0 {
+            let resplit = self.pair_drawn * self.ev(pending + 1, hands + 1);
+            paired = paired.max(resplit);
+        }
+        let ev = self.unpaired + (1.0 - self.pair_drawn) * others + paired;
+        self.evs.insert((pending, hands), ev);
+        ev
+    }
+}
+
+/// Returns the probability of drawing each card, ace first, once the dealer
+/// has been dealt a holecard that does not make a natural.
+fn next_card_probabilities(shoe: &CardCounts, up: u8) -> [f64; 10] {
+    let left: u16 = shoe.iter().sum();
+    let mut probs = [0.0; 10];
+
+    match natural_holecard(Card(up)) {
+        Some(natural) => {
+            let holes = (left - shoe[natural]) as f64;
+            for (i, prob) in probs.iter_mut().enumerate() {
+                let n = shoe[i] as f64;
+                let drawn = if i == natural { n * holes } else {
+                    n * (holes - 1.0)
+                };
+                *prob = drawn / (holes * (left as f64 - 1.0));
+            }
+        }
+        None => {
+            for (prob, &n) in probs.iter_mut().zip(shoe) {
+                *prob = n as f64 / left as f64;
+            }
+        }
+    }
+
+    probs
+}
+
+/// Returns the sum of cards with aces as 1, and whether there is an ace.
+fn sum_of(cards: &[Card]) -> (u8, bool) {
+    (cards.iter().map(|card| card.0).sum(), cards.contains(&Card(1)))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::analyzer::Analyzer;
+    use crate::game_rules::{GameRules, GameType};
+    use crate::game_rules::SurrenderPolicy::{LateSurrender, EarlySurrender};
+    use crate::card::Card;
+    use crate::test_utils::assert_f64_eq;
+
+    fn cards(values: &[u8]) -> Vec<Card> {
+        values.iter().map(|&value| Card(value)).collect()
+    }
+
+    #[test]
+    fn it_computes_the_hands_evs() {
+        let rules = GameRules { decks: 1, ..GameRules::default() };
+        let mut analyzer = Analyzer::new(&rules);
+
+        let evs = analyzer.hand_evs(&cards(&[10, 6]), Card(10));
+        assert_f64_eq(evs.stand, -0.542951853824503, 1e-12);
+        assert_f64_eq(evs.hit.unwrap(), -0.506929242578811, 1e-12);
+        assert_f64_eq(evs.double.unwrap(), -1.013858485157622, 1e-12);
+        assert_eq!(evs.split, None);
+        assert_eq!(evs.surrender, None);
+
+        let evs = analyzer.hand_evs(&cards(&[8, 8]), Card(1));
+        assert_f64_eq(evs.split.unwrap(), -0.321019309014045, 1e-12);
+        assert_eq!(evs.best(), evs.split.unwrap());
+        let no_split = GameRules { decks: 1, max_splits: 1,
+                                   ..GameRules::default() };
+        let evs = Analyzer::new(&no_split).hand_evs(&cards(&[8, 8]), Card(1));
+        assert_eq!(evs.split, None);
+
+        let two_hands = GameRules { decks: 1, max_splits: 2,
+                                    ..GameRules::default() };
+        let evs = Analyzer::new(&two_hands).hand_evs(&cards(&[8, 8]), Card(1));
+        assert_f64_eq(evs.split.unwrap(), -0.333718134972456, 1e-12);
+
+        let evs = analyzer.hand_evs(&cards(&[10, 5, 6]), Card(6));
+        assert_eq!(evs.hit, None);
+        assert_eq!(evs.double, None);
+    }
+
+    #[test]
+    fn it_follows_the_rules() {
+        let mut rules = GameRules {
+            surrender: LateSurrender,
+            ..GameRules::default()
+        };
+        let evs = Analyzer::new(&rules).hand_evs(&cards(&[10, 6]), Card(10));
+        assert_eq!(evs.surrender, Some(-0.5));
+        assert_eq!(evs.best(), -0.5);
+
+        // An early surrender is worth more once the dealer has no natural
+        rules.surrender = EarlySurrender;
+        let evs = Analyzer::new(&rules).hand_evs(&cards(&[10, 6]), Card(1));
+        let natural = 95.0 / 309.0;
+        assert_f64_eq(evs.surrender.unwrap(),
+                      (natural - 0.5) / (1.0 - natural), 1e-12);
+
+        // Doubling 11 against a 10 risks losing twice to a natural
+        rules.game_type = GameType::Enhc;
+        let evs = Analyzer::new(&rules).hand_evs(&cards(&[6, 5]), Card(10));
+        assert!(evs.hit.unwrap() > evs.double.unwrap());
+        rules.original_bets_only = true;
+        let evs = Analyzer::new(&rules).hand_evs(&cards(&[6, 5]), Card(10));
+        assert!(evs.hit.unwrap() < evs.double.unwrap());
+    }
+
+    #[test]
+    fn it_computes_the_single_deck_strategy() {
+        let rules = GameRules { decks: 1, ..GameRules::default() };
+        let file = Analyzer::new(&rules).strategy();
+
+        assert_eq!(&file.hard[9], b" DDDDDDDDDD");  // 11
+        assert_eq!(&file.hard[10], b" +DDDDDDDD+");  // 10
+        assert_eq!(&file.hard[4], b" +=====++++");  // 16
+        assert_eq!(&file.hard[12], b" ++++DD++++");  // 8
+        assert_eq!(&file.soft[2], b" =====d====");  // A8
+        assert_eq!(&file.pairs[1], b"  VVVVV VV ");  // 9/9
+        assert_eq!(&file.pairs[2], b" VVVVVVVVVV");  // 8/8
+        assert!(file.deviations.is_empty());
+    }
+}
@@ -2,9 +2,12 @@ use crate::strategy::{Strategy, GameContext, Decision};
 use crate::hand::Hand;
 use crate::card::Card;
 use crate::strategy::Decision::*;
-use crate::game_rules::GameType::Ahc;
 use crate::game_rules::Soft17::{H17, S17};
-use crate::deviation::{Deviation, DeviationTable};
+use crate::deviation::{Deviation, DeviationTable, DevOverride};
+use crate::holecarding::{HolecardingTables, InformedTables, ten_probability};
+use crate::composition::{CompositionRule, CompositionStrategy};
+use crate::strategy_file::StrategyFile;
+use std::sync::OnceLock;
 
 static HARD_TABLE: [&[u8; 11]; 17] = [
     // A23456789J
@@ -59,6 +62,20 @@ static PAIRS_TABLE: [&[u8; 11]; 10] = [
     // A23456789J
 ];
 
+/// Hard hands one card away from a Charlie: the next card either busts the hand
+/// or wins, which makes hitting stiff hands more valuable than usual. Hard
+/// totals under 12 are played from the hard table, above 17 always stand.
+static CHARLIE_TABLE: [&[u8; 11]; 6] = [
+    // A23456789J
+    b" +=======++", // 17
+    b" +++===++++", // 16
+    b" ++++++++++", // 15
+    b" ++++++++++", // 14
+    b" ++++++++++", // 13
+    b" ++++++++++", // 12
+    // A23456789J
+];
+
 static DEFAULT_HARD_DEVIATIONS: [[u8; 40]; 17] = [
     //  A | 2 | 3 | 4 | 5 | 6 | 7 | 8 | 9 | 10 |
     *b"                                        ", // 20
@@ -84,25 +101,86 @@ static DEFAULT_HARD_DEVIATIONS: [[u8; 40]; 17] = [
 
 pub struct BasicStrategy {
     hilo: bool,
+    hard_table: Box<[[u8; 11]; 17]>,
+    soft_table: Box<[[u8; 11]; 10]>,
+    pair_table: Box<[[u8; 11]; 10]>,
     deviations: bool,
     dev_hard_table: Box<[[u8; 40]; 17]>,
     dev_soft_table: Box<[[u8; 40]; 10]>,
     dev_pair_table: Box<[[u8; 40]; 10]>,
+    insurance_amount: f64,
+    /// The decisions against known holecards, computed on the first one for
+    /// the game rules, which are the same for all the strategy's decisions
+    holecarding: OnceLock<HolecardingTables>,
+    /// The decisions against dealer hands known from their upcard only, for
+    /// players knowing the next card off the shoe
+    untold: OnceLock<InformedTables>,
+    /// The decisions against dealer hands known from a tell, computed on the
+    /// first one for its accuracy, which is the same for all the tells
+    told: OnceLock<InformedTables>,
+    /// The composition-dependent exceptions to the tables
+    composition: CompositionStrategy,
 }
 
 impl BasicStrategy {
     pub fn new(hilo: bool) -> BasicStrategy {
         BasicStrategy {
             hilo,
+            hard_table: Box::new(HARD_TABLE.map(|row| *row)),
+            soft_table: Box::new(SOFT_TABLE.map(|row| *row)),
+            pair_table: Box::new(PAIRS_TABLE.map(|row| *row)),
             deviations: false,
             dev_hard_table: Box::new([[b' '; 40]; 17]),
             dev_soft_table: Box::new([[b' '; 40]; 10]),
             dev_pair_table: Box::new([[b' '; 40]; 10]),
+            insurance_amount: 0.5,
+            holecarding: OnceLock::new(),
+            untold: OnceLock::new(),
+            told: OnceLock::new(),
+            composition: CompositionStrategy::default(),
+        }
+    }
+
+    /// Sets how much of the bet to insure for when taking insurance, 0.5 by
+    /// default (full insurance).
+    pub fn set_insurance_amount(&mut self, amount: f64) {
+        assert!(amount > 0.0 && amount <= 0.5);
+        self.insurance_amount = amount;
+    }
+
+    /// Plays the strategy of a strategy file: its tables replace the built-in
+    /// ones, and its deviations and composition rules are added.
+    pub fn load(&mut self, file: StrategyFile) {
+        *self.hard_table = file.hard;
+        *self.soft_table = file.soft;
+        *self.pair_table = file.pairs;
+        for deviation in file.deviations {
+            self.add_deviation(deviation);
+        }
+        self.set_composition(file.composition);
+    }
+
+    /// Returns the strategy played, as written in a strategy file.
+    pub fn export(&self) -> StrategyFile {
+        StrategyFile {
+            hard: *self.hard_table,
+            soft: *self.soft_table,
+            pairs: *self.pair_table,
+            deviations: self.deviations(),
+            composition: self.composition.rules(),
+        }
+    }
+
+    /// Plays the hands of the composition rules by their cards rather than
+    /// their total, the later rules overriding the earlier ones.
+    pub fn set_composition(&mut self, rules: Vec<CompositionRule>) {
+        for rule in rules {
+            self.composition.add(rule);
         }
     }
 
     pub fn set_default_deviations(&mut self) {
-        self.dev_hard_table = Box::new(DEFAULT_HARD_DEVIATIONS);
+        *self.dev_hard_table = DEFAULT_HARD_DEVIATIONS;
         self.deviations = true;
     }
 
@@ -130,6 +208,38 @@ impl BasicStrategy {
         self.deviations = true;
     }
 
+    /// Returns the playing deviations in use, by table, row, and upcard.
+    pub fn deviations(&self) -> Vec<Deviation> {
+        let tables: [(DeviationTable, &[[u8; 40]]); 3] = [
+            (DeviationTable::HardTable, &self.dev_hard_table[..]),
+            (DeviationTable::SoftTable, &self.dev_soft_table[..]),
+            (DeviationTable::PairTable, &self.dev_pair_table[..]),
+        ];
+        let mut deviations = Vec::new();
+
+        for (table, rows) in tables {
+            for (row, slots) in rows.iter().enumerate() {
+                for (dealer, slot) in slots.chunks(4).enumerate() {
+                    if slot[0] == b' ' {
+                        continue;
+                    }
+
+                    let action = std::str::from_utf8(slot).ok()
+                        .and_then(|s| s.parse::<DevOverride>().ok())
+                        .expect("Invalid deviation in the tables");
+                    deviations.push(Deviation {
+                        table: table.clone(),
+                        row: row as u8,
+                        dealer: dealer as u8 + 1,
+                        action,
+                    });
+                }
+            }
+        }
+
+        deviations
+    }
+
     fn basic_strategy(&self,
                       game: &GameContext,
                       dealer: Card,
@@ -138,9 +248,14 @@ impl BasicStrategy {
             return b'=';
         }
 
+        if let Some(ch) = self.composition
+            .cards_decision(dealer, me, game.may_split) {
+            return ch;
+        }
+
         if game.may_split && me.count() == 2 && me[0] == me[1] {
-            let ch = PAIRS_TABLE[10 - me[0].0 as usize][dealer.0 as usize];
-            let ahc = game.rules.game_type == Ahc;
+            let ch = self.pair_table[10 - me[0].0 as usize][dealer.0 as usize];
+            let ahc = game.rules.extra_bets_protected(dealer);
 
             if ch == b'V'
                || (ch == b'*' && game.rules.das)
@@ -150,20 +265,70 @@ impl BasicStrategy {
             }
         }
 
+        if let Some(ch) = self.composition.total_decision(dealer, me) {
+            return ch;
+        }
+
         if me.is_soft() {
             let soft_sum = me.iter().map(|c| c.0 as usize).sum::<usize>() - 1;
-            SOFT_TABLE[10 - soft_sum][dealer.0 as usize]
+            self.soft_table[10 - soft_sum][dealer.0 as usize]
         } else {
-            HARD_TABLE[20 - me.value() as usize][dealer.0 as usize]
+            self.hard_table[20 - me.value() as usize][dealer.0 as usize]
         }
     }
 
-    fn holecarding_strategy(&self,
-                            _game: &GameContext,
-                            _d1: Card,
-                            _d2: Card,
-                            _me: &Hand) -> u8 {
-        unimplemented!()
+    /// Returns the decision for a hand one card away from a Charlie, if the
+    /// rules have one, or `None` for the regular strategy to apply.
+    fn charlie_strategy(&self,
+                        game: &GameContext,
+                        dealer: Card,
+                        me: &Hand) -> Option<u8> {
+        let charlie = game.rules.charlie?;
+
+        if me.count() + 1 != charlie.cards as usize
+           || (me.is_split() && !charlie.split_hands) {
+            return None;
+        }
+
+        if me.is_soft() {
+            // A soft hand cannot bust by drawing one card, even a soft 21:
+            // the Charlie is certain
+            Some(b'+')
+        } else if me.value() > 17 {
+            Some(b'=')
+        } else if me.value() < 12 {
+            None
+        } else {
+            Some(CHARLIE_TABLE[17 - me.value() as usize][dealer.0 as usize])
+        }
+    }
+
+    /// Returns the decision against a dealer hand known beyond its upcard,
+    /// from its holecard, a tell, or the next card off the shoe, if any.
+    fn informed_strategy(&self,
+                         game: &GameContext,
+                         dealer: Card,
+                         me: &Hand) -> Option<u8> {
+        if let Some(holecard) = game.holecard {
+            Some(self.holecarding
+                .get_or_init(|| HolecardingTables::new(game.rules))
+                .decision(game.rules, dealer, holecard, me, game.may_split,
+                          game.next_card))
+        } else if let Some(tell) = game.hole_tell {
+            Some(self.told
+                .get_or_init(|| {
+                    InformedTables::new(game.rules, Some(tell.accuracy))
+                })
+                .decision(game.rules, dealer, Some(tell), me, game.may_split,
+                          game.next_card))
+        } else if game.next_card.is_some() {
+            Some(self.untold
+                .get_or_init(|| InformedTables::new(game.rules, None))
+                .decision(game.rules, dealer, None, me, game.may_split,
+                          game.next_card))
+        } else {
+            None
+        }
     }
 
     fn apply_deviations(&self,
@@ -190,18 +355,15 @@ impl BasicStrategy {
             }
         }
 
-        let dev;
-
-        if me.is_soft() {
+        let dev = if me.is_soft() {
             let soft_sum = me.iter().map(|c| c.0 as usize).sum::<usize>() - 1;
-            dev = &self.dev_soft_table[10 - soft_sum][d_index..d_index + 4];
+            &self.dev_soft_table[10 - soft_sum][d_index..d_index + 4]
         } else {
-            dev = &self.dev_hard_table[20 - val][d_index..d_index + 4];
-        }
+            &self.dev_hard_table[20 - val][d_index..d_index + 4]
+        };
 
         if let Some(action) = self.try_deviate(dev, tc) {
             *decision = action;
-            return;
         }
     }
 
@@ -233,20 +395,29 @@ impl Strategy for BasicStrategy {
                    game: &GameContext,
                    dealer: Card,
                    me: &Hand) -> Decision {
-        let mut decision = if let Some(holecard) = game.holecard {
-            self.holecarding_strategy(game, dealer, holecard, me)
+        let charlie = self.charlie_strategy(game, dealer, me);
+
+        let informed = if charlie.is_none() {
+            self.informed_strategy(game, dealer, me)
+        } else {
+            None
+        };
+        let mut decision = if let Some(decision) = charlie.or(informed) {
+            decision
         } else {
             self.basic_strategy(game, dealer, me)
         };
 
-        if self.deviations {
+        // The count does not tell more than what is known of the dealer's
+        // hand
+        if self.deviations && charlie.is_none() && informed.is_none() {
             self.apply_deviations(&mut decision, game, dealer, me);
         }
 
         decision = match decision {
-            b'?' if game.rules.game_type == Ahc => b'D',
+            b'?' if game.rules.extra_bets_protected(dealer) => b'D',
             b'?' => b'+',
-            b'&' if game.rules.game_type == Ahc
+            b'&' if game.rules.extra_bets_protected(dealer)
                    && game.rules.soft17 == H17 => b'D',
             b'&' => b'+',
             b'h' if game.rules.soft17 == H17 => b'd',
@@ -260,6 +431,11 @@ impl Strategy for BasicStrategy {
             _ => decision,
         };
 
+        // A doubled-down hand can only be re-doubled or stood
+        if me.is_doubled() && decision != b'D' && decision != b'd' {
+            return Stand;
+        }
+
         match decision {
             b'+' | b'S' | b'E' | b'U' => Hit,
             b'=' | b's' | b'e' | b'u' => Stand,
@@ -278,11 +454,16 @@ impl Strategy for BasicStrategy {
             return false;
         }
 
-        let decision = if let Some(holecard) = game.holecard {
-            self.holecarding_strategy(game, dealer, holecard, me)
-        } else {
-            self.basic_strategy(game, dealer, me)
-        };
+        if me.is_doubled() {
+            // Double-down rescue: standing on a stiff hand against a strong
+            // upcard loses more than half the doubled stake. An approximation
+            // of the rescue's expected value, the tables have none.
+            return me.value() <= 16
+                && (dealer.0 >= 8 || dealer == Card(1));
+        }
+
+        let decision = self.informed_strategy(game, dealer, me)
+            .unwrap_or_else(|| self.basic_strategy(game, dealer, me));
 
         match decision {
             b'S' | b's' => true,
@@ -292,9 +473,16 @@ impl Strategy for BasicStrategy {
         }
     }
 
+    fn insurance_amount(&self, _game: &GameContext, _me: &Hand) -> f64 {
+        self.insurance_amount
+    }
+
     fn take_insurance(&self, game: &GameContext, _me: &Hand) -> bool {
         if let Some(holecard) = game.holecard {
             holecard == Card(10)
+        } else if let Some(tell) = game.hole_tell {
+            // Insurance pays 2:1 on a ten
+            ten_probability(tell) > 1.0 / 3.0
         } else if !self.hilo {
             false
         } else {
@@ -308,11 +496,13 @@ mod tests {
     use crate::strategy::{Decision, GameContext, Strategy};
     use crate::card::Card;
     use crate::basic_strategy::BasicStrategy;
-    use crate::game_rules::GameRules;
+    use crate::game_rules::{GameRules, PeekPolicy};
     use crate::strategy::Decision::*;
     use crate::hand::Hand;
     use crate::test_utils::make_rules;
     use crate::deviation::Deviation;
+    use crate::composition::CompositionRule;
+    use crate::strategy_file::StrategyFile;
     use std::str::FromStr;
 
     #[test]
@@ -493,6 +683,53 @@ mod tests {
         test_decision(Hit,    &[2, 2],   8,  AHC|S17);
     }
 
+    #[test]
+    fn it_hits_towards_a_charlie() {
+        test_decision(Stand,  &[2, 2, 2, 10],    4,  AHC|S17);
+        test_decision(Hit,    &[2, 2, 2, 10],    10, AHC|S17|CHARLIE5);
+        test_decision(Stand,  &[2, 2, 2, 10],    5,  AHC|S17|CHARLIE5);
+        test_decision(Hit,    &[2, 2, 2, 10],    3,  AHC|S17|CHARLIE5);
+        test_decision(Hit,    &[2, 3, 2, 10],    10, AHC|S17|CHARLIE5);
+        test_decision(Stand,  &[2, 3, 3, 10],    10, AHC|S17|CHARLIE5);
+        test_decision(Stand,  &[2, 3, 3, 10],    10, AHC|S17);
+        test_decision(Hit,    &[1, 2, 2, 4],     7,  AHC|S17|CHARLIE5);
+        test_decision(Stand,  &[1, 2, 2, 4],     7,  AHC|S17);
+        test_decision(Hit,    &[1, 2, 2, 6],     10, AHC|S17|CHARLIE5);
+        test_decision(Stand,  &[1, 2, 2, 6],     10, AHC|S17);
+        test_decision(Stand,  &[2, 3, 6, 10],    10, AHC|S17|CHARLIE5);
+
+        // The Charlie takes precedence over the 16 vs 10 deviation
+        let mut strat = BasicStrategy::new(true);
+        strat.set_default_deviations();
+        let rules = make_rules(AHC|S17|CHARLIE5);
+        let mut game = make_context(&rules, 0);
+        game.true_count = 2.0;
+        assert_eq!(strat.player_turn(&game, Card(10),
+                                     &make_player_hand(&[10, 6])),
+                   Decision::Stand);
+        assert_eq!(strat.player_turn(&game, Card(10),
+                                     &make_player_hand(&[2, 2, 2, 10])),
+                   Decision::Hit);
+    }
+
+    #[test]
+    fn it_plays_redoubles() {
+        let rules = make_rules(AHC|S17);
+        let game = make_context(&rules, 0);
+        let strategy = BasicStrategy::new(false);
+
+        let mut hand = make_player_hand(&[2, 3]);
+        hand.double_down();
+        hand.add(Card(4));
+        assert_eq!(strategy.player_turn(&game, Card(6), &hand), Double);
+        assert_eq!(strategy.player_turn(&game, Card(10), &hand), Stand);
+
+        let mut hand = make_player_hand(&[2, 3]);
+        hand.double_down();
+        hand.add(Card(10));
+        assert_eq!(strategy.player_turn(&game, Card(6), &hand), Stand);
+    }
+
     #[test]
     fn it_plays_surrenders() {
         test_surrender(false, &[10, 7],  1,  AHC|S17|LSURR);
@@ -506,6 +743,43 @@ mod tests {
         test_surrender(true,  &[10, 5],  1,  AHC|H17|ESURR);
     }
 
+    #[test]
+    fn it_plays_double_down_rescues() {
+        let rules = make_rules(AHC|S17);
+        let game = make_context(&rules, 0);
+        let strategy = BasicStrategy::new(false);
+        let mut hand = make_player_hand(&[5, 6]);
+        hand.double_down();
+        hand.add(Card(5));
+
+        assert!(strategy.surrender(&game, Card(10), &hand, false));
+        assert!(strategy.surrender(&game, Card(1), &hand, false));
+        assert!(strategy.surrender(&game, Card(8), &hand, false));
+        assert!(!strategy.surrender(&game, Card(7), &hand, false));
+
+        let mut hand = make_player_hand(&[5, 6]);
+        hand.double_down();
+        hand.add(Card(7));
+        assert!(!strategy.surrender(&game, Card(10), &hand, false));
+    }
+
+    #[test]
+    fn it_plays_against_an_unpeeked_hole_card() {
+        let strategy = BasicStrategy::new(false);
+        let mut rules = make_rules(AHC|H17);
+        let hand = make_player_hand(&[5, 6]);
+
+        rules.peek = PeekPolicy::PeekAce;
+        let game = make_context(&rules, 0);
+        assert_eq!(strategy.player_turn(&game, Card(10), &hand), Hit);
+        assert_eq!(strategy.player_turn(&game, Card(1), &hand), Double);
+
+        rules.peek = PeekPolicy::NoPeek;
+        rules.original_bets_only = true;
+        let game = make_context(&rules, 0);
+        assert_eq!(strategy.player_turn(&game, Card(10), &hand), Double);
+    }
+
     #[test]
     fn it_plays_specific_deviations() {
         let rules = make_rules(AHC|S17);
@@ -550,6 +824,76 @@ mod tests {
                    Decision::Hit);
     }
 
+    #[test]
+    fn it_plays_by_composition() {
+        let mut rules = make_rules(AHC|S17);
+        rules.decks = 1;
+        let game = make_context(&rules, 0);
+
+        let mut strat = BasicStrategy::new(false);
+        strat.set_composition(
+            CompositionRule::table("single-deck", &rules.soft17).unwrap()
+        );
+
+        let decide = |strat: &BasicStrategy, hand: &[u8], dealer: u8| {
+            strat.player_turn(&game, Card(dealer), &make_player_hand(hand))
+        };
+        assert_eq!(decide(&strat, &[10, 6], 10), Decision::Hit);
+        assert_eq!(decide(&strat, &[8, 4, 4], 10), Decision::Stand);
+        assert_eq!(decide(&strat, &[10, 2], 4), Decision::Hit);
+        assert_eq!(decide(&strat, &[4, 8], 4), Decision::Stand);
+        assert_eq!(decide(&strat, &[9, 2], 1), Decision::Double);
+        assert_eq!(decide(&strat, &[6, 6], 2), Decision::Split);
+        assert_eq!(decide(&strat, &[4, 8], 3), Decision::Stand);
+        assert_eq!(decide(&strat, &[2, 10], 3), Decision::Hit);
+
+        strat.set_composition(vec![
+            CompositionRule::from_str("10-2vs4:=").unwrap(),
+        ]);
+        assert_eq!(decide(&strat, &[10, 2], 4), Decision::Stand);
+    }
+
+    #[test]
+    fn it_loads_and_exports_strategy_files() {
+        let rules = make_rules(AHC|S17);
+        let game = make_context(&rules, 0);
+
+        let mut file = StrategyFile::default();
+        file.hard[8] = *b" E+====++++";
+        file.pairs[0] = *b"          V";
+        file.deviations.push(Deviation::from_str("16vs10:>+0=").unwrap());
+        file.composition.push(CompositionRule::from_str("8-4-4vs10:=")
+            .unwrap());
+
+        let mut strat = BasicStrategy::new(true);
+        strat.load(file.clone());
+        assert_eq!(strat.player_turn(&game, Card(3),
+                                     &make_player_hand(&[9, 3])),
+                   Decision::Stand);
+        assert_eq!(strat.player_turn(&game, Card(10),
+                                     &make_player_hand(&[10, 10])),
+                   Decision::Split);
+        assert_eq!(strat.export(), file);
+    }
+
+    #[test]
+    fn it_lists_its_deviations() {
+        let mut strat = BasicStrategy::new(true);
+        assert!(strat.deviations().is_empty());
+
+        strat.add_deviation(Deviation::from_str("T/TvsA:>+5V").unwrap());
+        strat.add_deviation(Deviation::from_str("A5vs2:<-2D").unwrap());
+        strat.add_deviation(Deviation::from_str("16vs10:>+0=").unwrap());
+        strat.add_deviation(Deviation::from_str("12vs3:>+2=").unwrap());
+
+        let deviations: Vec<String> = strat.deviations().iter()
+            .map(Deviation::to_string)
+            .collect();
+        assert_eq!(deviations, [
+            "16vs10:>+0=", "12vs3:>+2=", "A5vs2:<-2D", "T/TvsA:>+5V",
+        ]);
+    }
+
     const AHC: u32          = 0;
     const ENHC: u32         = 1 << 0;
     const S17: u32          = 0;
@@ -559,14 +903,17 @@ mod tests {
     const NO_SPLIT: u32     = 1 << 4;
     const ESURR: u32        = 0;
     const LSURR: u32        = 1 << 5;
+    const CHARLIE5: u32     = crate::test_utils::options::CHARLIE5;
 
-    fn make_context(rules: &GameRules, opts: u32) -> GameContext {
+    fn make_context(rules: &GameRules, opts: u32) -> GameContext<'_> {
         GameContext {
             rules,
             may_split: opts & NO_SPLIT == 0,
             may_double: opts & NO_DOUBLE == 0,
             true_count: 0.0,
             holecard: None,
+            hole_tell: None,
+            next_card: None,
         }
     }
 
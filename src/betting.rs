@@ -1,5 +1,11 @@
+use serde::{Serialize, Deserialize};
+
 pub trait BettingStrategy {
+    /// The bet placed on each spot played.
     fn place_bet(&self, true_count: f32) -> f64;
+
+    /// The number of spots played this round, one by default.
+    fn spots(&self, _true_count: f32) -> u8 { 1 }
 }
 
 pub struct FixedBet(pub f64);
@@ -14,6 +20,9 @@ pub struct HiloBetting {
     neg_null_bet: Option<f64>,
     max_tc: Option<f32>,
     wongout_under: Option<f32>,
+    /// The number of spots played from a true count on, by increasing true
+    /// count
+    spots: Vec<(f32, u8)>,
 }
 
 impl HiloBetting {
@@ -28,8 +37,18 @@ impl HiloBetting {
             neg_null_bet,
             max_tc,
             wongout_under,
+            spots: Vec::new(),
         }
     }
+
+    /// Spreads to several spots at high counts: each `(true_count, spots)`
+    /// step plays `spots` spots from `true_count` on, one spot below the
+    /// lowest step.
+    pub fn with_spots(mut self, mut spots: Vec<(f32, u8)>) -> Self {
+        spots.sort_by(|a, b| a.0.total_cmp(&b.0));
+        self.spots = spots;
+        self
+    }
 }
 
 impl BettingStrategy for HiloBetting {
@@ -51,11 +70,50 @@ impl BettingStrategy for HiloBetting {
 
         bet.max(0.0)
     }
+
+    fn spots(&self, true_count: f32) -> u8 {
+        let true_count = true_count.round();
+
+        self.spots.iter()
+            .rev()
+            .find(|&&(tc, _)| true_count >= tc)
+            .map_or(1, |&(_, spots)| spots)
+    }
+}
+
+/// Back-counting: the player watches the rounds without playing until the
+/// count is good enough, and leaves the shoe when it turns bad.
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Clone, Copy)]
+#[serde(default, deny_unknown_fields)]
+pub struct Wonging {
+    /// The true count from which the player enters the shoe, at any count if
+    /// not set
+    pub enter_tc:       Option<f32>,
+    /// The player leaves the shoe under this true count, never if not set
+    pub exit_tc:        Option<f32>,
+    /// Whether the player may only enter at the start of a shoe
+    pub no_mid_shoe_entry: bool,
+}
+
+impl Wonging {
+    /// Whether the player plays the next round, given whether they played the
+    /// previous one and if the next one starts a shoe.
+    pub fn plays(&self, playing: bool, true_count: f32, shoe_start: bool)
+        -> bool {
+        let true_count = true_count.round();
+
+        if playing {
+            self.exit_tc.map_or(true, |tc| true_count >= tc)
+        } else {
+            (shoe_start || !self.no_mid_shoe_entry)
+                && self.enter_tc.map_or(true, |tc| true_count >= tc)
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::betting::{FixedBet, BettingStrategy, HiloBetting};
+    use crate::betting::{FixedBet, BettingStrategy, HiloBetting, Wonging};
 
     #[test]
     fn it_uses_a_fixed_bet() {
@@ -133,4 +191,48 @@ mod tests {
         assert_eq!(betting.place_bet(6.0),  50.0);
         assert_eq!(betting.place_bet(10.0), 50.0);
     }
+
+    #[test]
+    fn it_spreads_to_several_spots() {
+        let betting = HiloBetting::new(10.0, 10.0, None, None, None)
+            .with_spots(vec![(4.0, 3), (2.0, 2)]);
+
+        assert_eq!(betting.spots(-2.0), 1);
+        assert_eq!(betting.spots(1.4), 1);
+        assert_eq!(betting.spots(1.5), 2);
+        assert_eq!(betting.spots(3.0), 2);
+        assert_eq!(betting.spots(4.0), 3);
+        assert_eq!(betting.spots(10.0), 3);
+        assert_eq!(betting.place_bet(4.0), 50.0);
+        assert_eq!(FixedBet(5.0).spots(10.0), 1);
+    }
+
+    #[test]
+    fn it_wongs_in_and_out() {
+        let wonging = Wonging {
+            enter_tc: Some(2.0),
+            exit_tc: Some(0.0),
+            no_mid_shoe_entry: false,
+        };
+
+        assert!(!wonging.plays(false, 1.0, true));
+        assert!(wonging.plays(false, 1.6, false));
+        assert!(wonging.plays(true, 1.0, false));
+        assert!(wonging.plays(true, -0.4, false));
+        assert!(!wonging.plays(true, -1.0, false));
+    }
+
+    #[test]
+    fn it_only_enters_at_the_start_of_a_shoe() {
+        let wonging = Wonging {
+            enter_tc: None,
+            exit_tc: Some(-1.0),
+            no_mid_shoe_entry: true,
+        };
+
+        assert!(wonging.plays(false, 0.0, true));
+        assert!(!wonging.plays(false, 5.0, false));
+        assert!(wonging.plays(true, 5.0, false));
+        assert!(!wonging.plays(true, -2.0, false));
+    }
 }
@@ -1,7 +1,10 @@
 use std::fmt;
 use bitflags::_core::convert::TryFrom;
+use serde::{Serialize, Deserialize, Deserializer};
+use serde::de::Error;
 
-#[derive(Copy, Clone, PartialEq, Debug)]
+/// A card's value, from 1 (ace) to 10 (ten or face card).
+#[derive(Copy, Clone, PartialEq, Debug, Serialize)]
 pub struct Card(pub u8);
 
 impl fmt::Display for Card {
@@ -14,6 +17,18 @@ impl fmt::Display for Card {
     }
 }
 
+/// Cards are deserialized from their value, 1 for an ace.
+impl<'de> Deserialize<'de> for Card {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where D: Deserializer<'de> {
+        let value = u8::deserialize(deserializer)?;
+        if !(1..=10).contains(&value) {
+            return Err(D::Error::custom(format!("invalid card {}", value)));
+        }
+        Ok(Card(value))
+    }
+}
+
 impl TryFrom<&str> for Card {
     type Error = &'static str;
 
@@ -22,7 +37,7 @@ impl TryFrom<&str> for Card {
             Ok(Card(1))
         } else {
             let n: u8 = value.parse().map_err(|_| "Invalid card")?;
-            if n < 2 || n > 10 {
+            if !(2..=10).contains(&n) {
                 Err("Invalid card")
             } else {
                 Ok(Card(n))
@@ -31,11 +46,94 @@ impl TryFrom<&str> for Card {
     }
 }
 
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Suit {
+    Spades,
+    Hearts,
+    Diamonds,
+    Clubs,
+}
+
+impl Suit {
+    pub const ALL: [Suit; 4] = [Suit::Spades, Suit::Hearts,
+                                Suit::Diamonds, Suit::Clubs];
+
+    pub fn is_red(self) -> bool {
+        self == Suit::Hearts || self == Suit::Diamonds
+    }
+}
+
+impl fmt::Display for Suit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", match self {
+            Suit::Spades => "s",
+            Suit::Hearts => "h",
+            Suit::Diamonds => "d",
+            Suit::Clubs => "c",
+        })
+    }
+}
+
+/// A card with its rank and suit, for the rules where they matter on top of
+/// the card's value, e.g. side bets.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct SuitedCard {
+    /// The card's rank, from 1 (ace) to 13 (king)
+    pub rank:   u8,
+    pub suit:   Suit,
+}
+
+impl SuitedCard {
+    pub fn new(rank: u8, suit: Suit) -> SuitedCard {
+        assert!((1..=13).contains(&rank));
+
+        SuitedCard { rank, suit }
+    }
+
+    /// Returns the card as valued in Blackjack, face cards counting as 10.
+    pub fn card(self) -> Card {
+        Card(self.rank.min(10))
+    }
+}
+
+impl From<Card> for SuitedCard {
+    /// Converts a card whose rank and suit are unknown, tens are considered as
+    /// such (not face cards) and all cards as spades.
+    fn from(card: Card) -> Self {
+        SuitedCard::new(card.0, Suit::Spades)
+    }
+}
+
+impl fmt::Display for SuitedCard {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.rank {
+            1 => write!(f, "A"),
+            11 => write!(f, "J"),
+            12 => write!(f, "Q"),
+            13 => write!(f, "K"),
+            n => write!(f, "{}", n),
+        }?;
+
+        write!(f, "{}", self.suit)
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::card::Card;
+    use crate::card::{Card, Suit, SuitedCard};
     use std::convert::TryFrom;
 
+    #[test]
+    fn it_values_suited_cards() {
+        assert_eq!(SuitedCard::new(1, Suit::Hearts).card(), Card(1));
+        assert_eq!(SuitedCard::new(9, Suit::Clubs).card(), Card(9));
+        assert_eq!(SuitedCard::new(10, Suit::Spades).card(), Card(10));
+        assert_eq!(SuitedCard::new(12, Suit::Hearts).card(), Card(10));
+        assert_eq!(SuitedCard::new(12, Suit::Hearts).to_string(), "Qh");
+        assert!(Suit::Diamonds.is_red());
+        assert!(!Suit::Clubs.is_red());
+    }
+
     #[test]
     fn it_converts_from_str() {
         assert_eq!(Card::try_from("2"), Ok(Card(2)));
@@ -0,0 +1,561 @@
+use std::io::{self, Write};
+
+use freebj::basic_strategy::BasicStrategy;
+use freebj::card::Card;
+use freebj::deviation::{DeviationTable, DevOverride, row_hand};
+use freebj::game_rules::{GameRules, GameType, Soft17, SurrenderPolicy,
+                         DoublePolicy};
+use freebj::hand::Hand;
+use freebj::hand_logic::may_double;
+use freebj::strategy::{Strategy, GameContext, Decision};
+use freebj::strategy_file::StrategyFile;
+
+/// The format a strategy chart is written in.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ChartFormat {
+    Text,
+    Markdown,
+    Html,
+    Svg,
+}
+
+/// The actions of the chart's cells, with their meaning and colour.
+static ACTIONS: [(&str, &str, &str); 7] = [
+    ("H", "hit", "#ef9a9a"),
+    ("S", "stand", "#fff59d"),
+    ("D", "double, else hit", "#a5d6a7"),
+    ("Ds", "double, else stand", "#dcedc8"),
+    ("P", "split", "#90caf9"),
+    ("Rh", "surrender, else hit", "#e0e0e0"),
+    ("Rs", "surrender, else stand", "#bdbdbd"),
+];
+
+/// A deviation from a cell's action at a true count and above (or below).
+struct Overlay {
+    above: bool,
+    index: i32,
+    action: &'static str,
+}
+
+struct Cell {
+    action: &'static str,
+    deviation: Option<Overlay>,
+}
+
+struct Table {
+    name: &'static str,
+    rows: Vec<(String, Vec<Cell>)>,
+}
+
+/// A printable chart of a strategy for some game rules: the action played
+/// with each starting hand against each upcard, the rule-dependent actions
+/// of the strategy tables resolved, and the deviations overlaid.
+pub struct Chart {
+    title: String,
+    tables: Vec<Table>,
+    /// The composition-dependent rules, which a chart of totals cannot show
+    exceptions: Vec<String>,
+}
+
+impl Chart {
+    pub fn new(rules: &GameRules, strategy: &BasicStrategy) -> Chart {
+        let file = strategy.export();
+        let title = if file.deviations.is_empty() {
+            format!("Basic strategy: {}", describe(rules))
+        } else {
+            format!("Strategy with hi-lo deviations: {}", describe(rules))
+        };
+        let exceptions = file.composition.iter()
+            .map(|rule| rule.to_string())
+            .collect();
+
+        // The tables alone, then with the deviations, totals only
+        let mut plain = BasicStrategy::new(false);
+        plain.load(StrategyFile {
+            deviations: Vec::new(),
+            composition: Vec::new(),
+            ..file.clone()
+        });
+        let mut counted = BasicStrategy::new(false);
+        counted.load(StrategyFile { composition: Vec::new(), ..file });
+
+        let sections = [
+            ("Hard", DeviationTable::HardTable, 0..17),
+            ("Soft", DeviationTable::SoftTable, 1..9),
+            ("Pairs", DeviationTable::PairTable, 0..10),
+        ];
+        let mut tables: Vec<Table> = sections.iter()
+            .map(|(name, table, rows)| Table {
+                name,
+                rows: rows.clone()
+                    .map(|row| {
+                        let hand = row_hand(table, row);
+                        let label = match table {
+                            DeviationTable::HardTable => {
+                                hand.value().to_string()
+                            },
+                            DeviationTable::SoftTable => {
+                                format!("A{}", hand[1])
+                            },
+                            DeviationTable::PairTable => {
+                                format!("{0}/{0}", hand[0])
+                            },
+                        };
+                        let cells = (1..=10)
+                            .map(|dealer| Cell {
+                                action: action(rules, &plain, &hand,
+                                               Card(dealer), table, 0.0),
+                                deviation: None,
+                            })
+                            .collect();
+                        (label, cells)
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        for dev in counted.deviations() {
+            let (above, index) = match dev.action {
+                DevOverride::AboveEqual(tc, _) => (true, tc.round() as i32),
+                DevOverride::UnderEqual(tc, _) => (false, tc.round() as i32),
+            };
+            let (section, first) = match dev.table {
+                DeviationTable::HardTable => (0, 0),
+                DeviationTable::SoftTable => (1, 1),
+                DeviationTable::PairTable => (2, 0),
+            };
+            let row = match (dev.row as usize).checked_sub(first) {
+                Some(row) if row < tables[section].rows.len() => row,
+                _ => continue,
+            };
+
+            let hand = row_hand(&dev.table, dev.row);
+            let deviated = action(rules, &counted, &hand, Card(dev.dealer),
+                                  &dev.table, index as f32);
+            let cells = &mut tables[section].rows[row].1;
+            let cell = &mut cells[dev.dealer as usize - 1];
+            if deviated != cell.action {
+                cell.deviation = Some(Overlay {
+                    above,
+                    index,
+                    action: deviated,
+                });
+            }
+        }
+
+        Chart { title, tables, exceptions }
+    }
+
+    pub fn write(&self, output: &mut dyn Write, format: ChartFormat)
+        -> io::Result<()> {
+        match format {
+            ChartFormat::Text => self.write_text(output),
+            ChartFormat::Markdown => self.write_markdown(output),
+            ChartFormat::Html => self.write_html(output),
+            ChartFormat::Svg => self.write_svg(output),
+        }
+    }
+
+    fn write_text(&self, output: &mut dyn Write) -> io::Result<()> {
+        let width = self.tables.iter()
+            .flat_map(|table| table.rows.iter())
+            .flat_map(|(_, cells)| cells.iter())
+            .map(|cell| text_cell(cell).len())
+            .max()
+            .unwrap_or(0)
+            .max(2) + 1;
+
+        writeln!(output, "{}", self.title)?;
+        for table in &self.tables {
+            write!(output, "\n{:6}", table.name)?;
+            for dealer in 1..=10 {
+                write!(output, "{:>w$}", Card(dealer).to_string(), w = width)?;
+            }
+            writeln!(output)?;
+
+            for (label, cells) in &table.rows {
+                write!(output, "{:6}", label)?;
+                for cell in cells {
+                    write!(output, "{:>w$}", text_cell(cell), w = width)?;
+                }
+                writeln!(output)?;
+            }
+        }
+
+        writeln!(output)?;
+        let mut legend = legend(">", "<");
+        if !self.exceptions.is_empty() {
+            legend += &format!(" Composition exceptions: {}.",
+                               self.exceptions.join(", "));
+        }
+        // Wrapped to 80 columns
+        let mut line = String::new();
+        for word in legend.split(' ') {
+            if !line.is_empty() && line.len() + word.len() >= 80 {
+                writeln!(output, "{}", line)?;
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line += word;
+        }
+        writeln!(output, "{}", line)
+    }
+
+    fn write_markdown(&self, output: &mut dyn Write) -> io::Result<()> {
+        writeln!(output, "# {}", self.title)?;
+        for table in &self.tables {
+            write!(output, "\n| {} |", table.name)?;
+            for dealer in 1..=10 {
+                write!(output, " {} |", Card(dealer))?;
+            }
+            writeln!(output, "\n|---|{}", "---:|".repeat(10))?;
+
+            for (label, cells) in &table.rows {
+                write!(output, "| {} |", label)?;
+                for cell in cells {
+                    write!(output, " {} |", text_cell(cell))?;
+                }
+                writeln!(output)?;
+            }
+        }
+
+        writeln!(output, "\n{}", legend(">", "<"))?;
+        if !self.exceptions.is_empty() {
+            writeln!(output, "\nComposition exceptions: {}",
+                     self.exceptions.join(", "))?;
+        }
+        Ok(())
+    }
+
+    fn write_html(&self, output: &mut dyn Write) -> io::Result<()> {
+        writeln!(output, "<!DOCTYPE html>\n<html>\n<head>\n\
+                          <meta charset=\"utf-8\">\n<title>{0}</title>\n\
+                          <style>\n\
+                          body {{ font-family: sans-serif; }}\n\
+                          table {{ border-collapse: collapse; \
+                          margin-bottom: 1em; }}\n\
+                          th, td {{ border: 1px solid #757575; \
+                          padding: 2px 6px; text-align: center; }}\n\
+                          sup {{ font-size: 0.7em; }}",
+                 self.title)?;
+        for (i, (_, _, colour)) in ACTIONS.iter().enumerate() {
+            writeln!(output, ".a{} {{ background: {}; }}", i, colour)?;
+        }
+        writeln!(output, "</style>\n</head>\n<body>\n<h1>{}</h1>",
+                 self.title)?;
+
+        for table in &self.tables {
+            write!(output, "<table>\n<tr><th>{}</th>", table.name)?;
+            for dealer in 1..=10 {
+                write!(output, "<th>{}</th>", Card(dealer))?;
+            }
+            writeln!(output, "</tr>")?;
+
+            for (label, cells) in &table.rows {
+                write!(output, "<tr><th>{}</th>", label)?;
+                for cell in cells {
+                    write!(output, "<td class=\"a{}\">{}",
+                           action_index(cell.action), cell.action)?;
+                    if let Some(dev) = &cell.deviation {
+                        write!(output, "<sup>{}{:+} {}</sup>",
+                               if dev.above { "&ge;" } else { "&le;" },
+                               dev.index, dev.action)?;
+                    }
+                    write!(output, "</td>")?;
+                }
+                writeln!(output, "</tr>")?;
+            }
+            writeln!(output, "</table>")?;
+        }
+
+        writeln!(output, "<p>{}</p>", legend("&ge;", "&le;"))?;
+        if !self.exceptions.is_empty() {
+            writeln!(output, "<p>Composition exceptions: {}</p>",
+                     self.exceptions.join(", "))?;
+        }
+        writeln!(output, "</body>\n</html>")
+    }
+
+    fn write_svg(&self, output: &mut dyn Write) -> io::Result<()> {
+        const CELL_W: usize = 44;
+        const CELL_H: usize = 22;
+        const MARGIN: usize = 10;
+
+        let rows: usize = self.tables.iter()
+            .map(|table| table.rows.len() + 1)
+            .sum();
+        let width = 2 * MARGIN + 11 * CELL_W;
+        let legend_y = MARGIN + 30 + rows * CELL_H
+            + (self.tables.len() - 1) * MARGIN;
+        let height = legend_y + (ACTIONS.len() + 2) * 16 + MARGIN;
+
+        writeln!(output, "<svg xmlns=\"http://www.w3.org/2000/svg\" \
+                          width=\"{0}\" height=\"{1}\" \
+                          viewBox=\"0 0 {0} {1}\" font-family=\"sans-serif\" \
+                          font-size=\"12\" text-anchor=\"middle\">",
+                 width, height)?;
+        writeln!(output, "<rect width=\"100%\" height=\"100%\" \
+                          fill=\"white\"/>")?;
+        writeln!(output, "<text x=\"{}\" y=\"{}\" text-anchor=\"start\" \
+                          font-size=\"14\" font-weight=\"bold\">{}</text>",
+                 MARGIN, MARGIN + 14, self.title)?;
+
+        let cell = |output: &mut dyn Write, x: usize, y: usize, fill: &str,
+                    text: &str, bold: bool| -> io::Result<()> {
+            writeln!(output, "<rect x=\"{}\" y=\"{}\" width=\"{}\" \
+                              height=\"{}\" fill=\"{}\" stroke=\"#757575\"/>",
+                     x, y, CELL_W, CELL_H, fill)?;
+            writeln!(output, "<text x=\"{}\" y=\"{}\"{}>{}</text>",
+                     x + CELL_W / 2, y + 15,
+                     if bold { " font-weight=\"bold\"" } else { "" }, text)
+        };
+
+        let mut y = MARGIN + 30;
+        for table in &self.tables {
+            cell(output, MARGIN, y, "#f5f5f5", table.name, true)?;
+            for dealer in 1..=10 {
+                cell(output, MARGIN + dealer * CELL_W, y, "#f5f5f5",
+                     &Card(dealer as u8).to_string(), true)?;
+            }
+            y += CELL_H;
+
+            for (label, cells) in &table.rows {
+                cell(output, MARGIN, y, "#f5f5f5", label, true)?;
+                for (i, c) in cells.iter().enumerate() {
+                    let x = MARGIN + (i + 1) * CELL_W;
+                    let (_, _, colour) = ACTIONS[action_index(c.action)];
+                    if let Some(dev) = &c.deviation {
+                        cell(output, x, y, colour, "", false)?;
+                        writeln!(output, "<text x=\"{}\" y=\"{}\">{}</text>",
+                                 x + 12, y + 15, c.action)?;
+                        writeln!(output, "<text x=\"{}\" y=\"{}\" \
+                                          font-size=\"8\">{}{:+} {}</text>",
+                                 x + 31, y + 14,
+                                 if dev.above { "&#8805;" } else { "&#8804;" },
+                                 dev.index, dev.action)?;
+                    } else {
+                        cell(output, x, y, colour, c.action, false)?;
+                    }
+                }
+                y += CELL_H;
+            }
+            y += MARGIN;
+        }
+
+        let mut y = legend_y;
+        for (action, meaning, colour) in ACTIONS.iter() {
+            writeln!(output, "<rect x=\"{}\" y=\"{}\" width=\"20\" \
+                              height=\"12\" fill=\"{}\" \
+                              stroke=\"#757575\"/>",
+                     MARGIN, y, colour)?;
+            writeln!(output, "<text x=\"{}\" y=\"{}\" text-anchor=\"start\">\
+                              {}: {}</text>",
+                     MARGIN + 26, y + 10, action, meaning)?;
+            y += 16;
+        }
+        writeln!(output, "<text x=\"{}\" y=\"{}\" text-anchor=\"start\">\
+                          &#8805;TC X: X at true count TC and above, \
+                          &#8804;TC X: X at TC and below</text>",
+                 MARGIN, y + 10)?;
+        if !self.exceptions.is_empty() {
+            writeln!(output, "<text x=\"{}\" y=\"{}\" text-anchor=\"start\" \
+                              font-size=\"9\">Composition exceptions: {}\
+                              </text>",
+                     MARGIN, y + 26, self.exceptions.join(", "))?;
+        }
+        writeln!(output, "</svg>")
+    }
+}
+
+/// Returns the action played with a starting hand of a table: the decision
+/// of the strategy, and whether it surrenders, when the rules allow it.
+fn action(rules: &GameRules,
+          strategy: &BasicStrategy,
+          hand: &Hand,
+          dealer: Card,
+          table: &DeviationTable,
+          true_count: f32) -> &'static str {
+    let mut game = GameContext {
+        rules,
+        may_split: *table == DeviationTable::PairTable && rules.max_splits > 1,
+        may_double: may_double(rules, hand),
+        true_count,
+        holecard: None,
+        hole_tell: None,
+        next_card: None,
+    };
+
+    let early = rules.surrenders_early(dealer);
+    let late = rules.surrender == SurrenderPolicy::LateSurrender;
+    let surrenders = (early || late)
+        && strategy.surrender(&game, dealer, hand, early);
+    let decision = strategy.player_turn(&game, dealer, hand);
+    game.may_double = false;
+    let otherwise = strategy.player_turn(&game, dealer, hand);
+
+    match (decision, surrenders, otherwise) {
+        (_, true, Decision::Stand) => "Rs",
+        (_, true, _) => "Rh",
+        (Decision::Split, ..) => "P",
+        (Decision::Double, _, Decision::Stand) => "Ds",
+        (Decision::Double, ..) => "D",
+        (Decision::Hit, ..) => "H",
+        (Decision::Stand, ..) => "S",
+    }
+}
+
+fn action_index(action: &str) -> usize {
+    ACTIONS.iter().position(|(a, _, _)| *a == action).unwrap()
+}
+
+fn text_cell(cell: &Cell) -> String {
+    match &cell.deviation {
+        Some(dev) => format!("{} {}{:+}{}", cell.action,
+                             if dev.above { ">" } else { "<" },
+                             dev.index, dev.action),
+        None => cell.action.to_string(),
+    }
+}
+
+fn legend(above: &str, under: &str) -> String {
+    let actions: Vec<String> = ACTIONS.iter()
+        .map(|(action, meaning, _)| format!("{}: {}", action, meaning))
+        .collect();
+    format!("{}. {}TC X: X at true count TC and above; {}TC X: X at TC and \
+             below.", actions.join("; "), above, under)
+}
+
+/// Returns a short description of the rules the strategy depends on.
+fn describe(rules: &GameRules) -> String {
+    let mut rules_text = vec![
+        if rules.decks == 1 {
+            String::from("1 deck")
+        } else {
+            format!("{} decks", rules.decks)
+        },
+        String::from(if rules.soft17 == Soft17::H17 { "H17" } else { "S17" }),
+        String::from(if rules.das { "DAS" } else { "no DAS" }),
+        String::from(match rules.game_type {
+            GameType::Ahc => "AHC",
+            GameType::Enhc => "ENHC",
+        }),
+    ];
+    if rules.original_bets_only {
+        rules_text.push(String::from("OBO"));
+    }
+    match rules.double_down {
+        DoublePolicy::AnyTwo => {},
+        DoublePolicy::AnyHand => rules_text.push("double any hand".into()),
+        DoublePolicy::Hard9To11 => rules_text.push("double 9-11".into()),
+        DoublePolicy::Hard10To11 => rules_text.push("double 10-11".into()),
+        DoublePolicy::NoDouble => rules_text.push("no double".into()),
+    }
+    match rules.surrender {
+        SurrenderPolicy::NoSurrender => {},
+        SurrenderPolicy::EarlySurrender => {
+            rules_text.push("early surrender".into())
+        },
+        SurrenderPolicy::LateSurrender => {
+            rules_text.push("late surrender".into())
+        },
+        SurrenderPolicy::EarlySurrenderTens => {
+            rules_text.push("early surrender against tens".into())
+        },
+    }
+    rules_text.join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use freebj::basic_strategy::BasicStrategy;
+    use freebj::deviation::Deviation;
+    use freebj::game_rules::{GameRules, Soft17, SurrenderPolicy};
+    use crate::chart::{Chart, ChartFormat};
+
+    fn chart(rules: &GameRules, strategy: &BasicStrategy,
+             format: ChartFormat) -> String {
+        let mut output = Vec::new();
+        Chart::new(rules, strategy).write(&mut output, format).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    fn row<'a>(chart: &'a str, table: &str, label: &str) -> Vec<&'a str> {
+        let table = &chart[chart.find(&format!("\n{} ", table)).unwrap()..];
+        table.lines()
+            .find(|line| line.split_whitespace().next() == Some(label))
+            .unwrap()
+            .split_whitespace()
+            .skip(1)
+            .collect()
+    }
+
+    #[test]
+    fn it_resolves_the_rules() {
+        let mut rules = GameRules::default();
+        let strategy = BasicStrategy::new(false);
+
+        let text = chart(&rules, &strategy, ChartFormat::Text);
+        assert!(text.starts_with("Basic strategy: 6 decks, S17, DAS, AHC\n"));
+        assert_eq!(row(&text, "Hard", "16"),
+                   ["H", "S", "S", "S", "S", "S", "H", "H", "H", "H"]);
+        assert_eq!(row(&text, "Hard", "11"),
+                   ["H", "D", "D", "D", "D", "D", "D", "D", "D", "D"]);
+        assert_eq!(row(&text, "Soft", "A7"),
+                   ["H", "S", "Ds", "Ds", "Ds", "Ds", "S", "S", "H", "H"]);
+        assert_eq!(row(&text, "Pairs", "6/6"),
+                   ["H", "P", "P", "P", "P", "P", "H", "H", "H", "H"]);
+
+        rules.soft17 = Soft17::H17;
+        rules.das = false;
+        rules.surrender = SurrenderPolicy::LateSurrender;
+        let text = chart(&rules, &strategy, ChartFormat::Text);
+        assert_eq!(row(&text, "Hard", "16"),
+                   ["Rh", "S", "S", "S", "S", "S", "H", "H", "Rh", "Rh"]);
+        assert_eq!(row(&text, "Hard", "11"),
+                   ["D", "D", "D", "D", "D", "D", "D", "D", "D", "D"]);
+        assert_eq!(row(&text, "Soft", "A7"),
+                   ["H", "Ds", "Ds", "Ds", "Ds", "Ds", "S", "S", "H", "H"]);
+        assert_eq!(row(&text, "Pairs", "6/6"),
+                   ["H", "H", "P", "P", "P", "P", "H", "H", "H", "H"]);
+    }
+
+    #[test]
+    fn it_overlays_the_deviations() {
+        let rules = GameRules::default();
+        let mut strategy = BasicStrategy::new(true);
+        strategy.add_deviation(Deviation::from_str("16vs10:>+0=").unwrap());
+        strategy.add_deviation(Deviation::from_str("12vs4:<-1+").unwrap());
+        strategy.add_deviation(Deviation::from_str("T/Tvs6:>+4V").unwrap());
+        // Playing the same action at any count is no deviation
+        strategy.add_deviation(Deviation::from_str("20vs6:>+3=").unwrap());
+
+        let text = chart(&rules, &strategy, ChartFormat::Text);
+        assert!(text.starts_with("Strategy with hi-lo deviations"));
+        assert_eq!(row(&text, "Hard", "16")[9..], ["H", ">+0S"]);
+        assert_eq!(row(&text, "Hard", "12")[3..5], ["S", "<-1H"]);
+        assert_eq!(row(&text, "Pairs", "10/10")[5..7], ["S", ">+4P"]);
+        assert_eq!(row(&text, "Hard", "20")[5], "S");
+
+        let html = chart(&rules, &strategy, ChartFormat::Html);
+        assert!(html.contains("<td class=\"a0\">H<sup>&ge;+0 S</sup></td>"));
+    }
+
+    #[test]
+    fn it_writes_every_format() {
+        let rules = GameRules::default();
+        let strategy = BasicStrategy::new(false);
+
+        let markdown = chart(&rules, &strategy, ChartFormat::Markdown);
+        assert!(markdown.contains("\n| 16 | H | S | S | S | S | S | H | H \
+                                   | H | H |\n"));
+        let html = chart(&rules, &strategy, ChartFormat::Html);
+        assert_eq!(html.matches("<tr>").count(), 3 + 17 + 8 + 10);
+        let svg = chart(&rules, &strategy, ChartFormat::Svg);
+        assert!(svg.starts_with("<svg ") && svg.ends_with("</svg>\n"));
+        assert_eq!(svg.matches("<rect").count(),
+                   1 + 11 * (3 + 17 + 8 + 10) + 7);
+    }
+}
@@ -0,0 +1,413 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+use regex::Regex;
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+use serde::de::Error;
+
+use crate::card::Card;
+use crate::hand::Hand;
+use crate::game_rules::Soft17;
+
+/// The exceptions of single-deck games to the strategy tables, for S17 then
+/// H17 rules: peeked naturals, any two cards doubled, no DAS, no surrender.
+static SINGLE_DECK: [&[&str]; 2] = [
+    &[
+        "11vsA:D", "A7vsA:=", "9vs2:D", "A6vs2:D", "10-3vs2:+", "6-6vs2:V",
+        "2-2vs3:V", "8-4vs3:=", "7-5vs3:=", "A2vs4:D", "A3vs4:D",
+        "10-2vs4:+", "8vs5:D", "AAvs5:D", "6-2vs5:+", "8vs6:D", "A8vs6:d",
+        "6-2vs6:+", "10-2vs6:+", "7-7vs10:=", "16(3+)vs10:=",
+    ],
+    &[
+        "8-8vsA:V", "9vs2:D", "A6vs2:D", "A7vs2:=", "6-6vs2:V", "2-2vs3:V",
+        "8-4vs3:=", "7-5vs3:=", "12(3+)vs3:=", "A2vs4:D", "A3vs4:D",
+        "10-2vs4:+", "8vs5:D", "AAvs5:D", "8vs6:D", "6-2vs6:+", "7-7vs10:=",
+        "16(3+)vs10:=",
+    ],
+];
+
+/// The exceptions of double-deck games to the strategy tables, as
+/// `SINGLE_DECK`'s.
+static DOUBLE_DECK: [&[&str]; 2] = [
+    &[
+        "11vsA:D", "9-2vsA:+", "8-3vsA:+", "A7(3+)vsA:=", "9vs2:D",
+        "6-6vs2:V", "10-2vs4:+", "AAvs5:D", "16(3+)vs10:=",
+    ],
+    &[
+        "8-8vsA:V", "9vs2:D", "6-6vs2:V", "8-4vs3:=", "7-5vs3:=", "A3vs4:D",
+        "10-2vs4:+", "AAvs5:D", "16(3+)vs10:=",
+    ],
+];
+
+/// The names of the built-in composition-dependent tables.
+pub static COMPOSITION_TABLES: [&str; 2] = ["single-deck", "double-deck"];
+
+/// The player hands a composition rule applies to.
+#[derive(Debug, PartialEq, Clone)]
+pub enum CompositionHand {
+    /// Exactly these cards, in any order
+    Cards(Vec<Card>),
+    /// Any hand of a hard or soft total, of at least `min_cards` cards
+    Total {
+        value: u8,
+        soft: bool,
+        min_cards: u8,
+    },
+}
+
+/// A composition-dependent rule: the decision of a hand against a dealer
+/// upcard, with the basic strategy tables' codes, overriding the tables.
+///
+/// Rules are written "10-6vs10:+" for exact cards, in any order, "16vs10:="
+/// and "A7vsA:=" for any hard or soft total, and "16(3+)vs10:=" for totals of
+/// three cards or more.
+#[derive(Debug, PartialEq, Clone)]
+pub struct CompositionRule {
+    pub hand: CompositionHand,
+    pub dealer: u8,
+    pub action: u8,
+}
+
+impl CompositionRule {
+    /// Returns the rules of a built-in table for the dealer's soft 17 rule,
+    /// by name (see `COMPOSITION_TABLES`).
+    pub fn table(name: &str, soft17: &Soft17)
+        -> Option<Vec<CompositionRule>> {
+        let tables = match name {
+            "single-deck" => &SINGLE_DECK,
+            "double-deck" => &DOUBLE_DECK,
+            _ => return None,
+        };
+
+        Some(tables[(*soft17 == Soft17::H17) as usize].iter()
+            .map(|rule| rule.parse().expect("Invalid built-in rule"))
+            .collect())
+    }
+
+    /// Parses the rules of a file, one per line; blank lines and comments
+    /// starting with '#' are ignored.
+    pub fn parse_file(text: &str) -> Result<Vec<CompositionRule>, String> {
+        text.lines()
+            .enumerate()
+            .map(|(i, line)| (i, line.split('#').next().unwrap().trim()))
+            .filter(|(_, line)| !line.is_empty())
+            .map(|(i, line)| {
+                line.parse()
+                    .map_err(|e| format!("line {}: {}: {}", i + 1, line, e))
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for CompositionRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.hand {
+            CompositionHand::Cards(cards) => {
+                let cards: Vec<String> = cards.iter()
+                    .map(Card::to_string)
+                    .collect();
+                write!(f, "{}", cards.join("-"))?;
+            },
+            CompositionHand::Total { value, soft, min_cards } => {
+                if !soft {
+                    write!(f, "{}", value)?;
+                } else if *value == 12 {
+                    write!(f, "AA")?;
+                } else {
+                    write!(f, "A{}", value - 11)?;
+                }
+                if *min_cards > 2 {
+                    write!(f, "({}+)", min_cards)?;
+                }
+            },
+        }
+
+        write!(f, "vs{}:{}", Card(self.dealer), self.action as char)
+    }
+}
+
+impl FromStr for CompositionRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let regex = Regex::new(
+            r"^(?:([0-9AT]+(?:-[0-9AT]+)+)|(\d+|A\d+|AA)(?:\((\d+)\+\))?)vs(\d+|A):(.)$"
+        ).unwrap();
+        let c = regex.captures(s).ok_or_else(|| "Invalid syntax".to_string())?;
+
+        let hand = if let Some(cards) = c.get(1) {
+            let mut cards = cards.as_str().split('-')
+                .map(|card| match card {
+                    "T" => Ok(Card(10)),
+                    card => Card::try_from(card)
+                        .map_err(|_| format!("Invalid card '{}'", card)),
+                })
+                .collect::<Result<Vec<Card>, String>>()?;
+            // Aces first, then from the highest card
+            cards.sort_by_key(|card| if card.0 == 1 { 0 } else { 11 - card.0 });
+            CompositionHand::Cards(cards)
+        } else {
+            let total = &c[2];
+            let (value, soft) = if total == "AA" {
+                (12, true)
+            } else if let Some(card) = total.strip_prefix('A') {
+                match card.parse::<u8>() {
+                    Ok(card) if (2..=9).contains(&card) => (card + 11, true),
+                    _ => return Err("Invalid soft total".into()),
+                }
+            } else {
+                match total.parse::<u8>() {
+                    Ok(value) if (4..=20).contains(&value) => (value, false),
+                    _ => return Err("Invalid hard total".into()),
+                }
+            };
+            let min_cards = match c.get(3) {
+                Some(n) => match n.as_str().parse::<u8>() {
+                    Ok(n) if n >= 2 => n,
+                    _ => return Err("Invalid number of cards".into()),
+                },
+                None => 2,
+            };
+            CompositionHand::Total { value, soft, min_cards }
+        };
+
+        let dealer = if &c[4] == "A" {
+            1
+        } else {
+            c[4].parse().map_err(|_| String::from("Invalid dealer card"))?
+        };
+        if dealer == 0 || dealer > 10 {
+            return Err(String::from("Invalid dealer card"));
+        }
+
+        let action = c[5].as_bytes()[0];
+        if !b"+=DdVSs".contains(&action) {
+            return Err(String::from("Invalid action"));
+        }
+        let pair = matches!(&hand, CompositionHand::Cards(cards)
+                            if cards.len() == 2 && cards[0] == cards[1]);
+        if action == b'V' && !pair {
+            return Err(String::from("Only pairs can be split"));
+        }
+
+        Ok(CompositionRule { hand, dealer, action })
+    }
+}
+
+/// Composition rules are serialized with the same syntax as their files.
+impl Serialize for CompositionRule {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for CompositionRule {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where D: Deserializer<'de> {
+        let s = String::deserialize(deserializer)?;
+        CompositionRule::from_str(&s)
+            .map_err(|e| D::Error::custom(format!("{}: {}", s, e)))
+    }
+}
+
+/// A composition-dependent strategy: rules on exact cards and totals looked
+/// up before the strategy tables.
+#[derive(Debug, Default)]
+pub struct CompositionStrategy {
+    /// By upcard and count of each card (ace first), the exact cards' action
+    cards: HashMap<(u8, [u8; 10]), u8>,
+    /// The rules on totals, the most cards first
+    totals: Vec<CompositionRule>,
+}
+
+impl CompositionStrategy {
+    /// Adds a rule, replacing any rule on the same hand and upcard.
+    pub fn add(&mut self, rule: CompositionRule) {
+        match &rule.hand {
+            CompositionHand::Cards(cards) => {
+                self.cards.insert((rule.dealer, counts(cards.iter())),
+                                  rule.action);
+            },
+            CompositionHand::Total { min_cards, .. } => {
+                self.totals.retain(|other| {
+                    other.hand != rule.hand || other.dealer != rule.dealer
+                });
+                let i = self.totals.iter()
+                    .position(|other| match other.hand {
+                        CompositionHand::Total { min_cards: n, .. } => {
+                            n < *min_cards
+                        },
+                        CompositionHand::Cards(_) => unreachable!(),
+                    })
+                    .unwrap_or(self.totals.len());
+                self.totals.insert(i, rule);
+            },
+        }
+    }
+
+    /// Returns the rules in use: on exact cards by upcard, then on totals.
+    pub fn rules(&self) -> Vec<CompositionRule> {
+        let mut cards: Vec<_> = self.cards.iter().collect();
+        cards.sort_by_key(|((dealer, counts), _)| (*dealer, *counts));
+
+        cards.into_iter()
+            .map(|((dealer, counts), action)| {
+                // Aces first, then from the highest card
+                let cards = std::iter::once(0).chain((1..10).rev())
+                    .flat_map(|i| {
+                        std::iter::repeat(Card(i as u8 + 1))
+                            .take(counts[i] as usize)
+                    })
+                    .collect();
+                CompositionRule {
+                    hand: CompositionHand::Cards(cards),
+                    dealer: *dealer,
+                    action: *action,
+                }
+            })
+            .chain(self.totals.iter().cloned())
+            .collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cards.is_empty() && self.totals.is_empty()
+    }
+
+    /// Returns the action of the rule on exactly the hand's cards, if any;
+    /// splitting only when `may_split`.
+    pub fn cards_decision(&self, dealer: Card, me: &Hand, may_split: bool)
+        -> Option<u8> {
+        self.cards.get(&(dealer.0, counts(me.iter())))
+            .copied()
+            .filter(|&action| action != b'V' || may_split)
+    }
+
+    /// Returns the action of the rule on the hand's total with the most
+    /// cards, if any.
+    pub fn total_decision(&self, dealer: Card, me: &Hand) -> Option<u8> {
+        self.totals.iter()
+            .find(|rule| rule.dealer == dealer.0 && match rule.hand {
+                CompositionHand::Total { value, soft, min_cards } => {
+                    value == me.value() && soft == me.is_soft()
+                        && me.count() >= min_cards as usize
+                },
+                CompositionHand::Cards(_) => false,
+            })
+            .map(|rule| rule.action)
+    }
+}
+
+/// Returns the number of each card, ace first.
+fn counts<'a>(cards: impl Iterator<Item=&'a Card>) -> [u8; 10] {
+    let mut counts = [0; 10];
+    for card in cards {
+        counts[card.0 as usize - 1] += 1;
+    }
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use crate::composition::{CompositionRule, CompositionHand,
+                             CompositionStrategy, COMPOSITION_TABLES};
+    use crate::game_rules::Soft17::{H17, S17};
+    use crate::hand::Hand;
+    use crate::card::Card;
+
+    #[test]
+    fn it_parses_rules() {
+        assert_eq!(CompositionRule::from_str("10-6vs10:+"),
+                   Ok(CompositionRule {
+                       hand: CompositionHand::Cards(vec![Card(10), Card(6)]),
+                       dealer: 10,
+                       action: b'+',
+                   }));
+        assert_eq!(CompositionRule::from_str("16(3+)vsT:="),
+                   Err(String::from("Invalid syntax")));
+        assert_eq!(CompositionRule::from_str("16(3+)vs10:="),
+                   Ok(CompositionRule {
+                       hand: CompositionHand::Total {
+                           value: 16,
+                           soft: false,
+                           min_cards: 3,
+                       },
+                       dealer: 10,
+                       action: b'=',
+                   }));
+
+        for rule in ["A-7vsA:=", "A7vsA:=", "AAvs5:D", "8-4-4vs10:=",
+                     "11vs10:D", "7-7vs10:V", "16(4+)vs9:s"] {
+            assert_eq!(CompositionRule::from_str(rule).unwrap().to_string(),
+                       rule);
+        }
+        assert_eq!(CompositionRule::from_str("4-4-8vs10:=").unwrap()
+                       .to_string(), "8-4-4vs10:=");
+        assert_eq!(CompositionRule::from_str("T-AvsA:=").unwrap()
+                       .to_string(), "A-10vsA:=");
+
+        assert_eq!(CompositionRule::from_str("10-6vs10:V"),
+                   Err(String::from("Only pairs can be split")));
+        assert_eq!(CompositionRule::from_str("10-6vs10:X"),
+                   Err(String::from("Invalid action")));
+        assert_eq!(CompositionRule::from_str("21vs10:="),
+                   Err(String::from("Invalid hard total")));
+        assert_eq!(CompositionRule::from_str("16(1+)vs10:="),
+                   Err(String::from("Invalid number of cards")));
+        assert_eq!(CompositionRule::from_str("16vs11:="),
+                   Err(String::from("Invalid dealer card")));
+    }
+
+    #[test]
+    fn it_parses_files() {
+        let rules = CompositionRule::parse_file(
+            "# Single deck\n\n10-2vs4:+  # not 4-8\n16(3+)vs10:=\n"
+        ).unwrap();
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0].to_string(), "10-2vs4:+");
+
+        assert_eq!(CompositionRule::parse_file("10-2vs4:+\n\n16vs10:X\n"),
+                   Err(String::from("line 3: 16vs10:X: Invalid action")));
+    }
+
+    #[test]
+    fn it_loads_the_built_in_tables() {
+        for name in COMPOSITION_TABLES {
+            assert!(!CompositionRule::table(name, &S17).unwrap().is_empty());
+            assert!(!CompositionRule::table(name, &H17).unwrap().is_empty());
+        }
+        assert_eq!(CompositionRule::table("shoe", &S17), None);
+    }
+
+    #[test]
+    fn it_looks_up_the_most_specific_rule() {
+        let mut strategy = CompositionStrategy::default();
+        for rule in ["16vs10:=", "16(3+)vs10:+", "16(4+)vs10:=", "8-8vs10:V",
+                     "16vs10:S", "10-6vs10:d"] {
+            strategy.add(rule.parse().unwrap());
+        }
+        let decide = |cards: &[u8], may_split| {
+            let hand = Hand::from(cards);
+            strategy.cards_decision(Card(10), &hand, may_split)
+                .or_else(|| strategy.total_decision(Card(10), &hand))
+        };
+
+        assert_eq!(decide(&[6, 10], true), Some(b'd'));
+        assert_eq!(decide(&[9, 7], true), Some(b'S'));
+        assert_eq!(decide(&[8, 8], true), Some(b'V'));
+        assert_eq!(decide(&[8, 8], false), Some(b'S'));
+        assert_eq!(decide(&[8, 4, 4], true), Some(b'+'));
+        assert_eq!(decide(&[2, 2, 8, 4], true), Some(b'='));
+        assert_eq!(decide(&[1, 5], true), None);
+        assert_eq!(strategy.total_decision(Card(9), &Hand::from(&[9, 7][..])),
+                   None);
+
+        let rules: Vec<String> = strategy.rules().iter()
+            .map(CompositionRule::to_string)
+            .collect();
+        assert_eq!(rules, ["8-8vs10:V", "10-6vs10:d", "16(4+)vs10:=",
+                           "16(3+)vs10:+", "16vs10:S"]);
+    }
+}
@@ -0,0 +1,359 @@
+use serde::{Serialize, Deserialize};
+use serde_json::Value;
+
+use crate::game_rules::GameRules;
+use crate::deviation::Deviation;
+use crate::composition::CompositionRule;
+use crate::betting::Wonging;
+use crate::side_bet::SideBetWager;
+use crate::card::Card;
+use crate::script::Script;
+
+/// A whole simulation as described by an experiment configuration file. The
+/// resolved configuration is included in the results, so a simulation can be
+/// run again exactly from its result file.
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub rules:          GameRules,
+    pub table:          TableConfig,
+    /// The card counting system, if any
+    pub counting:       Option<CountingSystem>,
+    pub strategy:       StrategyConfig,
+    pub betting:        BettingConfig,
+    pub shoe:           ShoeConfig,
+    pub scenario:       ScenarioConfig,
+    pub run:            RunConfig,
+}
+
+/// The players seated at the table.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct TableConfig {
+    /// The number of players at the table, from 1 to 7
+    pub players:        u8,
+    /// The player's seat, from 1 (first base) to `players`
+    pub seat:           u8,
+    /// The strategy played at each seat, or empty for every seat to play the
+    /// player's strategy
+    pub seats:          Vec<SeatStrategy>,
+}
+
+impl Default for TableConfig {
+    fn default() -> Self {
+        TableConfig {
+            players:        1,
+            seat:           1,
+            seats:          Vec::new(),
+        }
+    }
+}
+
+/// The playing and betting strategies of a seat at the table.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Copy, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum SeatStrategy {
+    /// The player's strategy and bet ramp, as configured
+    Player,
+    /// Basic strategy without counting, with a flat bet
+    Basic,
+    /// The mistakes of a typical tourist (see `TouristStrategy`), with a flat
+    /// bet
+    Tourist,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Copy, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum CountingSystem {
+    Hilo,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct StrategyConfig {
+    /// The strategy file played instead of the built-in tables
+    pub file:           Option<String>,
+    /// Whether to play the exact strategy of the rules, computed by
+    /// combinatorial analysis, instead of the built-in tables
+    pub exact:          bool,
+    /// Whether to play the default deviations, requires card counting
+    pub deviations:     bool,
+    /// More deviations, overriding the default ones
+    pub add_deviations: Vec<Deviation>,
+    /// The composition-dependent exceptions to the strategy tables
+    pub composition:    Vec<CompositionRule>,
+    /// How much of the bet is insured when taking insurance, up to 0.5
+    pub insurance_amount: f64,
+    /// Whether the player sees the dealer's holecard (AHC only)
+    pub holecarding:    bool,
+    /// The accuracy of a tell on whether the dealer's holecard is a ten, in
+    /// (0.5, 1.0], 1.0 for a warp (AHC only)
+    pub tell:           Option<f32>,
+    /// Whether the player knows the next card off the shoe (first-basing)
+    pub next_card:      bool,
+}
+
+impl Default for StrategyConfig {
+    fn default() -> Self {
+        StrategyConfig {
+            file:           None,
+            exact:          false,
+            deviations:     false,
+            add_deviations: Vec::new(),
+            composition:    Vec::new(),
+            insurance_amount: 0.5,
+            holecarding:    false,
+            tell:           None,
+            next_card:      false,
+        }
+    }
+}
+
+/// The bet ramp: a fixed bet, or a bet spread on the true count when counting.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct BettingConfig {
+    pub bet:            f64,
+    pub bet_per_tc:     f64,
+    pub bet_neg_tc:     Option<f64>,
+    pub bet_max_tc:     Option<f32>,
+    pub wongout_under:  Option<f32>,
+    /// Spreading to several spots at high counts, by true count
+    pub spots:          Vec<SpotsStep>,
+    /// Back-counting, entering and leaving shoes on the true count
+    pub wonging:        Option<Wonging>,
+    /// The side bets placed along with the player's first spot
+    pub side_bets:      Vec<SideBetWager>,
+}
+
+/// Playing `spots` spots from a true count on, each with the ramp's bet.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Copy, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct SpotsStep {
+    pub true_count:     f32,
+    pub spots:          u8,
+}
+
+impl Default for BettingConfig {
+    fn default() -> Self {
+        BettingConfig {
+            bet:            1.0,
+            bet_per_tc:     1.0,
+            bet_neg_tc:     None,
+            bet_max_tc:     None,
+            wongout_under:  None,
+            spots:          Vec::new(),
+            wonging:        None,
+            side_bets:      Vec::new(),
+        }
+    }
+}
+
+/// Where cards are dealt from.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Default)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum ShoeConfig {
+    /// A shuffled shoe of `rules.decks` decks, dealt to `rules.penetration_cards`
+    #[default]
+    Standard,
+    /// A shoe file, see `FileShoe`
+    File { path: String },
+}
+
+/// A situation every round starts from, to study it.
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScenarioConfig {
+    /// The cards the players start with, the same for each player
+    pub start_cards:    Vec<Card>,
+    /// The cards the dealer starts with
+    pub dealer_cards:   Vec<Card>,
+    /// The script fixing the player's decisions instead of their strategy
+    pub script:         Option<Script>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct RunConfig {
+    /// The number of rounds to simulate
+    pub rounds:         u64,
+    /// The number of simulation threads
+    pub jobs:           u32,
+    /// The seed of the shoe shuffles, a random one is picked when not set
+    pub seed:           Option<u64>,
+    /// Force the true count before each round
+    pub force_tc:       Option<f32>,
+    /// Measure the EV gained from each information source of the players
+    pub information_gains: bool,
+}
+
+impl Default for RunConfig {
+    fn default() -> Self {
+        RunConfig {
+            rounds:         1_000_000,
+            jobs:           4,
+            seed:           None,
+            force_tc:       None,
+            information_gains: false,
+        }
+    }
+}
+
+impl Config {
+    /// Parses a JSON experiment configuration; missing fields take their
+    /// default values, those of the preset for the rules of a preset.
+    pub fn from_json(json: &str) -> Result<Config, String> {
+        let mut config: Value = serde_json::from_str(json)
+            .map_err(|e| e.to_string())?;
+
+        // The rules given along with a preset override the preset's rules
+        let rules = config.get_mut("rules").and_then(Value::as_object_mut);
+        if let Some(rules) = rules {
+            if let Some(name) = rules.get("preset").and_then(Value::as_str) {
+                let preset = GameRules::preset(name)
+                    .ok_or_else(|| format!("unknown preset \"{}\"", name))?;
+                let mut merged = match serde_json::to_value(preset) {
+                    Ok(Value::Object(merged)) => merged,
+                    _ => unreachable!(),
+                };
+                merged.extend(std::mem::take(rules));
+                *rules = merged;
+            }
+        }
+
+        serde_json::from_value(config).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::{Config, CountingSystem, ShoeConfig, SeatStrategy,
+                        SpotsStep};
+    use crate::game_rules::{GameType, Soft17, SurrenderPolicy};
+    use crate::deviation::Deviation;
+    use crate::betting::Wonging;
+    use crate::card::Card;
+    use std::str::FromStr;
+
+    #[test]
+    fn it_parses_a_config() {
+        let config = Config::from_json(r#"{
+            "rules": {"game_type": "enhc", "soft17": "h17", "decks": 8},
+            "table": {"players": 3, "seat": 3,
+                      "seats": ["tourist", "basic", "player"]},
+            "counting": "hilo",
+            "strategy": {"deviations": true, "add_deviations": ["16vs10:>+1="]},
+            "betting": {"bet": 10, "bet_per_tc": 20, "bet_max_tc": 5,
+                        "spots": [{"true_count": 3, "spots": 2}],
+                        "wonging": {"enter_tc": 1, "no_mid_shoe_entry": true}},
+            "shoe": {"type": "file", "path": "rc/shoe_1M"},
+            "run": {"rounds": 1000, "seed": 42}
+        }"#).unwrap();
+
+        assert_eq!(config.rules.game_type, GameType::Enhc);
+        assert_eq!(config.rules.soft17, Soft17::H17);
+        assert_eq!(config.rules.decks, 8);
+        assert_eq!(config.rules.surrender, SurrenderPolicy::NoSurrender);
+        assert_eq!(config.table.players, 3);
+        assert_eq!(config.table.seat, 3);
+        assert_eq!(config.table.seats, vec![SeatStrategy::Tourist,
+                                            SeatStrategy::Basic,
+                                            SeatStrategy::Player]);
+        assert_eq!(config.counting, Some(CountingSystem::Hilo));
+        assert!(config.strategy.deviations);
+        assert_eq!(config.strategy.add_deviations,
+                   vec![Deviation::from_str("16vs10:>+1=").unwrap()]);
+        assert_eq!(config.strategy.insurance_amount, 0.5);
+        assert_eq!(config.betting.bet_per_tc, 20.0);
+        assert_eq!(config.betting.bet_max_tc, Some(5.0));
+        assert_eq!(config.betting.spots,
+                   vec![SpotsStep { true_count: 3.0, spots: 2 }]);
+        assert_eq!(config.betting.wonging, Some(Wonging {
+            enter_tc: Some(1.0),
+            exit_tc: None,
+            no_mid_shoe_entry: true,
+        }));
+        assert_eq!(config.shoe,
+                   ShoeConfig::File { path: "rc/shoe_1M".to_string() });
+        assert_eq!(config.run.rounds, 1000);
+        assert_eq!(config.run.jobs, 4);
+        assert_eq!(config.run.seed, Some(42));
+    }
+
+    #[test]
+    fn it_rejects_unknown_fields() {
+        assert!(Config::from_json(r#"{"rules": {"deks": 8}}"#).is_err());
+        assert!(Config::from_json(r#"{"rounds": 10}"#).is_err());
+        assert!(Config::from_json(r#"{"shoe": {"type": "infinite"}}"#)
+            .is_err());
+    }
+
+    #[test]
+    fn it_round_trips_a_config() {
+        let config = Config::from_json(r#"{
+            "rules": {"preset": "vegas-strip-6d", "surrender": "late_surrender"},
+            "strategy": {"add_deviations": ["A/Avs7:<+1D", "12vs2:>+3="],
+                         "composition": ["8-4-4vs10:=", "16(3+)vs10:="]},
+            "run": {"seed": 7}
+        }"#).unwrap();
+
+        let json = serde_json::to_string(&config).unwrap();
+        let again = Config::from_json(&json).unwrap();
+        assert_eq!(serde_json::to_string(&again).unwrap(), json);
+        assert_eq!(again.rules.preset.as_deref(), Some("vegas-strip-6d"));
+        assert_eq!(again.rules.surrender, SurrenderPolicy::LateSurrender);
+        assert_eq!(again.run.seed, Some(7));
+        assert_eq!(again.strategy.composition.len(), 2);
+    }
+
+    #[test]
+    fn it_applies_a_preset() {
+        let config = Config::from_json(r#"{
+            "rules": {"preset": "downtown-2d-h17"}
+        }"#).unwrap();
+        assert_eq!(config.rules.soft17, Soft17::H17);
+        assert_eq!(config.rules.decks, 2);
+        assert_eq!(config.rules.penetration_cards, 78);
+
+        // The rules given override the preset's
+        let config = Config::from_json(r#"{
+            "rules": {"preset": "downtown-2d-h17", "soft17": "s17",
+                      "surrender": "late_surrender"}
+        }"#).unwrap();
+        assert_eq!(config.rules.preset.as_deref(), Some("downtown-2d-h17"));
+        assert_eq!(config.rules.soft17, Soft17::S17);
+        assert_eq!(config.rules.surrender, SurrenderPolicy::LateSurrender);
+        assert_eq!(config.rules.decks, 2);
+
+        assert!(Config::from_json(r#"{"rules": {"preset": "monaco"}}"#)
+            .is_err());
+        assert!(Config::from_json(r#"{"rules": {"preset": "downtown-2d-h17",
+                                                "deks": 4}}"#).is_err());
+    }
+
+    #[test]
+    fn it_round_trips_a_scenario() {
+        let config = Config::from_json(r#"{
+            "counting": "hilo",
+            "betting": {"side_bets": [
+                {"side_bet": "21+3", "bet": 5},
+                {"side_bet": "perfect-pairs:25,12,6@2", "bet": 1}]},
+            "scenario": {"start_cards": [8, 8], "dealer_cards": [10],
+                         "script": "V+,="}
+        }"#).unwrap();
+
+        let json = serde_json::to_string(&config).unwrap();
+        let again = Config::from_json(&json).unwrap();
+        assert_eq!(serde_json::to_string(&again).unwrap(), json);
+        assert_eq!(again.betting.side_bets.len(), 2);
+        assert_eq!(again.betting.side_bets[0].bet, 5.0);
+        assert_eq!(again.betting.side_bets[1].min_true_count, Some(2.0));
+        assert_eq!(again.scenario.start_cards, vec![Card(8), Card(8)]);
+        assert_eq!(again.scenario.dealer_cards, vec![Card(10)]);
+        assert_eq!(again.scenario.script.unwrap().to_string(), "V+,=");
+        assert!(Config::from_json(r#"{"scenario": {"start_cards": [11]}}"#)
+            .is_err());
+        assert!(Config::from_json(r#"{"betting": {"side_bets": [
+            {"side_bet": "21+3", "bet": 0}]}}"#).is_err());
+    }
+}
@@ -0,0 +1,306 @@
+use std::collections::HashMap;
+use serde::Serialize;
+
+use crate::card::Card;
+use crate::game_rules::Soft17;
+
+/// The number of cards of each value in a shoe, ace first.
+pub type CardCounts = [u16; 10];
+
+/// The probabilities of the dealer's final totals, from 17 to 26 (busted).
+pub type DealerFinals = [f64; 10];
+
+/// Returns the cards of a shoe of `decks` decks.
+pub fn shoe_counts(decks: u32) -> CardCounts {
+    let decks = decks as u16;
+    let mut cards = [4 * decks; 10];
+    cards[9] = 16 * decks;
+    cards
+}
+
+/// The probabilities of the outcomes of the dealer's hand from an upcard.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+pub struct DealerOutcomes {
+    /// Standing on 17 to 21
+    pub totals:     [f64; 5],
+    pub blackjack:  f64,
+    pub bust:       f64,
+}
+
+/// The exact probabilities of the dealer's final totals, drawing from a shoe
+/// of known cards, for a soft 17 rule.
+///
+/// They are computed from all the hands the dealer may stand on, by their
+/// cards: the probability of a hand is that of drawing its cards in any of
+/// the orders the dealer's rules allow.
+pub struct DealerProbabilities {
+    /// By upcard, the hands the dealer stands on but naturals
+    hands:  Vec<Vec<DealerHand>>,
+}
+
+/// The cards the dealer draws to an upcard, holecard included, and stands
+/// on.
+struct DealerHand {
+    /// The number of cards of each value, by index
+    cards:      Vec<(usize, u16)>,
+    /// The number of cards
+    count:      usize,
+    /// By holecard, the number of orders the cards can be drawn in
+    sequences:  [f64; 10],
+    /// The final total's index in `DealerFinals`
+    total:      usize,
+}
+
+impl DealerProbabilities {
+    pub fn new(soft17: &Soft17) -> DealerProbabilities {
+        let hits_soft17 = *soft17 == Soft17::H17;
+
+        DealerProbabilities {
+            hands: (1..=10).map(|up| dealer_hands(hits_soft17, up)).collect(),
+        }
+    }
+
+    /// Returns the probabilities of the dealer's final totals from an upcard
+    /// and a holecard that does not make a natural, drawn from `cards`.
+    pub fn finals(&self, cards: &CardCounts, upcard: Card) -> DealerFinals {
+        let left: u16 = cards.iter().sum();
+        let holes = left - natural_holecard(upcard).map_or(0, |i| cards[i]);
+        self.sum_finals(cards, upcard, holes, |hand| {
+            hand.sequences.iter().sum()
+        })
+    }
+
+    /// Returns the probabilities of the dealer's final totals from an upcard
+    /// and a known holecard that does not make a natural, the holecard being
+    /// among `cards` and the draws from the rest.
+    pub fn holecard_finals(&self, cards: &CardCounts, upcard: Card,
+                           holecard: Card) -> DealerFinals {
+        let hole = holecard.0 as usize - 1;
+        assert_ne!(Some(hole), natural_holecard(upcard));
+        self.sum_finals(cards, upcard, cards[hole], |hand| {
+            hand.sequences[hole]
+        })
+    }
+
+    /// Sums the probabilities of the hands the dealer stands on, `holes` of
+    /// the cards being the holecards they are drawn with and `sequences`
+    /// the orders of a hand's cards they are drawn in.
+    fn sum_finals<F>(&self, cards: &CardCounts, upcard: Card, holes: u16,
+                     sequences: F) -> DealerFinals
+        where F: Fn(&DealerHand) -> f64 {
+        // The probability of a hand's sequences of cards is the product of
+        // the counts of its cards, over the cards left at each draw; the
+        // dealer stands on at most 12 cards
+        let left: u16 = cards.iter().sum();
+        let mut draws = [holes as f64; 12];
+        for n in 1..draws.len() {
+            draws[n] = draws[n - 1] * (left as f64 - n as f64);
+        }
+
+        let mut finals = [0.0; 10];
+        for hand in self.hands[upcard.0 as usize - 1].iter() {
+            let mut p = sequences(hand);
+            if p == 0.0 {
+                continue;
+            }
+            for &(card, n) in hand.cards.iter() {
+                for drawn in 0..n {
+                    p *= cards[card].saturating_sub(drawn) as f64;
+                }
+            }
+            if p > 0.0 {
+                finals[hand.total] += p / draws[hand.count - 1];
+            }
+        }
+
+        finals
+    }
+
+    /// Returns the probabilities of the outcomes of the dealer's hand from an
+    /// upcard, drawing from `cards`; once the dealer has no natural when
+    /// they `peek`.
+    pub fn outcomes(&self, cards: &CardCounts, upcard: Card, peek: bool)
+        -> DealerOutcomes {
+        let finals = self.finals(cards, upcard);
+        let blackjack = if peek {
+            0.0
+        } else {
+            natural_probability(cards, upcard)
+        };
+
+        let mut totals = [0.0; 5];
+        for (total, p) in totals.iter_mut().zip(finals) {
+            *total = (1.0 - blackjack) * p;
+        }
+
+        DealerOutcomes {
+            totals,
+            blackjack,
+            bust: (1.0 - blackjack) * finals[5..].iter().sum::<f64>(),
+        }
+    }
+}
+
+/// Returns the probability of the dealer's holecard making a natural with an
+/// upcard, drawn from `cards`.
+pub fn natural_probability(cards: &CardCounts, upcard: Card) -> f64 {
+    natural_holecard(upcard).map_or(0.0, |natural| {
+        cards[natural] as f64 / cards.iter().sum::<u16>() as f64
+    })
+}
+
+/// Returns the index of the holecard making a natural with an upcard.
+pub fn natural_holecard(upcard: Card) -> Option<usize> {
+    match upcard.0 {
+        1 => Some(9),
+        10 => Some(0),
+        _ => None,
+    }
+}
+
+/// Returns all the hands the dealer stands on from an upcard, but naturals.
+fn dealer_hands(hits_soft17: bool, up: u8) -> Vec<DealerHand> {
+    fn draw(hits_soft17: bool, cards: &mut CardCounts, hole: usize, sum: u8,
+            ace: bool, hands: &mut HashMap<CardCounts, ([f64; 10], usize)>) {
+        let value = value_of(sum, ace);
+        if value >= 17 && !(hits_soft17 && value == 17 && value != sum) {
+            hands.entry(*cards).or_insert(([0.0; 10], value as usize - 17))
+                .0[hole] += 1.0;
+            return;
+        }
+        for card in 0..10 {
+            cards[card] += 1;
+            draw(hits_soft17, cards, hole, sum + card as u8 + 1,
+                 ace || card == 0, hands);
+            cards[card] -= 1;
+        }
+    }
+
+    let mut hands = HashMap::new();
+    for hole in 0..10 {
+        if Some(hole) != natural_holecard(Card(up)) {
+            let mut cards = [0; 10];
+            cards[hole] = 1;
+            draw(hits_soft17, &mut cards, hole, up + hole as u8 + 1,
+                 up == 1 || hole == 0, &mut hands);
+        }
+    }
+
+    hands.into_iter().map(|(cards, (sequences, total))| DealerHand {
+        cards: cards.iter().enumerate()
+            .filter(|(_, &n)| n > 0)
+            .map(|(card, &n)| (card, n))
+            .collect(),
+        count: cards.iter().sum::<u16>() as usize,
+        sequences,
+        total,
+    }).collect()
+}
+
+/// Returns the value of cards summing to `sum` with aces as 1.
+pub(crate) fn value_of(sum: u8, ace: bool) -> u8 {
+    if ace && sum + 10 <= 21 { sum + 10 } else { sum }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dealer_probabilities::{DealerProbabilities, shoe_counts,
+                                      natural_probability};
+    use crate::game_rules::Soft17::{S17, H17};
+    use crate::card::Card;
+    use crate::test_utils::assert_f64_eq;
+
+    #[test]
+    fn it_computes_the_dealer_finals() {
+        let probabilities = DealerProbabilities::new(&S17);
+
+        // A single card left, a 10
+        let mut cards = [0; 10];
+        cards[9] = 1;
+        assert_eq!(probabilities.finals(&cards, Card(7)),
+                   [1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0]);
+
+        // An ace, a 5 and a 6 left, the ace is not the holecard
+        let mut cards = [0; 10];
+        cards[0] = 1;
+        cards[4] = 1;
+        cards[5] = 1;
+        let finals = probabilities.finals(&cards, Card(10));
+        assert_f64_eq(finals[0], 0.25, 1e-12);  // 10-6-A
+        assert_f64_eq(finals[4], 0.5, 1e-12);   // 10-5-6, 10-6-5
+        assert_f64_eq(finals[5], 0.25, 1e-12);  // 10-5-A-6
+    }
+
+    #[test]
+    fn it_computes_the_finals_of_a_holecard() {
+        let probabilities = DealerProbabilities::new(&S17);
+        let mut cards = shoe_counts(1);
+        cards[9] -= 1;
+
+        // A dealer 16 busts unless they draw an ace to a five
+        let finals = probabilities.holecard_finals(&cards, Card(10), Card(6));
+        for p in &finals[..5] {
+            assert_f64_eq(*p, 4.0 / 50.0, 1e-12);
+        }
+        assert_f64_eq(finals[5..].iter().sum(), 30.0 / 50.0, 1e-12);
+
+        // The holecards add up to the dealer's finals from the upcard
+        let mut sum = [0.0; 10];
+        for hole in 2..=10 {
+            let finals = probabilities.holecard_finals(&cards, Card(10),
+                                                       Card(hole));
+            for (total, p) in sum.iter_mut().zip(finals) {
+                *total += cards[hole as usize - 1] as f64 / 47.0 * p;
+            }
+        }
+        let finals = probabilities.finals(&cards, Card(10));
+        for (total, p) in sum.iter().zip(finals) {
+            assert_f64_eq(*total, p, 1e-12);
+        }
+
+        // A soft 17
+        let mut cards = shoe_counts(6);
+        cards[0] -= 1;
+        let finals = probabilities.holecard_finals(&cards, Card(1), Card(6));
+        assert_eq!(finals[0], 1.0);
+        let finals = DealerProbabilities::new(&H17)
+            .holecard_finals(&cards, Card(1), Card(6));
+        assert!(finals[0] < 0.5);
+        assert_f64_eq(finals.iter().sum(), 1.0, 1e-12);
+    }
+
+    #[test]
+    fn it_computes_the_dealer_outcomes() {
+        let cards = shoe_counts(6);
+        for soft17 in [S17, H17] {
+            let probabilities = DealerProbabilities::new(&soft17);
+            for up in 1..=10 {
+                let outcomes = probabilities.outcomes(&cards, Card(up), false);
+                let sum = outcomes.totals.iter().sum::<f64>()
+                    + outcomes.blackjack + outcomes.bust;
+                assert_f64_eq(sum, 1.0, 1e-12);
+            }
+        }
+
+        let mut cards = shoe_counts(1);
+        cards[5] -= 1;
+        let s17 = DealerProbabilities::new(&S17).outcomes(&cards, Card(6),
+                                                           true);
+        let h17 = DealerProbabilities::new(&H17).outcomes(&cards, Card(6),
+                                                           true);
+        assert!(h17.totals[0] < s17.totals[0]);
+        assert!(h17.bust > s17.bust);
+        assert_f64_eq(s17.bust, 0.420823033989383, 1e-12);
+        assert_f64_eq(h17.bust, 0.437755884218590, 1e-12);
+
+        let mut cards = shoe_counts(1);
+        cards[0] -= 1;
+        let outcomes = DealerProbabilities::new(&S17)
+            .outcomes(&cards, Card(1), false);
+        assert_f64_eq(outcomes.blackjack, 16.0 / 51.0, 1e-12);
+        assert_eq!(outcomes.blackjack, natural_probability(&cards, Card(1)));
+        let outcomes = DealerProbabilities::new(&S17)
+            .outcomes(&cards, Card(1), true);
+        assert_eq!(outcomes.blackjack, 0.0);
+    }
+}
@@ -1,20 +1,45 @@
+use std::fmt;
 use std::str::FromStr;
 use regex::Regex;
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+use serde::de::Error;
 
-#[derive(Debug, PartialEq)]
+use crate::card::Card;
+use crate::hand::Hand;
+
+#[derive(Debug, PartialEq, Clone)]
 pub enum DevOverride {
     AboveEqual(f32, u8),
     UnderEqual(f32, u8),
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum DeviationTable {
     HardTable,
     SoftTable,
     PairTable,
 }
 
-#[derive(Debug, PartialEq)]
+/// Returns a two-card hand of a strategy table's row.
+pub fn row_hand(table: &DeviationTable, row: u8) -> Hand {
+    let cards = match table {
+        DeviationTable::HardTable => {
+            let total = 20 - row;
+            let low = total.saturating_sub(10).max(2);
+            [total - low, low]
+        },
+        DeviationTable::SoftTable => [1, 10 - row],
+        DeviationTable::PairTable => [10 - row; 2],
+    };
+
+    let mut hand = Hand::new();
+    for card in cards {
+        hand.add(Card(card));
+    }
+    hand
+}
+
+#[derive(Debug, PartialEq, Clone)]
 pub struct Deviation {
     pub table: DeviationTable,
     pub row: u8,
@@ -22,11 +47,11 @@ pub struct Deviation {
     pub action: DevOverride,
 }
 
-impl ToString for DevOverride {
-    fn to_string(&self) -> String {
+impl fmt::Display for DevOverride {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Self::AboveEqual(tc, a) => format!(">{:+}{}", tc, *a as char),
-            Self::UnderEqual(tc, a) => format!("<{:+}{}", tc, *a as char),
+            Self::AboveEqual(tc, a) => write!(f, ">{:+}{}", tc, *a as char),
+            Self::UnderEqual(tc, a) => write!(f, "<{:+}{}", tc, *a as char),
         }
     }
 }
@@ -47,7 +72,7 @@ impl FromStr for DevOverride {
             }
             let action = action[0];
 
-            if !"+=DdV*?@h&SsUuEe".as_bytes().into_iter().any(|&c| c == action) {
+            if !b"+=DdV*?@h&SsUuEe".contains(&action) {
                 return Err(String::from("Invalid action"));
             }
 
@@ -62,6 +87,44 @@ impl FromStr for DevOverride {
     }
 }
 
+impl fmt::Display for Deviation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.table {
+            DeviationTable::HardTable => write!(f, "{}", 20 - self.row)?,
+            DeviationTable::SoftTable if self.row == 9 => write!(f, "AA")?,
+            DeviationTable::SoftTable => write!(f, "A{}", 10 - self.row)?,
+            DeviationTable::PairTable if self.row == 9 => write!(f, "A/A")?,
+            DeviationTable::PairTable if self.row == 0 => write!(f, "T/T")?,
+            DeviationTable::PairTable => {
+                write!(f, "{0}/{0}", 10 - self.row)?
+            },
+        }
+
+        if self.dealer == 1 {
+            write!(f, "vsA:{}", self.action)
+        } else {
+            write!(f, "vs{}:{}", self.dealer, self.action)
+        }
+    }
+}
+
+/// Deviations are serialized with the same syntax as `--add-deviation`.
+impl Serialize for Deviation {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Deviation {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where D: Deserializer<'de> {
+        let s = String::deserialize(deserializer)?;
+        Deviation::from_str(&s)
+            .map_err(|e| D::Error::custom(format!("{}: {}", s, e)))
+    }
+}
+
 impl FromStr for Deviation {
     type Err = String;
 
@@ -82,7 +145,7 @@ impl FromStr for Deviation {
                 } else {
                     let pair: u8 = pair.parse()
                         .map_err(|_| String::from("Invalid syntax"))?;
-                    if pair < 1 || pair > 10 {
+                    if !(1..=10).contains(&pair) {
                         return Err(String::from("Invalid pair"));
                     }
                     row = 10 - pair;
@@ -94,7 +157,7 @@ impl FromStr for Deviation {
                 } else {
                     let card: u8 = c[1][1..].parse()
                         .map_err(|_| String::from("Invalid syntax"))?;
-                    if card < 1 || card > 10 {
+                    if !(1..=10).contains(&card) {
                         return Err(String::from("Invalid soft total"));
                     }
                     row = 10 - card;
@@ -103,7 +166,7 @@ impl FromStr for Deviation {
                 table = DeviationTable::HardTable;
                 let total: u8 = c[1].parse()
                     .map_err(|_| String::from("Invalid syntax"))?;
-                if total > 21 || total < 4 {
+                if !(4..=21).contains(&total) {
                     return Err(String::from("Invalid hard total"));
                 }
                 row = 20 - total;
@@ -134,7 +197,8 @@ impl FromStr for Deviation {
 #[cfg(test)]
 mod tests {
     use crate::deviation::DevOverride::*;
-    use crate::deviation::{DevOverride, Deviation};
+    use crate::deviation::{DevOverride, Deviation, row_hand};
+    use crate::hand::Hand;
     use crate::deviation::DeviationTable::*;
     use std::str::FromStr;
 
@@ -234,4 +298,24 @@ mod tests {
                        action: UnderEqual(1.0, b'D')
                    }));
     }
+
+    #[test]
+    fn it_formats_deviation() {
+        for dev in &["16vs10:>+1=", "4vsA:>-1=", "A6vs8:>-1=", "AAvs8:>-1=",
+                     "7/7vs7:<+1D", "A/Avs7:<+1D", "T/Tvs7:<-0.5D"] {
+            assert_eq!(Deviation::from_str(dev).unwrap().to_string(), *dev);
+        }
+    }
+
+    #[test]
+    fn it_makes_the_hand_of_a_row() {
+        assert_eq!(row_hand(&HardTable, 4).to_string(),
+                   Hand::from(&[10, 6][..]).to_string());
+        assert_eq!(row_hand(&HardTable, 12).to_string(),
+                   Hand::from(&[6, 2][..]).to_string());
+        assert_eq!(row_hand(&SoftTable, 3).to_string(),
+                   Hand::from(&[1, 7][..]).to_string());
+        assert_eq!(row_hand(&PairTable, 9).to_string(),
+                   Hand::from(&[1, 1][..]).to_string());
+    }
 }
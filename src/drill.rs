@@ -0,0 +1,459 @@
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+use rand::Rng;
+use serde::{Serialize, Deserialize};
+
+use freebj::basic_strategy::BasicStrategy;
+use freebj::card::Card;
+use freebj::deviation::{Deviation, DeviationTable, DevOverride, row_hand};
+use freebj::game_rules::GameRules;
+use freebj::shoe::CardShoe;
+use freebj::strategy::{Strategy, GameContext, Decision};
+
+/// A training drill.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Drill {
+    /// Keep the running count of cards flashed from the shoe
+    Count,
+    /// Convert running counts to true counts
+    TrueCount,
+    /// Play the hands of the strategy's deviations at various true counts
+    Deviations,
+}
+
+/// The accuracy and speed of the answers to a drill.
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Clone, Copy)]
+#[serde(default, deny_unknown_fields)]
+pub struct DrillRecord {
+    pub sessions:   u64,
+    pub questions:  u64,
+    pub correct:    u64,
+    /// The time taken to answer all the questions
+    pub seconds:    f64,
+}
+
+/// The records of each drill over all sessions, kept in a local file.
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct DrillStats {
+    pub count:      DrillRecord,
+    pub true_count: DrillRecord,
+    pub deviations: DrillRecord,
+}
+
+impl DrillRecord {
+    fn add(&mut self, session: &DrillRecord) {
+        self.sessions += session.sessions;
+        self.questions += session.questions;
+        self.correct += session.correct;
+        self.seconds += session.seconds;
+    }
+}
+
+impl DrillStats {
+    /// Loads the records from `path`, or empty records if it does not exist.
+    pub fn load(path: &Path) -> Result<DrillStats, String> {
+        match fs::read_to_string(path) {
+            Ok(json) => serde_json::from_str(&json)
+                .map_err(|e| format!("{}: {}", path.display(), e)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                Ok(DrillStats::default())
+            },
+            Err(e) => Err(format!("{}: {}", path.display(), e)),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self).unwrap();
+        fs::write(path, json + "\n")
+            .map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// Adds a session of a drill to its records, and returns them.
+    pub fn add(&mut self, drill: Drill, session: &DrillRecord) -> DrillRecord {
+        let record = match drill {
+            Drill::Count => &mut self.count,
+            Drill::TrueCount => &mut self.true_count,
+            Drill::Deviations => &mut self.deviations,
+        };
+        record.add(session);
+        *record
+    }
+}
+
+/// A session of questions asked on `input` and answered on `output`.
+pub struct DrillSession<'a> {
+    input:  &'a mut dyn BufRead,
+    output: &'a mut dyn Write,
+    record: DrillRecord,
+}
+
+impl<'a> DrillSession<'a> {
+    pub fn new(input: &'a mut dyn BufRead,
+               output: &'a mut dyn Write) -> DrillSession<'a> {
+        DrillSession {
+            input,
+            output,
+            record: DrillRecord {
+                sessions: 1,
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Asks a question until `parse` accepts the answer, then records
+    /// whether it is `expected`; returns `false` when the player quits, with
+    /// q or by ending the input.
+    fn ask<T, F>(&mut self,
+                 question: &str,
+                 parse: F,
+                 expected: T,
+                 explanation: &str) -> io::Result<bool>
+        where T: PartialEq, F: Fn(&str) -> Option<T> {
+        let start = Instant::now();
+
+        let answer = loop {
+            write!(self.output, "{} ", question)?;
+            self.output.flush()?;
+
+            let mut line = String::new();
+            if self.input.read_line(&mut line)? == 0 {
+                writeln!(self.output)?;
+                return Ok(false);
+            }
+
+            let line = line.trim();
+            if line.eq_ignore_ascii_case("q") {
+                return Ok(false);
+            }
+            if let Some(answer) = parse(line) {
+                break answer;
+            }
+        };
+
+        let correct = answer == expected;
+        self.record.questions += 1;
+        self.record.correct += correct as u64;
+        self.record.seconds += start.elapsed().as_secs_f64();
+
+        if correct {
+            writeln!(self.output, "Correct.")?;
+        } else {
+            writeln!(self.output, "Wrong, {}.", explanation)?;
+        }
+
+        Ok(true)
+    }
+
+    /// Ends the session, and returns its record.
+    pub fn finish(self) -> DrillRecord {
+        self.record
+    }
+}
+
+/// Flashes cards from the shoe, a few at a time, and asks for the hi-lo
+/// running count since the shoe was shuffled. Each card is shown for `flash`,
+/// the cards of a question are shown together when it is zero.
+pub fn count_drill(session: &mut DrillSession,
+                   shoe: &mut dyn CardShoe,
+                   rng: &mut impl Rng,
+                   flash: Duration) -> io::Result<()> {
+    loop {
+        let cards: Vec<Card> = (0..rng.gen_range(2..=6))
+            .map(|_| shoe.pick())
+            .collect();
+
+        if flash.is_zero() {
+            let cards: Vec<String> = cards.iter()
+                .map(Card::to_string)
+                .collect();
+            writeln!(session.output, "{}", cards.join(" "))?;
+        } else {
+            for card in cards.iter() {
+                write!(session.output, "\r{:>2}", card)?;
+                session.output.flush()?;
+                sleep(flash);
+                write!(session.output, "\r  ")?;
+                session.output.flush()?;
+                sleep(flash / 4);
+            }
+            write!(session.output, "\r")?;
+        }
+
+        let running_count = shoe.running_count();
+        if !session.ask("Running count?", parse_count, running_count,
+                        &format!("the running count is {:+}", running_count))? {
+            return Ok(());
+        }
+
+        if shoe.needs_reshuffle() {
+            shoe.reshuffle();
+            writeln!(session.output, "The shoe is reshuffled, the count \
+                                      starts over.")?;
+        }
+    }
+}
+
+/// Deals cards from the shoe, and asks for the true count of the running
+/// count and the decks left, estimated to the half deck; the true count is
+/// rounded, as for the deviations.
+pub fn true_count_drill(session: &mut DrillSession,
+                        shoe: &mut dyn CardShoe,
+                        rng: &mut impl Rng) -> io::Result<()> {
+    loop {
+        for _ in 0..rng.gen_range(5..=40) {
+            shoe.pick();
+        }
+        if shoe.needs_reshuffle() {
+            shoe.reshuffle();
+            continue;
+        }
+
+        let cards_left = shoe.cards_left()
+            .expect("The true count drill requires a standard shoe");
+        let decks = ((cards_left as f32 / 26.0).round() / 2.0).max(0.5);
+        let running_count = shoe.running_count();
+        let true_count = (running_count as f32 / decks).round() as i32;
+
+        let question = format!("Running count {:+}, {} deck(s) left: true \
+                                count?", running_count, decks);
+        if !session.ask(&question, parse_count, true_count,
+                        &format!("the true count is {:+} ({:+.2})",
+                                 true_count, running_count as f32 / decks))? {
+            return Ok(());
+        }
+    }
+}
+
+/// Shows the hands of the strategy's deviations at true counts around their
+/// index, and asks for the strategy's decision; surrender deviations are not
+/// asked.
+pub fn deviation_drill(session: &mut DrillSession,
+                       rules: &GameRules,
+                       strategy: &BasicStrategy,
+                       rng: &mut impl Rng) -> io::Result<()> {
+    let deviations: Vec<Deviation> = strategy.deviations().into_iter()
+        .filter(|dev| match dev.action {
+            DevOverride::AboveEqual(_, action)
+            | DevOverride::UnderEqual(_, action) => {
+                !b"SsUuEe".contains(&action)
+            },
+        })
+        .collect();
+    if deviations.is_empty() {
+        writeln!(session.output, "No deviations to drill, see --deviations \
+                                  and -D.")?;
+        return Ok(());
+    }
+
+    loop {
+        let dev = &deviations[rng.gen_range(0..deviations.len())];
+        let index = match dev.action {
+            DevOverride::AboveEqual(tc, _) | DevOverride::UnderEqual(tc, _) => {
+                tc.round() as i32
+            },
+        };
+        let true_count = rng.gen_range(index - 3..=index + 3);
+
+        let hand = row_hand(&dev.table, dev.row);
+        let dealer = Card(dev.dealer);
+        let game = GameContext {
+            rules,
+            may_split: dev.table == DeviationTable::PairTable,
+            may_double: true,
+            true_count: true_count as f32,
+            holecard: None,
+            hole_tell: None,
+            next_card: None,
+        };
+        let expected = strategy.player_turn(&game, dealer, &hand);
+
+        let cards: Vec<String> = hand.iter().map(Card::to_string).collect();
+        let mut question = format!("{} against {} at true count {:+}: [h]it, \
+                                    [s]tand, [d]ouble", cards.join(" "),
+                                   dealer, true_count);
+        if game.may_split {
+            question += ", s[p]lit";
+        }
+        let explanation = format!("the strategy would {} ({})",
+                                  match expected {
+                                      Decision::Hit => "hit",
+                                      Decision::Stand => "stand",
+                                      Decision::Double => "double down",
+                                      Decision::Split => "split",
+                                  }, dev);
+
+        if !session.ask(&(question + "?"), parse_decision, expected,
+                        &explanation)? {
+            return Ok(());
+        }
+    }
+}
+
+/// Writes the record of the session, and of all the sessions of the drill.
+pub fn report(output: &mut dyn Write,
+              session: &DrillRecord,
+              total: &DrillRecord) -> io::Result<()> {
+    for (name, record) in [("This session", session), ("All sessions", total)] {
+        if record.questions == 0 {
+            writeln!(output, "{}: no question answered", name)?;
+            continue;
+        }
+
+        writeln!(output, "{}: {} of {} correct ({:.1} %), {:.1} s per \
+                          answer", name, record.correct, record.questions,
+                 record.correct as f64 * 100.0 / record.questions as f64,
+                 record.seconds / record.questions as f64)?;
+    }
+
+    Ok(())
+}
+
+fn parse_count(answer: &str) -> Option<i32> {
+    answer.parse().ok()
+}
+
+fn parse_decision(answer: &str) -> Option<Decision> {
+    match answer.to_ascii_lowercase().as_str() {
+        "h" => Some(Decision::Hit),
+        "s" => Some(Decision::Stand),
+        "d" => Some(Decision::Double),
+        "p" => Some(Decision::Split),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::drill::{DrillSession, DrillRecord, DrillStats, Drill,
+                       count_drill, true_count_drill, deviation_drill};
+    use freebj::basic_strategy::BasicStrategy;
+    use freebj::deviation::Deviation;
+    use freebj::game_rules::GameRules;
+    use freebj::shoe::standard_shoe::StandardShoe;
+    use freebj::shoe::CardShoe;
+    use rand::rngs::SmallRng;
+    use rand::SeedableRng;
+    use std::str::FromStr;
+    use std::time::Duration;
+
+    #[test]
+    fn it_drills_the_running_count() {
+        let mut shoe = StandardShoe::seeded(6, 250, 42);
+        let mut rng = SmallRng::seed_from_u64(1);
+        let mut input = "x\n0\nq\n".as_bytes();
+        let mut output = Vec::new();
+
+        let mut session = DrillSession::new(&mut input, &mut output);
+        count_drill(&mut session, &mut shoe, &mut rng, Duration::ZERO)
+            .unwrap();
+        let record = session.finish();
+
+        let output = String::from_utf8(output).unwrap();
+        let expected = shoe.running_count();
+        assert_eq!(record.questions, 1);
+        assert_eq!(record.correct, (expected == 0) as u64);
+        assert_eq!(output.matches("Running count?").count(), 3);
+        if expected != 0 {
+            assert!(output.contains(&format!("Wrong, the running count is {:+}",
+                                             expected)));
+        }
+    }
+
+    #[test]
+    fn it_drills_the_true_count() {
+        let mut shoe = StandardShoe::seeded(2, 80, 7);
+        let mut rng = SmallRng::seed_from_u64(3);
+        let mut input = "".as_bytes();
+        let mut output = Vec::new();
+
+        let mut session = DrillSession::new(&mut input, &mut output);
+        true_count_drill(&mut session, &mut shoe, &mut rng).unwrap();
+        assert_eq!(session.finish().questions, 0);
+
+        let output = String::from_utf8(output).unwrap();
+        let decks = ((shoe.cards_left().unwrap() as f32 / 26.0).round()
+                     / 2.0).max(0.5);
+        assert!(output.starts_with(&format!(
+            "Running count {:+}, {} deck(s) left: true count?",
+            shoe.running_count(), decks)));
+    }
+
+    #[test]
+    fn it_drills_the_deviations() {
+        let rules = GameRules::default();
+        let mut strategy = BasicStrategy::new(true);
+        strategy.add_deviation(Deviation::from_str("16vs10:>+0=").unwrap());
+        strategy.add_deviation(Deviation::from_str("16vs9:>+4S").unwrap());
+        let mut rng = SmallRng::seed_from_u64(5);
+        let mut input = "s\nh\ns\nh\n".as_bytes();
+        let mut output = Vec::new();
+
+        let mut session = DrillSession::new(&mut input, &mut output);
+        deviation_drill(&mut session, &rules, &strategy, &mut rng).unwrap();
+        let record = session.finish();
+
+        // Only 16vs10 is drilled, standing from a true count of 0
+        let output = String::from_utf8(output).unwrap();
+        let true_counts: Vec<i32> = output
+            .split("10 6 against 10 at true count ")
+            .skip(1)
+            .map(|rest| rest[..rest.find(':').unwrap()].parse().unwrap())
+            .collect();
+        let correct = true_counts.iter()
+            .zip([true, false, true, false])
+            .filter(|&(&tc, stand)| (tc >= 0) == stand)
+            .count();
+        // The fifth question is left unanswered
+        assert_eq!(true_counts.len(), 5);
+        assert_eq!(record.questions, 4);
+        assert_eq!(record.correct, correct as u64);
+    }
+
+    #[test]
+    fn it_needs_deviations_to_drill() {
+        let rules = GameRules::default();
+        let strategy = BasicStrategy::new(true);
+        let mut rng = SmallRng::seed_from_u64(5);
+        let mut input = "".as_bytes();
+        let mut output = Vec::new();
+
+        let mut session = DrillSession::new(&mut input, &mut output);
+        deviation_drill(&mut session, &rules, &strategy, &mut rng).unwrap();
+        assert_eq!(session.finish().questions, 0);
+
+        assert_eq!(String::from_utf8(output).unwrap(),
+                   "No deviations to drill, see --deviations and -D.\n");
+    }
+
+    #[test]
+    fn it_keeps_records_across_sessions() {
+        let mut path = std::env::temp_dir();
+        path.push(format!("freebj-drills-{}.json", std::process::id()));
+
+        let session = DrillRecord {
+            sessions: 1,
+            questions: 10,
+            correct: 8,
+            seconds: 20.0,
+        };
+        let mut stats = DrillStats::load(&path).unwrap();
+        assert_eq!(stats, DrillStats::default());
+        stats.add(Drill::Count, &session);
+        stats.save(&path).unwrap();
+
+        let mut stats = DrillStats::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let total = stats.add(Drill::Count, &session);
+        assert_eq!(total, DrillRecord {
+            sessions: 2,
+            questions: 20,
+            correct: 16,
+            seconds: 40.0,
+        });
+        assert_eq!(stats.true_count, DrillRecord::default());
+    }
+}
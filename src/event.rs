@@ -0,0 +1,187 @@
+use std::io::{self, Write};
+use std::sync::Mutex;
+use serde::Serialize;
+
+use crate::card::Card;
+use crate::hand::Hand;
+use crate::hand_logic::HandOutcome;
+use crate::strategy::Decision;
+
+/// An event of a round, recorded when the round keeps an event log (see
+/// `Round::with_event_log()`), to audit the engine and the strategies.
+#[derive(Serialize, Debug, PartialEq, Clone)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    /// The state of the shoe before the round is dealt
+    RoundStart {
+        running_count:  i32,
+        true_count:     f32,
+        /// The number of cards left in the shoe, if known
+        cards_left:     Option<usize>,
+    },
+    /// The bet and first two cards of a spot
+    Deal {
+        player:         u8,
+        spot:           u8,
+        bet:            f64,
+        cards:          [Card; 2],
+    },
+    /// The dealer's upcard, and their holecard in AHC games
+    DealerDeal {
+        upcard:         Card,
+        holecard:       Option<Card>,
+    },
+    /// An action taken on a hand, and the context it was decided in
+    Decision {
+        player:         u8,
+        spot:           u8,
+        /// The hand's index in the round, split hands come after the spots
+        hand:           usize,
+        cards:          Vec<Card>,
+        value:          u8,
+        soft:           bool,
+        dealer:         Card,
+        action:         Action,
+        true_count:     f32,
+        may_double:     bool,
+        may_split:      bool,
+        /// Whether the action comes from a script rather than the strategy
+        scripted:       bool,
+    },
+    /// A card drawn by the dealer after their first two
+    DealerDraw {
+        card:           Card,
+        value:          u8,
+        soft:           bool,
+    },
+    /// The settlement of a hand, `result` including the insurance
+    Settle {
+        player:         u8,
+        spot:           u8,
+        hand:           usize,
+        cards:          Vec<Card>,
+        value:          u8,
+        stake:          f64,
+        outcome:        HandOutcome,
+        result:         f64,
+        insurance:      f64,
+    },
+    /// The end of the round
+    RoundEnd {
+        dealer:         Vec<Card>,
+        reshuffled:     bool,
+    },
+}
+
+/// An action of the player, the decisions on insurance and surrender included.
+#[derive(Serialize, Debug, PartialEq, Copy, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Hit,
+    Stand,
+    Double,
+    Split,
+    Surrender,
+    Insure,
+    EvenMoney,
+}
+
+impl From<Decision> for Action {
+    fn from(decision: Decision) -> Self {
+        match decision {
+            Decision::Hit => Action::Hit,
+            Decision::Stand => Action::Stand,
+            Decision::Double => Action::Double,
+            Decision::Split => Action::Split,
+        }
+    }
+}
+
+/// Returns the cards of a hand, for events.
+pub fn cards_of(hand: &Hand) -> Vec<Card> {
+    hand.iter().copied().collect()
+}
+
+/// A JSON Lines event log, shared by the simulation jobs: the events of each
+/// round are written at once, one JSON object per line, along with the job
+/// and the round number in the job.
+pub struct EventLog {
+    writer: Mutex<Box<dyn Write + Send>>,
+}
+
+#[derive(Serialize)]
+struct LogLine<'a> {
+    job: u32,
+    round: u64,
+    #[serde(flatten)]
+    event: &'a Event,
+}
+
+impl EventLog {
+    pub fn new(writer: Box<dyn Write + Send>) -> EventLog {
+        EventLog {
+            writer: Mutex::new(writer),
+        }
+    }
+
+    /// Writes the events of a round.
+    pub fn write_round(&self, job: u32, round: u64, events: &[Event])
+        -> io::Result<()> {
+        let mut writer = self.writer.lock().unwrap();
+
+        for event in events {
+            serde_json::to_writer(&mut *writer, &LogLine { job, round, event })?;
+            writer.write_all(b"\n")?;
+        }
+
+        Ok(())
+    }
+
+    pub fn flush(&self) -> io::Result<()> {
+        self.writer.lock().unwrap().flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::event::{Event, EventLog, Action};
+    use crate::card::Card;
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+
+    /// A writer into a buffer which outlives the log.
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn it_writes_json_lines() {
+        let buffer = Arc::new(Mutex::new(Vec::new()));
+        let log = EventLog::new(Box::new(SharedBuffer(buffer.clone())));
+
+        log.write_round(2, 7, &[
+            Event::DealerDeal { upcard: Card(1), holecard: None },
+            Event::DealerDraw { card: Card(10), value: 21, soft: true },
+        ]).unwrap();
+
+        let text = String::from_utf8(buffer.lock().unwrap().clone()).unwrap();
+        assert_eq!(text, "\
+{\"job\":2,\"round\":7,\"event\":\"dealer_deal\",\"upcard\":1,\"holecard\":null}
+{\"job\":2,\"round\":7,\"event\":\"dealer_draw\",\"card\":10,\"value\":21,\"soft\":true}
+");
+    }
+
+    #[test]
+    fn it_serializes_actions() {
+        assert_eq!(serde_json::to_string(&Action::EvenMoney).unwrap(),
+                   "\"even_money\"");
+    }
+}
@@ -1,6 +1,8 @@
-use serde::Serialize;
+use serde::{Serialize, Deserialize};
 
-#[derive(Debug, PartialEq, Serialize)]
+use crate::card::Card;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum GameType {
     /// American holecard game
@@ -9,7 +11,23 @@ pub enum GameType {
     Enhc,
 }
 
-#[derive(Debug, PartialEq, Serialize)]
+/// Which upcards the dealer peeks under for a natural in an AHC game, ending
+/// the round before the players play their hands when they have one.
+#[derive(Debug, PartialEq, Copy, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PeekPolicy {
+    /// Peek under an ace or a ten
+    PeekAceTen,
+    /// Peek under an ace only
+    PeekAce,
+    /// Peek under a ten only
+    PeekTen,
+    /// Never peek, the holecard is dealt but only revealed after the players
+    /// played their hands
+    NoPeek,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum Soft17 {
     /// Hit soft 17 hands
@@ -18,7 +36,7 @@ pub enum Soft17 {
     H17,
 }
 
-#[derive(Debug, PartialEq, Serialize)]
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum SurrenderPolicy {
     /// Do not allow surrender
@@ -27,9 +45,12 @@ pub enum SurrenderPolicy {
     EarlySurrender,
     /// Allow late surrender (AHC only)
     LateSurrender,
+    /// Allow early surrender against a dealer ten only, and no surrender
+    /// against an ace (a common ENHC compromise)
+    EarlySurrenderTens,
 }
 
-#[derive(Debug, PartialEq, Copy, Clone, Serialize)]
+#[derive(Debug, PartialEq, Copy, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum DoublePolicy {
     /// Do not allow doubling-down for any hand
@@ -44,33 +65,221 @@ pub enum DoublePolicy {
     Hard10To11
 }
 
-#[derive(Serialize, Debug)]
+/// A Charlie rule: a player hand reaching a given number of cards without
+/// busting wins automatically, regardless of the dealer's hand (except a
+/// dealer natural).
+#[derive(Debug, PartialEq, Copy, Clone, Serialize, Deserialize)]
+pub struct CharlieRule {
+    /// The number of cards a hand must reach, typically 5, 6, or 7
+    pub cards:          u8,
+    /// How much a Charlie pays for a bet of 1.0
+    pub pays:           f64,
+    /// Whether hands resulting from a pair split can make a Charlie
+    pub split_hands:    bool,
+}
+
+/// A dealer push rule: a dealer busting with exactly a given total (typically
+/// 22) pushes all standing player hands instead of losing to them.
+#[derive(Debug, PartialEq, Copy, Clone, Serialize, Deserialize)]
+pub struct DealerPushRule {
+    /// The dealer's busted total that pushes, typically 22
+    pub total:          u8,
+    /// Whether player naturals still win against the push total
+    pub except_naturals: bool,
+}
+
+/// The names of the rule-set presets for well-known tables, see
+/// `GameRules::preset()`.
+pub const PRESETS: [&str; 6] = [
+    "vegas-strip-6d",
+    "downtown-2d-h17",
+    "atlantic-city-8d",
+    "european-enhc-6d",
+    "uk-enhc-obo",
+    "single-deck-6to5",
+];
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
 pub struct GameRules {
+    /// The name of the preset the rules are based on, if any
+    pub preset:         Option<String>,
     pub game_type:      GameType,
+    /// The upcards the dealer peeks under, AHC only
+    pub peek:           PeekPolicy,
+    /// Whether the player only loses their original bet, not their doubles
+    /// and splits, to a dealer natural which was not peeked (OBO)
+    pub original_bets_only: bool,
     pub soft17:         Soft17,
     pub das:            bool,
     pub bj_pays:        f64,
+    /// Whether insurance is offered against a dealer ace
+    pub insurance:      bool,
+    /// Whether even money is offered to naturals against a dealer ace
+    pub even_money:     bool,
     pub double_down:    DoublePolicy,
+    /// The doubling-down policy for hands of three cards or more, unless
+    /// `double_down` already allows any hand
+    pub double_after_hit: DoublePolicy,
+    /// The maximum number of cards a hand may have to be doubled down
+    pub double_max_cards: Option<u8>,
+    /// Whether the player may double down for less than their stake
+    pub double_for_less: bool,
+    /// The number of times a doubled-down hand may be doubled again
+    pub max_redoubles:  u8,
     pub surrender:      SurrenderPolicy,
+    /// Whether the player may surrender after hitting, i.e. on any number of
+    /// cards, requires a surrender policy
+    pub surrender_any_cards: bool,
+    /// Whether the player may surrender split hands, requires a surrender
+    /// policy
+    pub surrender_after_split: bool,
+    /// Whether the player may surrender a doubled-down hand after receiving
+    /// its card, losing half its stake (i.e. the original bet when doubled
+    /// for the full amount): the double-down rescue
+    pub double_rescue:  bool,
     pub play_ace_pairs: bool,
     pub max_splits:     u32,
     pub decks:          u32,
     pub penetration_cards: u32,
+    pub charlie:        Option<CharlieRule>,
+    pub dealer_push:    Option<DealerPushRule>,
 }
 
 impl Default for GameRules {
     fn default() -> Self {
         GameRules {
+            preset:         None,
             game_type:      GameType::Ahc,
+            peek:           PeekPolicy::PeekAceTen,
+            original_bets_only: false,
             soft17:         Soft17::S17,
             das:            true,
             bj_pays:        1.5,
+            insurance:      true,
+            even_money:     true,
             double_down:    DoublePolicy::AnyTwo,
+            double_after_hit: DoublePolicy::NoDouble,
+            double_max_cards: None,
+            double_for_less: false,
+            max_redoubles:  0,
             surrender:      SurrenderPolicy::NoSurrender,
+            surrender_any_cards: false,
+            surrender_after_split: false,
+            double_rescue:  false,
             play_ace_pairs: false,
             max_splits:     4,
             decks:          6,
             penetration_cards: 5 * 52,
+            charlie:        None,
+            dealer_push:    None,
         }
     }
 }
+
+impl GameRules {
+    /// Returns the complete rules of a well-known table from its preset name,
+    /// one of `PRESETS`, or `None` if there is no such preset.
+    pub fn preset(name: &str) -> Option<GameRules> {
+        let preset = *PRESETS.iter().find(|&&preset| preset == name)?;
+        let base = GameRules {
+            preset: Some(preset.to_string()),
+            ..GameRules::default()
+        };
+
+        let rules = match preset {
+            "vegas-strip-6d" => GameRules {
+                surrender: SurrenderPolicy::LateSurrender,
+                penetration_cards: 5 * 52,
+                ..base
+            },
+            "downtown-2d-h17" => GameRules {
+                soft17: Soft17::H17,
+                decks: 2,
+                penetration_cards: 78,
+                ..base
+            },
+            "atlantic-city-8d" => GameRules {
+                surrender: SurrenderPolicy::LateSurrender,
+                decks: 8,
+                penetration_cards: 6 * 52 + 26,
+                ..base
+            },
+            "european-enhc-6d" => GameRules {
+                game_type: GameType::Enhc,
+                double_down: DoublePolicy::Hard9To11,
+                max_splits: 2,
+                penetration_cards: 4 * 52 + 26,
+                ..base
+            },
+            "uk-enhc-obo" => GameRules {
+                game_type: GameType::Enhc,
+                original_bets_only: true,
+                double_down: DoublePolicy::Hard9To11,
+                max_splits: 3,
+                penetration_cards: 4 * 52 + 26,
+                ..base
+            },
+            "single-deck-6to5" => GameRules {
+                soft17: Soft17::H17,
+                das: false,
+                bj_pays: 1.2,
+                decks: 1,
+                penetration_cards: 26,
+                ..base
+            },
+            _ => unreachable!(),
+        };
+
+        Some(rules)
+    }
+
+    /// Returns whether the dealer peeks for a natural under a given upcard.
+    pub fn dealer_peeks(&self, upcard: Card) -> bool {
+        self.game_type == GameType::Ahc && match self.peek {
+            PeekPolicy::PeekAceTen => upcard == Card(1) || upcard == Card(10),
+            PeekPolicy::PeekAce => upcard == Card(1),
+            PeekPolicy::PeekTen => upcard == Card(10),
+            PeekPolicy::NoPeek => false,
+        }
+    }
+
+    /// Returns whether the player may surrender before the dealer checks for
+    /// a natural under a given upcard.
+    pub fn surrenders_early(&self, upcard: Card) -> bool {
+        self.surrender == SurrenderPolicy::EarlySurrender
+            || (self.surrender == SurrenderPolicy::EarlySurrenderTens
+                && upcard == Card(10))
+    }
+
+    /// Returns whether doubles and splits are safe from a dealer natural with
+    /// a given upcard, either because the dealer peeks or because of OBO.
+    pub fn extra_bets_protected(&self, upcard: Card) -> bool {
+        self.dealer_peeks(upcard) || self.original_bets_only
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game_rules::{GameRules, GameType, Soft17, PRESETS};
+
+    #[test]
+    fn it_loads_presets() {
+        for &name in PRESETS.iter() {
+            let rules = GameRules::preset(name).unwrap();
+            assert_eq!(rules.preset.as_deref(), Some(name));
+            assert!(rules.penetration_cards < rules.decks * 52, "{}", name);
+        }
+
+        let rules = GameRules::preset("single-deck-6to5").unwrap();
+        assert_eq!(rules.decks, 1);
+        assert_eq!(rules.bj_pays, 1.2);
+        assert_eq!(rules.soft17, Soft17::H17);
+
+        let rules = GameRules::preset("uk-enhc-obo").unwrap();
+        assert_eq!(rules.game_type, GameType::Enhc);
+        assert!(rules.original_bets_only);
+
+        assert!(GameRules::preset("vegas").is_none());
+    }
+}
@@ -14,20 +14,35 @@ pub struct Hand {
     /// Owner's ID, useful when a player has many split hands
     pub id:         u8,
 
+    /// The spot the hand was dealt on, a player may play several spots
+    pub spot:       u8,
+
     /// The bet associated with the hand
     pub bet:        f64,
 
-    /// Whether this hand was doubled down
-    doubled:        bool,
+    /// The number of times this hand was doubled down, more than one when
+    /// re-doubled
+    doubles:        u8,
+
+    /// The stake of the hand relative to its bet, 1.0 unless doubled down
+    stake:          f64,
 
     /// Whether this hand is the result of a pair split
     split:          bool,
 
+    /// Whether this hand was split off another one, i.e. it is not the hand
+    /// the player started with
+    split_off:      bool,
+
     /// Whether this hand was surrendered or not
     surrendered:    bool,
 
-    /// Whether this hand was insured or not
-    insured:        bool,
+    /// The insurance bet relative to the hand's bet, up to 0.5; zero when the
+    /// hand was not insured
+    insurance:      f64,
+
+    /// Whether this natural was paid even money instead of being insured
+    even_money:     bool,
 }
 
 impl Hand {
@@ -39,11 +54,15 @@ impl Hand {
             is_soft:    false,
             is_busted:  false,
             id:         0,
+            spot:       0,
             bet:        1.0,
-            doubled:    false,
+            doubles:    0,
+            stake:      1.0,
             split:      false,
+            split_off:  false,
             surrendered: false,
-            insured:    false,
+            insurance:  0.0,
+            even_money: false,
         }
     }
 
@@ -89,14 +108,22 @@ impl Hand {
         self.value
     }
 
+    /// Doubles down the hand for its full current stake.
     pub fn double_down(&mut self) {
-        assert!(!self.doubled);
+        self.double_down_by(1.0);
+    }
+
+    /// Doubles down the hand for a ratio of its current stake; a ratio under
+    /// 1.0 doubles for less, doubling an already doubled hand re-doubles it.
+    pub fn double_down_by(&mut self, ratio: f64) {
+        assert!(ratio > 0.0 && ratio <= 1.0);
         assert!(!self.surrendered);
-        self.doubled = true;
+        self.doubles += 1;
+        self.stake += self.stake * ratio;
     }
 
     pub fn split(&mut self) -> Hand {
-        assert!(!self.doubled);
+        assert!(!self.is_doubled());
         assert!(!self.surrendered);
         assert!(!self.is_busted);
         assert_eq!(self.cards.len(), 2);
@@ -112,23 +139,43 @@ impl Hand {
 
         let mut new_hand = Hand::from(&[card][..]);
         new_hand.id = self.id;
+        new_hand.spot = self.spot;
         new_hand.bet = self.bet;
         new_hand.split = true;
+        new_hand.split_off = true;
 
         new_hand
     }
 
+    /// Surrenders the hand, which loses half its stake; whether the hand may
+    /// be surrendered after splitting, hitting, or doubling down depends on
+    /// the game rules.
     pub fn surrender(&mut self) {
         assert!(!self.surrendered);
-        assert!(!self.split);
-        assert!(!self.doubled);
+        assert!(!self.is_busted);
         self.surrendered = true;
     }
 
+    /// Insures the hand for half its bet.
     pub fn insure(&mut self) {
-        assert!(!self.insured);
+        self.insure_for(0.5);
+    }
+
+    /// Insures the hand for a ratio of its bet, up to 0.5 (full insurance);
+    /// less than that is partial insurance.
+    pub fn insure_for(&mut self, amount: f64) {
+        assert!(amount > 0.0 && amount <= 0.5);
+        assert!(!self.is_insured());
         assert!(!self.surrendered);
-        self.insured = true;
+        self.insurance = amount;
+    }
+
+    /// Takes even money on a natural against a dealer ace, which pays 1:1
+    /// whatever the dealer's hand; the hand then counts as fully insured.
+    pub fn take_even_money(&mut self) {
+        assert!(self.is_bj());
+        self.insure_for(0.5);
+        self.even_money = true;
     }
 
     /// Returns whether the hand is a natural blackjack or not
@@ -142,13 +189,36 @@ impl Hand {
     /// Returns whether the hand is soft
     pub fn is_soft(&self) -> bool { self.is_soft }
 
-    pub fn is_doubled(&self) -> bool { self.doubled }
+    pub fn is_doubled(&self) -> bool { self.doubles > 0 }
+
+    /// Returns the number of times the hand was doubled down
+    pub fn doubles(&self) -> u8 { self.doubles }
+
+    /// Returns the stake of the hand relative to its bet: 1.0 for a regular
+    /// hand, 2.0 when doubled down, less when doubled for less, and more when
+    /// re-doubled.
+    pub fn stake(&self) -> f64 { self.stake }
 
     pub fn is_split(&self) -> bool { self.split }
 
+    /// Returns whether the hand was split off another one, as opposed to being
+    /// the player's original hand
+    pub fn is_split_off(&self) -> bool { self.split_off }
+
     pub fn is_surrendered(&self) -> bool { self.surrendered }
 
-    pub fn is_insured(&self) -> bool { self.insured }
+    pub fn is_insured(&self) -> bool { self.insurance > 0.0 }
+
+    /// Returns the insurance bet relative to the hand's bet
+    pub fn insurance(&self) -> f64 { self.insurance }
+
+    pub fn is_even_money(&self) -> bool { self.even_money }
+}
+
+impl Default for Hand {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Index<usize> for Hand {
@@ -192,9 +262,13 @@ impl Display for Hand {
 
         write!(f, "] = {}", self.value)?;
 
-        if self.doubled { write!(f, ", doubled")?; }
+        if self.is_doubled() { write!(f, ", doubled")?; }
         if self.split { write!(f, ", split")?; }
-        if self.insured { write!(f, ", insured")?; }
+        if self.even_money {
+            write!(f, ", even money")?;
+        } else if self.is_insured() {
+            write!(f, ", insured")?;
+        }
         if self.surrendered { write!(f, ", surrendered")?; }
 
         Ok(())
@@ -301,6 +375,7 @@ mod tests {
     fn it_splits_88_pairs() {
         let mut hand = Hand::from(&[8, 8][..]);
         hand.id = 4;
+        hand.spot = 5;
         hand.bet = 40.0;
 
         let mut new = hand.split();
@@ -310,6 +385,7 @@ mod tests {
 
         assert_eq!(hand.id, 4);
         assert_eq!(new.id, 4);
+        assert_eq!(new.spot, 5);
         assert_eq!(hand.bet, 40.0);
         assert_eq!(new.bet, 40.0);
         assert!(hand.split);
@@ -339,6 +415,40 @@ mod tests {
         assert!(!hand.is_bj());
     }
 
+    #[test]
+    fn it_doubles_down() {
+        let mut hand = Hand::from(&[5, 6][..]);
+        assert_eq!(hand.stake(), 1.0);
+        assert!(!hand.is_doubled());
+
+        hand.double_down();
+        assert_eq!(hand.stake(), 2.0);
+        assert_eq!(hand.doubles(), 1);
+        assert!(hand.is_doubled());
+
+        hand.double_down();
+        assert_eq!(hand.stake(), 4.0);
+        assert_eq!(hand.doubles(), 2);
+    }
+
+    #[test]
+    fn it_doubles_down_for_less() {
+        let mut hand = Hand::from(&[5, 6][..]);
+        hand.double_down_by(0.5);
+        assert_eq!(hand.stake(), 1.5);
+        assert!(hand.is_doubled());
+
+        hand.double_down_by(0.5);
+        assert_eq!(hand.stake(), 2.25);
+    }
+
+    #[test]
+    #[should_panic]
+    fn it_panics_when_doubling_for_more() {
+        let mut hand = Hand::from(&[5, 6][..]);
+        hand.double_down_by(1.5);
+    }
+
     #[test]
     fn it_splits_insured_pairs() {
         let mut hand = Hand::from(&[8, 8][..]);
@@ -350,8 +460,33 @@ mod tests {
         hand.add(Card(10));
         new.add(Card(8));
 
-        assert!(hand.insured);
-        assert!(!new.insured);
+        assert!(hand.is_insured());
+        assert!(!new.is_insured());
+    }
+
+    #[test]
+    fn it_insures_for_less() {
+        let mut hand = Hand::from(&[10, 6][..]);
+        hand.insure_for(0.25);
+        assert!(hand.is_insured());
+        assert_eq!(hand.insurance(), 0.25);
+        assert!(!hand.is_even_money());
+    }
+
+    #[test]
+    fn it_takes_even_money() {
+        let mut hand = Hand::from(&[1, 10][..]);
+        hand.take_even_money();
+        assert!(hand.is_insured());
+        assert!(hand.is_even_money());
+        assert_eq!(hand.insurance(), 0.5);
+    }
+
+    #[test]
+    #[should_panic]
+    fn it_panics_when_insuring_for_more() {
+        let mut hand = Hand::from(&[10, 6][..]);
+        hand.insure_for(0.75);
     }
 
     fn test_hand(cards: &[u8],
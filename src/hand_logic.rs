@@ -1,13 +1,18 @@
+use serde::Serialize;
+
 use crate::hand::Hand;
-use crate::game_rules::DoublePolicy;
+use crate::game_rules::{DoublePolicy, GameRules, SurrenderPolicy};
 
 /// The game outcome of a played hand.
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum HandOutcome {
     /// The hand is considered won when it either:
     ///   * is not busted and has a higher score than the dealer;
     ///   * is not busted and the dealer busted;
     ///   * is a blackjack against a non-blackjack for the dealer;
+    ///   * is a Charlie against a non-blackjack for the dealer;
+    ///
     /// If the hand was insured, whether the insurance bet won or lost has no
     /// impact on the fact the hand is considered won or not.
     Win,
@@ -16,10 +21,16 @@ pub enum HandOutcome {
     ///   * is not busted, is not a blackjack and has the same score as a dealer
     ///     non-blackjack;
     ///   * is a blackjack against a dealer blackjack.
+    ///
     /// If the hand was insured, whether the insurance bet won or lost has no
     /// impact on the fact the hand is considered pushed or not.
     Push,
 
+    /// The hand pushed only because the dealer busted with the push total of
+    /// the game's dealer push rule (e.g. dealer 22), it would have won
+    /// otherwise. This is counted as a push.
+    DealerPush,
+
     /// The hand lose, which includes surrendered hands and lost insured hands
     /// that won their insurance.
     Lose,
@@ -29,17 +40,41 @@ pub enum HandOutcome {
 ///
 /// The result is given as a normalized bet of 1.0, a simple win gives +1.0,
 /// a simple loss gives -1.0, a won doubled-down hand +2.0, a lost doubled-down
-/// hand a -2.0, a natural +1.5, a surrenderred hand gives -0.5. Insurance is
-/// also taken into account, adding 1.0 to the result if the dealer received a
-/// blackjack, substracting 0.5 if not.
-pub fn hand_result(player: &Hand, dealer: &Hand) -> (HandOutcome, f64) {
+/// hand a -2.0 (or the hand's stake when doubled for less or re-doubled), a
+/// natural what the rules state (+1.5 usually), a surrenderred hand gives
+/// -0.5 (-1.0 when doubled down). Insurance is also taken into account, see
+/// `insurance_result()`, e.g. adding 1.0 to the result if the dealer received
+/// a blackjack, substracting 0.5 if not. A Charlie pays what the rules state.
+/// A dealer push total pushes any non-busted hand, including Charlies, and
+/// naturals unless the rule excepts them. With the original bets only rule
+/// (OBO), a dealer natural takes -1.0 from the player's original hand and
+/// nothing from its split-off hands.
+pub fn hand_result(rules: &GameRules,
+                   player: &Hand,
+                   dealer: &Hand) -> (HandOutcome, f64) {
+    if rules.original_bets_only && dealer.is_bj() && !player.is_bj()
+       && !player.is_surrendered() {
+        // Only the original bet is lost to the dealer natural, split-off
+        // hands do not carry it
+        let (outcome, res) = if player.is_split_off() {
+            (HandOutcome::Push, 0.0)
+        } else {
+            (HandOutcome::Lose, -1.0)
+        };
+        return (outcome, res + insurance_result(rules, player, dealer));
+    }
+
     let (outcome, mut res) = if player.is_surrendered() {
         (HandOutcome::Lose, -0.5)
     } else if player.is_busted() {
         (HandOutcome::Lose, -1.0)
+    } else if is_dealer_push(rules, player, dealer) {
+        (HandOutcome::DealerPush, 0.0)
     } else {
         if player.is_bj() && !dealer.is_bj() {
-            (HandOutcome::Win, 1.5)
+            (HandOutcome::Win, rules.bj_pays)
+        } else if is_charlie(rules, player) && !dealer.is_bj() {
+            (HandOutcome::Win, rules.charlie.unwrap().pays)
         } else if dealer.is_busted() {
             (HandOutcome::Win, 1.0)
         } else {
@@ -56,33 +91,104 @@ pub fn hand_result(player: &Hand, dealer: &Hand) -> (HandOutcome, f64) {
         }
     };
 
-    if player.is_doubled() {
-        res *= 2.0;
+    res *= player.stake();
+    res += insurance_result(rules, player, dealer);
+
+    (outcome, res)
+}
+
+/// Computes the result of the insurance bet of a player hand, if any, relative
+/// to the hand's bet: insurance pays 2:1 when the dealer has a natural, and is
+/// lost otherwise. Even money is accounted as the difference between its 1:1
+/// payout and what the natural would have paid without it.
+pub fn insurance_result(rules: &GameRules,
+                        player: &Hand,
+                        dealer: &Hand) -> f64 {
+    if !player.is_insured() {
+        0.0
+    } else if player.is_even_money() {
+        if dealer.is_bj() { 1.0 } else { 1.0 - rules.bj_pays }
+    } else if dealer.is_bj() {
+        player.insurance() * 2.0
+    } else {
+        -player.insurance()
     }
+}
 
-    if player.is_insured() {
-        if dealer.is_bj() {
-            res += 1.0;
-        } else {
-            res -= 0.5;
-        }
+/// Determines whether a player hand is a Charlie, i.e. it has not busted and
+/// reached the number of cards of the game's Charlie rule, if any.
+pub fn is_charlie(rules: &GameRules, hand: &Hand) -> bool {
+    match rules.charlie {
+        Some(charlie) => !hand.is_busted()
+            && hand.count() >= charlie.cards as usize
+            && (charlie.split_hands || !hand.is_split()),
+        None => false,
     }
+}
 
-    (outcome, res)
+/// Determines whether the dealer's hand pushes the player's instead of losing
+/// to it, as per the game's dealer push rule, if any.
+pub fn is_dealer_push(rules: &GameRules, player: &Hand, dealer: &Hand) -> bool {
+    match rules.dealer_push {
+        Some(push) => dealer.is_busted()
+            && dealer.value() == push.total
+            && !(push.except_naturals && player.is_bj()),
+        None => false,
+    }
+}
+
+/// Determines whether a player hand may be surrendered while it is being
+/// played, i.e. after the initial surrender opportunity on the first two
+/// cards: after hitting, after splitting, or after doubling down (double-down
+/// rescue).
+pub fn may_surrender_in_play(rules: &GameRules, hand: &Hand) -> bool {
+    if hand.is_surrendered() || hand.is_busted() {
+        false
+    } else if hand.is_doubled() {
+        rules.double_rescue
+    } else if rules.surrender == SurrenderPolicy::NoSurrender {
+        false
+    } else if hand.is_split() {
+        rules.surrender_after_split
+            && (hand.count() == 2 || rules.surrender_any_cards)
+    } else {
+        hand.count() > 2 && rules.surrender_any_cards
+    }
 }
 
-/// Determines whether a player hand can double-down based on the game policy.
+/// Determines whether a player hand can double-down based on the game rules.
+///
+/// Two-card hands follow the `double_down` policy, hands of more cards follow
+/// the `double_after_hit` policy unless `double_down` allows any hand; hands
+/// above `double_max_cards` may never double. A doubled-down hand may only be
+/// re-doubled, up to `max_redoubles` times.
 ///
 /// # Parameters
 ///
-///  * `policy` - The game policy in action concerning doubling-down;
-///  * `das`    - Whether double-after-split (DAS) is allowed or not;
-///  * `hand`   - The player's hand.
-pub fn may_double(policy: DoublePolicy, das: bool, hand: &Hand) -> bool {
-    if hand.is_split() && !das {
+///  * `rules` - The game rules;
+///  * `hand`  - The player's hand.
+pub fn may_double(rules: &GameRules, hand: &Hand) -> bool {
+    if hand.is_doubled() {
+        return hand.doubles() <= rules.max_redoubles;
+    }
+
+    if hand.is_split() && !rules.das {
         return false;
     }
 
+    if let Some(max_cards) = rules.double_max_cards {
+        if hand.count() > max_cards as usize {
+            return false;
+        }
+    }
+
+    let policy = if hand.count() > 2
+                    && rules.double_down != DoublePolicy::AnyHand {
+        rules.double_after_hit
+    } else {
+        rules.double_down
+    };
+
     match policy {
         DoublePolicy::AnyHand => true,
         DoublePolicy::AnyTwo => hand.count() == 2,
@@ -97,7 +203,10 @@ pub fn may_double(policy: DoublePolicy, das: bool, hand: &Hand) -> bool {
 #[cfg(test)]
 mod tests {
     use crate::card::Card;
-    use crate::hand_logic::{hand_result, may_double, HandOutcome};
+    use crate::hand_logic::{hand_result, insurance_result, may_double,
+                            may_surrender_in_play, is_charlie, HandOutcome};
+    use crate::game_rules::{GameRules, CharlieRule, DealerPushRule,
+                            DoublePolicy, SurrenderPolicy};
     use crate::hand_logic::HandOutcome::*;
     use crate::hand::Hand;
 
@@ -121,6 +230,11 @@ mod tests {
 
         test_hand_result(Lose, -0.5, &[8, 8],     &[10, 7],       SURRENDERED);
         test_hand_result(Lose, -0.5, &[8, 8],     &[10, 6, 9],    SURRENDERED);
+        test_hand_result(Lose, -0.5, &[8, 8, 2],  &[10, 7],       SURRENDERED);
+        test_hand_result(Lose, -1.0, &[5, 6, 2],  &[10, 7],
+                         SURRENDERED | DOUBLED);
+        test_hand_result(Lose, -0.5, &[8, 8, 2],  &[10, 7],
+                         SURRENDERED | SPLIT);
 
         test_hand_result(Push,  0.0, &[1, 10],    &[7, 7, 7],     SPLIT);
 
@@ -130,68 +244,304 @@ mod tests {
         test_hand_result(Lose, -1.5, &[10, 6, 7], &[1, 9],        INSURED);
     }
 
+    #[test]
+    fn it_pays_partial_insurance_and_even_money() {
+        let rules = GameRules::default();
+        let dealer_bj = Hand::from(&[1, 10][..]);
+        let dealer_18 = Hand::from(&[1, 7][..]);
+
+        let mut partial = Hand::from(&[10, 8][..]);
+        partial.insure_for(0.25);
+        assert_eq!(insurance_result(&rules, &partial, &dealer_bj), 0.5);
+        assert_eq!(insurance_result(&rules, &partial, &dealer_18), -0.25);
+        assert_eq!(hand_result(&rules, &partial, &dealer_bj), (Lose, -0.5));
+        assert_eq!(hand_result(&rules, &partial, &dealer_18), (Push, -0.25));
+
+        let mut natural = Hand::from(&[1, 10][..]);
+        natural.take_even_money();
+        assert_eq!(hand_result(&rules, &natural, &dealer_bj), (Push, 1.0));
+        assert_eq!(hand_result(&rules, &natural, &dealer_18), (Win, 1.0));
+        assert_eq!(insurance_result(&rules, &natural, &dealer_18), -0.5);
+
+        let rules = GameRules { bj_pays: 1.2, ..GameRules::default() };
+        assert_eq!(hand_result(&rules, &natural, &dealer_18), (Win, 1.0));
+    }
+
+    #[test]
+    fn it_only_loses_original_bets_to_naturals() {
+        let rules = GameRules {
+            original_bets_only: true,
+            ..GameRules::default()
+        };
+        let dealer_bj = Hand::from(&[1, 10][..]);
+
+        let mut doubled = Hand::from(&[5, 6][..]);
+        doubled.double_down();
+        doubled.add(Card(9));
+        assert_eq!(hand_result(&rules, &doubled, &dealer_bj), (Lose, -1.0));
+
+        let mut first = Hand::from(&[8, 8][..]);
+        let mut second = first.split();
+        first.add(Card(3));
+        second.add(Card(2));
+        assert_eq!(hand_result(&rules, &first, &dealer_bj), (Lose, -1.0));
+        assert_eq!(hand_result(&rules, &second, &dealer_bj), (Push, 0.0));
+
+        let natural = Hand::from(&[10, 1][..]);
+        assert_eq!(hand_result(&rules, &natural, &dealer_bj), (Push, 0.0));
+
+        let mut insured = Hand::from(&[10, 8][..]);
+        insured.insure();
+        assert_eq!(hand_result(&rules, &insured, &dealer_bj), (Lose, 0.0));
+    }
+
+    #[test]
+    fn it_wins_with_a_charlie() {
+        test_hand_result(Lose, -1.0, &[2, 3, 4, 5, 2], &[10, 9], 0);
+        test_hand_result(Win,   1.0, &[2, 3, 4, 5, 2], &[10, 9], CHARLIE);
+        test_hand_result(Win,   1.0, &[2, 3, 4, 5, 7], &[7, 7, 7], CHARLIE);
+        test_hand_result(Win,   2.0, &[2, 3, 4, 5, 2], &[10, 9],
+                         CHARLIE | BONUS);
+        test_hand_result(Win,   4.0, &[2, 3, 4, 5, 2], &[10, 9],
+                         CHARLIE | BONUS | DOUBLED);
+        test_hand_result(Lose, -1.0, &[2, 3, 4, 5, 2], &[1, 10], CHARLIE);
+        test_hand_result(Lose, -1.0, &[2, 3, 4, 5, 9], &[10, 9], CHARLIE);
+        test_hand_result(Lose, -1.0, &[2, 3, 4, 5],    &[10, 9], CHARLIE);
+        test_hand_result(Lose, -1.0, &[2, 2, 4, 5, 2], &[10, 9],
+                         CHARLIE | SPLIT);
+        test_hand_result(Win,   1.0, &[2, 2, 4, 5, 2, 3], &[10, 9],
+                         CHARLIE | SPLIT | CHARLIE_SPLIT);
+    }
+
+    #[test]
+    fn it_pushes_on_dealer_push_total() {
+        test_hand_result(Win,   1.0, &[10, 8],     &[10, 6, 6],    0);
+        test_hand_result(DealerPush, 0.0, &[10, 8], &[10, 6, 6],   PUSH22);
+        test_hand_result(DealerPush, 0.0, &[10, 8], &[10, 6, 6],
+                         PUSH22 | DOUBLED);
+        test_hand_result(Win,   1.0, &[10, 8],     &[10, 6, 7],    PUSH22);
+        test_hand_result(Win,   1.0, &[10, 8],     &[10, 7],       PUSH22);
+        test_hand_result(Lose, -1.0, &[10, 6, 8],  &[10, 6, 6],    PUSH22);
+        test_hand_result(Lose, -0.5, &[10, 6],     &[10, 6, 6],
+                         PUSH22 | SURRENDERED);
+        test_hand_result(DealerPush, 0.0, &[1, 10], &[10, 6, 6],   PUSH22);
+        test_hand_result(Win,   1.5, &[1, 10],     &[10, 6, 6],
+                         PUSH22 | PUSH_EXCEPT_BJ);
+        test_hand_result(DealerPush, -0.5, &[10, 8], &[1, 5, 6, 10],
+                         PUSH22 | INSURED);
+    }
+
+    #[test]
+    fn it_determines_whether_it_can_surrender_in_play() {
+        let mut rules = GameRules {
+            surrender_any_cards: true,
+            ..GameRules::default()
+        };
+        assert!(!may_surrender_in_play(&rules, &Hand::from(&[4, 6, 6][..])));
+
+        rules.surrender = SurrenderPolicy::LateSurrender;
+        rules.surrender_any_cards = false;
+        let mut split = Hand::from(&[8, 8][..]);
+        split.split();
+        split.add(Card(5));
+        let mut doubled = Hand::from(&[5, 6][..]);
+        doubled.double_down();
+        doubled.add(Card(2));
+
+        assert!(!may_surrender_in_play(&rules, &Hand::from(&[10, 6][..])));
+        assert!(!may_surrender_in_play(&rules, &Hand::from(&[4, 6, 6][..])));
+        assert!(!may_surrender_in_play(&rules, &split));
+        assert!(!may_surrender_in_play(&rules, &doubled));
+
+        rules.surrender_any_cards = true;
+        assert!(!may_surrender_in_play(&rules, &Hand::from(&[10, 6][..])));
+        assert!(may_surrender_in_play(&rules, &Hand::from(&[4, 6, 6][..])));
+        assert!(!may_surrender_in_play(&rules, &Hand::from(&[4, 6, 6, 9][..])));
+        assert!(!may_surrender_in_play(&rules, &split));
+
+        rules.surrender_after_split = true;
+        assert!(may_surrender_in_play(&rules, &split));
+        split.add(Card(2));
+        assert!(may_surrender_in_play(&rules, &split));
+        rules.surrender_any_cards = false;
+        assert!(!may_surrender_in_play(&rules, &split));
+
+        assert!(!may_surrender_in_play(&rules, &doubled));
+        rules.double_rescue = true;
+        assert!(may_surrender_in_play(&rules, &doubled));
+        doubled.surrender();
+        assert!(!may_surrender_in_play(&rules, &doubled));
+    }
+
+    #[test]
+    fn it_detects_charlie_hands() {
+        let mut rules = GameRules::default();
+        assert!(!is_charlie(&rules, &Hand::from(&[2, 3, 2, 2, 2][..])));
+
+        rules.charlie = Some(CharlieRule {
+            cards: 6,
+            pays: 1.0,
+            split_hands: true,
+        });
+        assert!(!is_charlie(&rules, &Hand::from(&[2, 3, 2, 2, 2][..])));
+        assert!(is_charlie(&rules, &Hand::from(&[2, 3, 2, 2, 2, 1][..])));
+        assert!(is_charlie(&rules, &Hand::from(&[2, 3, 2, 2, 2, 1, 1][..])));
+        assert!(!is_charlie(&rules, &Hand::from(&[2, 3, 2, 2, 10, 10][..])));
+    }
+
     #[test]
     fn it_determines_whether_it_can_double_down() {
         use crate::game_rules::DoublePolicy::*;
 
-        assert!(may_double(AnyHand,     true,   &Hand::from(&[4, 7][..])));
-        assert!(may_double(AnyHand,     false,  &Hand::from(&[4, 7][..])));
-        assert!(may_double(AnyHand,     false,  &Hand::from(&[4, 3, 8][..])));
+        assert!(may_double(&db(AnyHand, true),    &Hand::from(&[4, 7][..])));
+        assert!(may_double(&db(AnyHand, false),   &Hand::from(&[4, 7][..])));
+        assert!(may_double(&db(AnyHand, false),   &Hand::from(&[4, 3, 8][..])));
         let mut hand = Hand::from(&[4, 4][..]);
         hand.split();
         hand.add(Card(3));
         hand.add(Card(8));
-        assert!(!may_double(AnyHand,    false,  &hand));
-        assert!(may_double(AnyHand,     true,   &hand));
-
-        assert!(may_double(AnyTwo,      true,   &Hand::from(&[4, 7][..])));
-        assert!(may_double(AnyTwo,      false,  &Hand::from(&[4, 7][..])));
-        assert!(may_double(AnyTwo,      false,  &Hand::from(&[1, 7][..])));
-        assert!(!may_double(AnyTwo,     false,  &Hand::from(&[4, 3, 8][..])));
-
-        assert!(!may_double(Hard9To11,  true,   &Hand::from(&[5, 3][..])));
-        assert!(may_double(Hard9To11,   true,   &Hand::from(&[4, 5][..])));
-        assert!(may_double(Hard9To11,   true,   &Hand::from(&[4, 6][..])));
-        assert!(may_double(Hard9To11,   true,   &Hand::from(&[4, 7][..])));
-        assert!(!may_double(Hard9To11,  true,   &Hand::from(&[4, 8][..])));
-        assert!(!may_double(Hard9To11,  true,   &Hand::from(&[1, 2][..])));
-
-        assert!(!may_double(Hard10To11, true,   &Hand::from(&[5, 3][..])));
-        assert!(!may_double(Hard10To11, true,   &Hand::from(&[4, 5][..])));
-        assert!(may_double(Hard10To11,  true,   &Hand::from(&[4, 6][..])));
-        assert!(may_double(Hard10To11,  true,   &Hand::from(&[4, 7][..])));
-        assert!(!may_double(Hard10To11, true,   &Hand::from(&[4, 8][..])));
-        assert!(!may_double(Hard10To11, true,   &Hand::from(&[1, 2][..])));
-
-        assert!(!may_double(NoDouble,   true,   &Hand::from(&[5, 3][..])));
-        assert!(!may_double(NoDouble,   true,   &Hand::from(&[4, 5][..])));
-        assert!(!may_double(NoDouble,   true,   &Hand::from(&[4, 6][..])));
-        assert!(!may_double(NoDouble,   true,   &Hand::from(&[4, 7][..])));
-        assert!(!may_double(NoDouble,   true,   &Hand::from(&[4, 8][..])));
-        assert!(!may_double(NoDouble,   true,   &Hand::from(&[1, 2][..])));
+        assert!(!may_double(&db(AnyHand, false),  &hand));
+        assert!(may_double(&db(AnyHand, true),    &hand));
+
+        assert!(may_double(&db(AnyTwo, true),     &Hand::from(&[4, 7][..])));
+        assert!(may_double(&db(AnyTwo, false),    &Hand::from(&[4, 7][..])));
+        assert!(may_double(&db(AnyTwo, false),    &Hand::from(&[1, 7][..])));
+        assert!(!may_double(&db(AnyTwo, false),   &Hand::from(&[4, 3, 8][..])));
+
+        assert!(!may_double(&db(Hard9To11, true), &Hand::from(&[5, 3][..])));
+        assert!(may_double(&db(Hard9To11, true),  &Hand::from(&[4, 5][..])));
+        assert!(may_double(&db(Hard9To11, true),  &Hand::from(&[4, 6][..])));
+        assert!(may_double(&db(Hard9To11, true),  &Hand::from(&[4, 7][..])));
+        assert!(!may_double(&db(Hard9To11, true), &Hand::from(&[4, 8][..])));
+        assert!(!may_double(&db(Hard9To11, true), &Hand::from(&[1, 2][..])));
+
+        assert!(!may_double(&db(Hard10To11, true), &Hand::from(&[5, 3][..])));
+        assert!(!may_double(&db(Hard10To11, true), &Hand::from(&[4, 5][..])));
+        assert!(may_double(&db(Hard10To11, true), &Hand::from(&[4, 6][..])));
+        assert!(may_double(&db(Hard10To11, true), &Hand::from(&[4, 7][..])));
+        assert!(!may_double(&db(Hard10To11, true), &Hand::from(&[4, 8][..])));
+        assert!(!may_double(&db(Hard10To11, true), &Hand::from(&[1, 2][..])));
+
+        assert!(!may_double(&db(NoDouble, true),  &Hand::from(&[5, 3][..])));
+        assert!(!may_double(&db(NoDouble, true),  &Hand::from(&[4, 5][..])));
+        assert!(!may_double(&db(NoDouble, true),  &Hand::from(&[4, 6][..])));
+        assert!(!may_double(&db(NoDouble, true),  &Hand::from(&[4, 7][..])));
+        assert!(!may_double(&db(NoDouble, true),  &Hand::from(&[4, 8][..])));
+        assert!(!may_double(&db(NoDouble, true),  &Hand::from(&[1, 2][..])));
+    }
+
+    #[test]
+    fn it_determines_whether_it_can_double_after_hit() {
+        use crate::game_rules::DoublePolicy::*;
+
+        let mut rules = db(AnyTwo, true);
+        rules.double_after_hit = Hard10To11;
+        assert!(may_double(&rules, &Hand::from(&[4, 3][..])));
+        assert!(may_double(&rules, &Hand::from(&[4, 3, 3][..])));
+        assert!(may_double(&rules, &Hand::from(&[4, 3, 2, 2][..])));
+        assert!(!may_double(&rules, &Hand::from(&[4, 3, 2][..])));
+        assert!(!may_double(&rules, &Hand::from(&[1, 3, 6][..])));
+
+        rules.double_max_cards = Some(3);
+        assert!(may_double(&rules, &Hand::from(&[4, 3, 3][..])));
+        assert!(!may_double(&rules, &Hand::from(&[4, 3, 2, 2][..])));
+
+        let mut rules = db(AnyHand, true);
+        rules.double_max_cards = Some(3);
+        assert!(may_double(&rules, &Hand::from(&[4, 3, 8][..])));
+        assert!(!may_double(&rules, &Hand::from(&[4, 3, 2, 2][..])));
+    }
+
+    #[test]
+    fn it_determines_whether_it_can_redouble() {
+        use crate::game_rules::DoublePolicy::*;
+
+        let mut rules = db(AnyTwo, true);
+        let mut hand = Hand::from(&[4, 3][..]);
+        hand.double_down();
+        hand.add(Card(2));
+        assert!(!may_double(&rules, &hand));
+
+        rules.max_redoubles = 1;
+        assert!(may_double(&rules, &hand));
+        hand.double_down();
+        hand.add(Card(2));
+        assert!(!may_double(&rules, &hand));
+    }
+
+    #[test]
+    fn it_returns_the_result_of_hands_doubled_for_less() {
+        let rules = GameRules::default();
+        let dealer = Hand::from(&[10, 8][..]);
+
+        let mut player = Hand::from(&[5, 6, 8][..]);
+        player.double_down_by(0.5);
+        assert_eq!(hand_result(&rules, &player, &dealer), (Win, 1.5));
+
+        let mut player = Hand::from(&[5, 6, 5][..]);
+        player.double_down_by(0.5);
+        assert_eq!(hand_result(&rules, &player, &dealer), (Lose, -1.5));
+
+        let mut player = Hand::from(&[5, 6, 2, 7][..]);
+        player.double_down();
+        player.double_down();
+        assert_eq!(hand_result(&rules, &player, &dealer), (Win, 4.0));
+    }
+
+    fn db(policy: DoublePolicy, das: bool) -> GameRules {
+        GameRules {
+            double_down: policy,
+            das,
+            ..GameRules::default()
+        }
     }
 
     const DOUBLED: u32      = 1 << 0;
     const SURRENDERED: u32  = 1 << 1;
     const INSURED: u32      = 1 << 2;
     const SPLIT: u32        = 1 << 3;
+    const CHARLIE: u32      = 1 << 4;
+    const BONUS: u32        = 1 << 5;
+    const CHARLIE_SPLIT: u32 = 1 << 6;
+    const PUSH22: u32       = 1 << 7;
+    const PUSH_EXCEPT_BJ: u32 = 1 << 8;
 
     fn test_hand_result(expected_outcome: HandOutcome,
                         expected_result: f64,
                         player_vals: &[u8],
                         dealer: &[u8],
                         opts: u32) {
-        let mut player = Hand::from(player_vals);
-        if opts & DOUBLED > 0 { player.double_down(); }
-        if opts & SURRENDERED > 0 { player.surrender(); }
-        if opts & INSURED > 0 { player.insure(); }
+        let mut rules = GameRules::default();
+        if opts & CHARLIE > 0 {
+            rules.charlie = Some(CharlieRule {
+                cards: 5,
+                pays: if opts & BONUS > 0 { 2.0 } else { 1.0 },
+                split_hands: opts & CHARLIE_SPLIT > 0,
+            });
+        }
+        if opts & PUSH22 > 0 {
+            rules.dealer_push = Some(DealerPushRule {
+                total: 22,
+                except_naturals: opts & PUSH_EXCEPT_BJ > 0,
+            });
+        }
+
+        let mut player;
         if opts & SPLIT > 0 {
+            player = Hand::from(&player_vals[..2]);
             player.split();
-            player.add(Card(player_vals[1]));
+            for &card in &player_vals[1..] {
+                player.add(Card(card));
+            }
+        } else {
+            player = Hand::from(player_vals);
         }
+        if opts & DOUBLED > 0 { player.double_down(); }
+        if opts & SURRENDERED > 0 { player.surrender(); }
+        if opts & INSURED > 0 { player.insure(); }
         let dealer = Hand::from(dealer);
 
-        let (outcome, result) = hand_result(&player, &dealer);
+        let (outcome, result) = hand_result(&rules, &player, &dealer);
 
         assert_eq!(outcome, expected_outcome, "\nplayer={player:?}\ndealer={dealer:?}");
         assert_eq!(result, expected_result);
@@ -3,7 +3,7 @@ use serde::Serialize;
 use crate::hand::Hand;
 use crate::hand_logic::HandOutcome;
 
-#[derive(Debug, PartialEq, Serialize)]
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub struct HandStats {
     /// Total number of hands a player played, this includes split hands,
     /// surrendered hands, and naturals.
@@ -39,6 +39,15 @@ pub struct HandStats {
 
     /// The number of hands the player surrendered.
     pub surrender:  u64,
+
+    /// The number of hands pushed by the dealer busting with the push total of
+    /// the game's dealer push rule (e.g. dealer 22), these are also counted in
+    /// `push`.
+    pub dealer_push: u64,
+
+    /// The number of naturals the player took even money on, these are also
+    /// counted in `insured`.
+    pub even_money: u64,
 }
 
 impl Default for HandStats {
@@ -55,6 +64,8 @@ impl Default for HandStats {
             split: 0,
             insured: 0,
             surrender: 0,
+            dealer_push: 0,
+            even_money: 0,
         }
     }
 }
@@ -83,6 +94,8 @@ impl From<(u64, u64, u64, u64, u64, u64, u64, u64, u64, u64)> for HandStats {
             split: v.7,
             insured: v.8,
             surrender: v.9,
+            dealer_push: 0,
+            even_money: 0,
         }
     }
 }
@@ -90,15 +103,19 @@ impl From<(u64, u64, u64, u64, u64, u64, u64, u64, u64, u64)> for HandStats {
 impl HandStats {
     /// Update the current stats based a player hand and its game outcome.
     ///
-    /// `total` is always incremented; the outcome will increment `won`, `push`,
-    /// or `lost`; other members are independently updated from the properties
-    /// of the given hand.
+    /// `total` is always incremented; the outcome will increment `won`, `push`
+    /// (and `dealer_push`), or `lost`; other members are independently
+    /// updated from the properties of the given hand.
     pub fn update(&mut self, hand: &Hand, outcome: HandOutcome) {
         self.total += 1;
 
         match outcome {
             HandOutcome::Win => self.won += 1,
             HandOutcome::Push => self.push += 1,
+            HandOutcome::DealerPush => {
+                self.push += 1;
+                self.dealer_push += 1;
+            },
             HandOutcome::Lose => self.lost += 1,
         }
 
@@ -117,6 +134,9 @@ impl HandStats {
         if hand.is_insured() {
             self.insured += 1;
         }
+        if hand.is_even_money() {
+            self.even_money += 1;
+        }
         if hand.is_surrendered() {
             self.surrender += 1;
         }
@@ -139,6 +159,8 @@ impl Add for HandStats {
             split: self.split + rhs.split,
             insured: self.insured + rhs.insured,
             surrender: self.surrender + rhs.surrender,
+            dealer_push: self.dealer_push + rhs.dealer_push,
+            even_money: self.even_money + rhs.even_money,
         }
     }
 }
@@ -156,6 +178,8 @@ impl AddAssign for HandStats {
         self.split += rhs.split;
         self.insured += rhs.insured;
         self.surrender += rhs.surrender;
+        self.dealer_push += rhs.dealer_push;
+        self.even_money += rhs.even_money;
     }
 }
 
@@ -163,7 +187,8 @@ impl AddAssign for HandStats {
 mod tests {
     use crate::hand_stats::HandStats;
     use crate::hand::Hand;
-    use crate::hand_logic::hand_result;
+    use crate::hand_logic::{hand_result, HandOutcome};
+    use crate::game_rules::GameRules;
 
     #[test]
     fn it_update_hand_stats() {
@@ -174,6 +199,31 @@ mod tests {
                         (1, 0, 0, 1, 0, 0, 0, 0, 1, 0));
     }
 
+    #[test]
+    fn it_counts_dealer_pushes_as_pushes() {
+        let mut stats = HandStats::default();
+        stats.update(&Hand::from(&[10, 8][..]), HandOutcome::DealerPush);
+        stats.update(&Hand::from(&[10, 8][..]), HandOutcome::Push);
+
+        assert_eq!(stats, HandStats {
+            dealer_push: 1,
+            ..HandStats::from((2, 0, 0, 2, 0, 0, 0, 0, 0, 0))
+        });
+    }
+
+    #[test]
+    fn it_counts_even_money_as_insured() {
+        let mut stats = HandStats::default();
+        let mut natural = Hand::from(&[1, 10][..]);
+        natural.take_even_money();
+        stats.update(&natural, HandOutcome::Win);
+
+        assert_eq!(stats, HandStats {
+            even_money: 1,
+            ..HandStats::from((1, 1, 0, 0, 0, 1, 0, 0, 1, 0))
+        });
+    }
+
     const DOUBLED: u32      = 1 << 0;
     const SURRENDERED: u32  = 1 << 1;
     const INSURED: u32      = 1 << 2;
@@ -193,7 +243,7 @@ mod tests {
 
         let dealer = Hand::from(dealer);
 
-        let (outcome, _) = hand_result(&player, &dealer);
+        let (outcome, _) = hand_result(&GameRules::default(), &player, &dealer);
         stats.update(&player, outcome);
 
         assert_eq!(stats, HandStats::from(values));
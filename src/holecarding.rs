@@ -0,0 +1,593 @@
+use crate::card::Card;
+use crate::dealer_probabilities::{DealerProbabilities, DealerFinals,
+                                  CardCounts, shoe_counts, value_of};
+use crate::game_rules::{GameRules, DoublePolicy};
+use crate::game_rules::SurrenderPolicy::NoSurrender;
+use crate::hand::Hand;
+use crate::strategy::HoleTell;
+
+/// A holecarding strategy: the best decision of each player hand against
+/// each dealer hand of an upcard and a holecard, for a set of game rules.
+///
+/// The decisions are computed from the dealer's exact final totals given
+/// their two cards, and the expected value of standing, hitting, doubling
+/// down, splitting (without resplitting), and surrendering each hand, drawing
+/// from the full shoe less the dealer's two cards. They use the basic strategy tables' codes: "+" hit,
+/// "=" stand, "D" double or hit, "d" double or stand, "V" split, "S"
+/// surrender or hit, and "s" surrender or stand.
+pub struct HolecardingTables {
+    /// By upcard then holecard, the decisions against the dealer's hand
+    hands:  Vec<Decisions>,
+}
+
+impl HolecardingTables {
+    pub fn new(rules: &GameRules) -> HolecardingTables {
+        let probabilities = DealerProbabilities::new(&rules.soft17);
+        let mut hands = Vec::with_capacity(100);
+
+        for upcard in 1..=10 {
+            let cards = shoe_less(rules, Card(upcard));
+            for holecard in 1..=10 {
+                let mut weights = [0.0; 10];
+                weights[holecard as usize - 1] = 1.0;
+                hands.push(Decisions::new(rules, &probabilities, &cards,
+                                          Card(upcard), &weights));
+            }
+        }
+
+        HolecardingTables { hands }
+    }
+
+    /// Returns the decision code of a hand against the dealer's two cards;
+    /// pairs are only split when `may_split`, and the next card off the shoe
+    /// is played when known.
+    pub fn decision(&self,
+                    rules: &GameRules,
+                    upcard: Card,
+                    holecard: Card,
+                    me: &Hand,
+                    may_split: bool,
+                    next_card: Option<Card>) -> u8 {
+        let index = (upcard.0 as usize - 1) * 10 + holecard.0 as usize - 1;
+        self.hands[index].decision(rules, me, may_split, next_card)
+    }
+}
+
+/// A strategy against dealer hands known in part: from their upcard only, or
+/// also from a tell on whether their holecard is a ten, of a given accuracy.
+///
+/// The decisions are computed as the holecarding strategy's, against each
+/// holecard weighted by its probability given the tell, and the dealer's peek
+/// for a natural.
+pub struct InformedTables {
+    /// The tell's accuracy, if any
+    accuracy:   Option<f32>,
+    /// By upcard, the decisions without a tell, or when the holecard reads
+    /// as not a ten then as a ten
+    hands:      Vec<Decisions>,
+}
+
+impl InformedTables {
+    pub fn new(rules: &GameRules, accuracy: Option<f32>) -> InformedTables {
+        let probabilities = DealerProbabilities::new(&rules.soft17);
+        let mut hands = Vec::with_capacity(20);
+
+        for upcard in 1..=10 {
+            let cards = shoe_less(rules, Card(upcard));
+            let readings: &[bool] = match accuracy {
+                Some(_) => &[false, true],
+                None => &[false],
+            };
+            for &ten in readings {
+                let mut weights = cards.map(f64::from);
+                if let Some(accuracy) = accuracy {
+                    for (i, weight) in weights.iter_mut().enumerate() {
+                        *weight *= if (i == 9) == ten {
+                            accuracy as f64
+                        } else {
+                            1.0 - accuracy as f64
+                        };
+                    }
+                }
+                hands.push(Decisions::new(rules, &probabilities, &cards,
+                                          Card(upcard), &weights));
+            }
+        }
+
+        InformedTables { accuracy, hands }
+    }
+
+    /// Returns the decision code of a hand against the dealer's upcard, read
+    /// with `tell` if the tables have an accuracy; pairs are only split when
+    /// `may_split`, and the next card off the shoe is played when known.
+    pub fn decision(&self,
+                    rules: &GameRules,
+                    upcard: Card,
+                    tell: Option<HoleTell>,
+                    me: &Hand,
+                    may_split: bool,
+                    next_card: Option<Card>) -> u8 {
+        let index = match (self.accuracy, tell) {
+            (Some(_), Some(tell)) => {
+                (upcard.0 as usize - 1) * 2 + tell.ten as usize
+            },
+            (None, None) => upcard.0 as usize - 1,
+            _ => panic!("The tables do not match the tell"),
+        };
+        self.hands[index].decision(rules, me, may_split, next_card)
+    }
+}
+
+/// Returns the probability for the dealer's holecard to be a ten given a
+/// tell, before the dealer peeks for a natural.
+pub fn ten_probability(tell: HoleTell) -> f64 {
+    let accuracy = tell.accuracy as f64;
+    let (ten, other) = if tell.ten {
+        (accuracy, 1.0 - accuracy)
+    } else {
+        (1.0 - accuracy, accuracy)
+    };
+
+    ten * 4.0 / 13.0 / (ten * 4.0 / 13.0 + other * 9.0 / 13.0)
+}
+
+/// The decisions of each player hand against a dealer hand known in part,
+/// with the expected values to play a hand knowing the next card.
+struct Decisions {
+    /// The decision of each hard total
+    hard:   [u8; 22],
+    /// The decision of each soft total
+    soft:   [u8; 22],
+    /// Whether to split each pair, ace first
+    pairs:  [bool; 10],
+    /// The expected values of the decisions, or `None` against a natural
+    evs:    Option<PlayerEvs>,
+}
+
+impl Decisions {
+    /// Computes the decisions against an upcard and each holecard weighted
+    /// by `weights`, the holecard and the draws from `cards`; naturals are
+    /// left out when the dealer peeks for them.
+    fn new(rules: &GameRules,
+           probabilities: &DealerProbabilities,
+           cards: &CardCounts,
+           upcard: Card,
+           weights: &[f64; 10]) -> Decisions {
+        let mut decisions = Decisions {
+            hard: [b'='; 22],
+            soft: [b'='; 22],
+            pairs: [false; 10],
+            evs: None,
+        };
+
+        let mut finals = [0.0; 10];
+        let (mut drawn, mut natural) = (0.0, 0.0);
+        for (i, &weight) in weights.iter().enumerate() {
+            let holecard = i as u8 + 1;
+            if upcard.0 + holecard == 11
+               && (upcard == Card(1) || holecard == 1) {
+                natural += weight;
+                continue;
+            }
+            if weight == 0.0 {
+                continue;
+            }
+
+            let hand = probabilities.holecard_finals(cards, upcard,
+                                                     Card(holecard));
+            for (total, p) in finals.iter_mut().zip(hand) {
+                *total += weight * p;
+            }
+            drawn += weight;
+        }
+
+        if rules.dealer_peeks(upcard) {
+            natural = 0.0;
+        }
+        if drawn == 0.0 {
+            // A natural not peeked: every hand loses its bet, which only
+            // surrendering saves
+            let code = if rules.surrenders_early(upcard) {
+                b's'
+            } else {
+                b'='
+            };
+            decisions.hard = [code; 22];
+            decisions.soft = [code; 22];
+            return decisions;
+        }
+
+        for total in finals.iter_mut() {
+            *total /= drawn;
+        }
+        // The player draws from the cards less the holecard, as expected
+        // from its weights
+        let left = cards.iter().sum::<u16>() as f64 - 1.0;
+        let all = drawn + natural;
+        let mut probs = [0.0; 10];
+        for (i, p) in probs.iter_mut().enumerate() {
+            *p = (cards[i] as f64 - weights[i] / all) / left;
+        }
+        let evs = PlayerEvs::new(rules, &finals, &probs, natural / all,
+                                 rules.extra_bets_protected(upcard));
+
+        for value in 4..=21 {
+            decisions.hard[value] = evs.decision(rules, value, false);
+        }
+        for value in 12..=21 {
+            decisions.soft[value] = evs.decision(rules, value, true);
+        }
+        for card in 1..=10 {
+            decisions.pairs[card as usize - 1] = evs.splits(rules, card);
+        }
+        decisions.evs = Some(evs);
+
+        decisions
+    }
+
+    fn decision(&self,
+                rules: &GameRules,
+                me: &Hand,
+                may_split: bool,
+                next_card: Option<Card>) -> u8 {
+        let pair = may_split && me.count() == 2 && me[0] == me[1];
+
+        if let (Some(evs), Some(next_card)) = (&self.evs, next_card) {
+            let sum = me.iter().map(|card| card.0 as usize).sum();
+            let ace = me.iter().any(|&card| card == Card(1));
+            let pair = if pair { Some(me[0].0) } else { None };
+            evs.decision_drawing(rules, sum, ace, pair, next_card)
+        } else if pair && self.pairs[me[0].0 as usize - 1] {
+            b'V'
+        } else if me.is_soft() {
+            self.soft[me.value() as usize]
+        } else {
+            self.hard[me.value() as usize]
+        }
+    }
+}
+
+/// The expected values of the player's decisions against known dealer
+/// finals, for a bet of 1.0.
+struct PlayerEvs {
+    /// The probability of drawing each card, ace first
+    probs:  [f64; 10],
+    /// By value, the expected value of standing
+    stand:  [f64; 22],
+    /// By sum of the cards (aces as 1), and whether there is an ace, the
+    /// expected value of hitting then playing on by standing or hitting
+    hit:    [[f64; 2]; 22],
+    /// The probability of a dealer natural, which the finals leave out
+    natural: f64,
+    /// Whether doubles and splits only lose the original bet to a natural
+    protected: bool,
+}
+
+impl PlayerEvs {
+    fn new(rules: &GameRules,
+           finals: &DealerFinals,
+           probs: &[f64; 10],
+           natural: f64,
+           protected: bool) -> PlayerEvs {
+        let mut stand = [0.0; 22];
+        for (value, ev) in stand.iter_mut().enumerate() {
+            *ev = finals.iter().enumerate().map(|(i, p)| {
+                let total = i + 17;
+                p * if total > 21 {
+                    match rules.dealer_push {
+                        Some(push) if push.total as usize == total => 0.0,
+                        _ => 1.0,
+                    }
+                } else if value > total {
+                    1.0
+                } else if value < total {
+                    -1.0
+                } else {
+                    0.0
+                }
+            }).sum();
+        }
+
+        let mut evs = PlayerEvs {
+            probs: *probs,
+            stand,
+            hit: [[0.0; 2]; 22],
+            natural,
+            protected,
+        };
+
+        for sum in (2..=21).rev() {
+            for ace in [false, true] {
+                evs.hit[sum][ace as usize] = evs.draw(sum, ace, |sum, ace| {
+                    let value = value_index(sum, ace);
+                    evs.stand[value].max(evs.hit[sum][ace as usize])
+                });
+            }
+        }
+
+        evs
+    }
+
+    /// Returns the expected value of drawing a card, then of `then` for the
+    /// new sum and ace; busting loses -1.0.
+    fn draw<F>(&self, sum: usize, ace: bool, then: F) -> f64
+        where F: Fn(usize, bool) -> f64 {
+        self.probs.iter().enumerate().map(|(i, p)| {
+            let (sum, ace) = (sum + i + 1, ace || i == 0);
+            p * if sum > 21 { -1.0 } else { then(sum, ace) }
+        }).sum()
+    }
+
+    fn double(&self, sum: usize, ace: bool) -> f64 {
+        2.0 * self.draw(sum, ace, |sum, ace| {
+            self.stand[value_index(sum, ace)]
+        })
+    }
+
+    /// The best expected value of a two-card hand, standing, hitting, or
+    /// doubling down if `may_double`.
+    fn best(&self, rules: &GameRules, sum: usize, ace: bool, may_double: bool)
+        -> f64 {
+        let value = value_index(sum, ace);
+        let mut best = self.stand[value].max(self.hit[sum][ace as usize]);
+        if may_double && double_allowed(rules, value, sum != value) {
+            best = best.max(self.double(sum, ace));
+        }
+        best
+    }
+
+    /// The expected value of a hand split from a pair of `card`, once dealt
+    /// its second card.
+    fn split_hand(&self, rules: &GameRules, card: usize, sum: usize, ace: bool)
+        -> f64 {
+        if ace && card == 1 && !rules.play_ace_pairs {
+            self.stand[value_index(sum, ace)]
+        } else {
+            self.best(rules, sum, ace, rules.das)
+        }
+    }
+
+    /// Returns an expected value counting the dealer naturals left out of the
+    /// finals, which take the whole `stake` unless it is protected.
+    fn with_naturals(&self, ev: f64, stake: f64) -> f64 {
+        let lost = if self.protected { 1.0 } else { stake };
+        (1.0 - self.natural) * ev - self.natural * lost
+    }
+
+    /// Returns the decision code of a hand of two cards or more.
+    fn decision(&self, rules: &GameRules, value: usize, soft: bool) -> u8 {
+        if value == 21 {
+            return b'=';
+        }
+
+        let sum = if soft { value - 10 } else { value };
+        let double = if double_allowed(rules, value, soft) {
+            self.with_naturals(self.double(sum, soft), 2.0)
+        } else {
+            f64::NEG_INFINITY
+        };
+
+        self.code(rules,
+                  self.with_naturals(self.stand[value], 1.0),
+                  self.with_naturals(self.hit[sum][soft as usize], 1.0),
+                  double)
+    }
+
+    /// Returns the decision code of a hand of cards summing to `sum` (aces
+    /// as 1), knowing the next card off the shoe; the cards after it are
+    /// not known. `pair` is the card of a pair which may be split.
+    fn decision_drawing(&self,
+                        rules: &GameRules,
+                        sum: usize,
+                        ace: bool,
+                        pair: Option<u8>,
+                        next_card: Card) -> u8 {
+        let value = value_index(sum, ace);
+        if value == 21 {
+            return b'=';
+        }
+
+        let soft = value != sum;
+        let (drawn, drawn_ace) = (sum + next_card.0 as usize,
+                                  ace || next_card == Card(1));
+        let drawn_value = value_index(drawn, drawn_ace);
+
+        let stand = self.with_naturals(self.stand[value], 1.0);
+        let hit = self.with_naturals(if drawn > 21 {
+            -1.0
+        } else {
+            self.stand[drawn_value].max(self.hit[drawn][drawn_ace as usize])
+        }, 1.0);
+        let double = if !double_allowed(rules, value, soft) {
+            f64::NEG_INFINITY
+        } else if drawn > 21 {
+            self.with_naturals(-2.0, 2.0)
+        } else {
+            self.with_naturals(2.0 * self.stand[drawn_value], 2.0)
+        };
+
+        if let Some(card) = pair {
+            // The first hand gets the next card, the second an unknown one
+            let card = card as usize;
+            let (sum, ace) = (card + next_card.0 as usize,
+                              card == 1 || next_card == Card(1));
+            let first = self.split_hand(rules, card, sum, ace);
+            let second = self.draw(card, card == 1, |sum, ace| {
+                self.split_hand(rules, card, sum, ace)
+            });
+            let split = self.with_naturals(first + second, 2.0);
+            if split > stand.max(hit).max(double).max(surrender(rules)) {
+                return b'V';
+            }
+        }
+
+        self.code(rules, stand, hit, double)
+    }
+
+    /// Returns the decision code given the expected value of each decision.
+    fn code(&self, rules: &GameRules, stand: f64, hit: f64, double: f64) -> u8 {
+        if surrender(rules) > stand.max(hit).max(double) {
+            if hit > stand { b'S' } else { b's' }
+        } else if double > stand.max(hit) {
+            if hit > stand { b'D' } else { b'd' }
+        } else if hit > stand {
+            b'+'
+        } else {
+            b'='
+        }
+    }
+
+    /// Whether splitting a pair of `card` is better than playing it.
+    fn splits(&self, rules: &GameRules, card: u8) -> bool {
+        let card = card as usize;
+        let ace = card == 1;
+        let split = 2.0 * self.draw(card, ace, |sum, ace| {
+            self.split_hand(rules, card, sum, ace)
+        });
+
+        self.with_naturals(split, 2.0) > surrender(rules).max(
+            self.with_naturals(self.best(rules, 2 * card, ace, true), 1.0)
+        )
+    }
+}
+
+/// The expected value of surrendering, or minus infinity when the rules do
+/// not allow it.
+fn surrender(rules: &GameRules) -> f64 {
+    if rules.surrender != NoSurrender { -0.5 } else { f64::NEG_INFINITY }
+}
+
+/// Returns the value of cards summing to `sum` with aces as 1, as an index.
+fn value_index(sum: usize, ace: bool) -> usize {
+    value_of(sum as u8, ace) as usize
+}
+
+/// Returns the cards of the rules' full shoe but the dealer's upcard.
+fn shoe_less(rules: &GameRules, upcard: Card) -> CardCounts {
+    let mut cards = shoe_counts(rules.decks);
+    cards[upcard.0 as usize - 1] -= 1;
+    cards
+}
+
+/// Whether the rules allow doubling down a two-card hand.
+fn double_allowed(rules: &GameRules, value: usize, soft: bool) -> bool {
+    match rules.double_down {
+        DoublePolicy::AnyHand | DoublePolicy::AnyTwo => true,
+        DoublePolicy::Hard9To11 => !soft && (9..=11).contains(&value),
+        DoublePolicy::Hard10To11 => !soft && (10..=11).contains(&value),
+        DoublePolicy::NoDouble => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::holecarding::{HolecardingTables, InformedTables,
+                             ten_probability};
+    use crate::strategy::HoleTell;
+    use crate::game_rules::GameRules;
+    use crate::game_rules::SurrenderPolicy::{LateSurrender, EarlySurrender};
+    use crate::game_rules::PeekPolicy::NoPeek;
+    use crate::hand::Hand;
+    use crate::card::Card;
+
+    fn make_hand(cards: &[u8]) -> Hand {
+        let mut hand = Hand::new();
+        for &card in cards {
+            hand.add(Card(card));
+        }
+        hand
+    }
+
+    fn decide(rules: &GameRules,
+              tables: &HolecardingTables,
+              cards: &[u8],
+              upcard: u8,
+              holecard: u8) -> u8 {
+        tables.decision(rules, Card(upcard), Card(holecard),
+                        &make_hand(cards), true, None)
+    }
+
+    #[test]
+    fn it_plays_against_the_dealer_hand() {
+        let rules = GameRules::default();
+        let tables = HolecardingTables::new(&rules);
+
+        // Against a dealer 16, stand on stiffs and double 11
+        assert_eq!(decide(&rules, &tables, &[10, 6], 10, 6), b'=');
+        assert_eq!(decide(&rules, &tables, &[10, 2], 6, 10), b'=');
+        assert_eq!(decide(&rules, &tables, &[6, 5], 6, 10), b'D');
+        // Against a dealer 20, draw to any lower hand, push 20
+        assert_eq!(decide(&rules, &tables, &[10, 7], 10, 10), b'+');
+        assert_eq!(decide(&rules, &tables, &[10, 9], 10, 10), b'+');
+        assert_eq!(decide(&rules, &tables, &[10, 10], 10, 10), b'=');
+        // Against a dealer 17, draw to 16 and push 17
+        assert_eq!(decide(&rules, &tables, &[10, 6], 7, 10), b'+');
+        assert_eq!(decide(&rules, &tables, &[10, 7], 7, 10), b'=');
+        assert_eq!(decide(&rules, &tables, &[1, 6], 7, 10), b'+');
+        // Split tens against a dealer 16, but not eights against a 20
+        assert_eq!(decide(&rules, &tables, &[10, 10], 6, 10), b'V');
+        assert_eq!(decide(&rules, &tables, &[8, 8], 10, 10), b'+');
+    }
+
+    #[test]
+    fn it_surrenders_known_losses() {
+        let mut rules = GameRules {
+            surrender: LateSurrender,
+            ..Default::default()
+        };
+        let tables = HolecardingTables::new(&rules);
+
+        assert_eq!(decide(&rules, &tables, &[10, 6], 10, 10), b'S');
+        assert_eq!(decide(&rules, &tables, &[10, 6], 10, 6), b'=');
+
+        rules.peek = NoPeek;
+        rules.surrender = EarlySurrender;
+        let tables = HolecardingTables::new(&rules);
+        assert_eq!(decide(&rules, &tables, &[10, 10], 10, 1), b's');
+    }
+
+    #[test]
+    fn it_plays_against_a_tell() {
+        let rules = GameRules::default();
+        let tables = InformedTables::new(&rules, Some(1.0));
+        let decide = |cards: &[u8], upcard: u8, ten: bool| {
+            let tell = HoleTell { ten, accuracy: 1.0 };
+            tables.decision(&rules, Card(upcard), Some(tell),
+                            &make_hand(cards), true, None)
+        };
+
+        // Against a ten known to hide a ten, draw to 17 and do not split
+        assert_eq!(decide(&[10, 7], 10, true), b'+');
+        assert_eq!(decide(&[10, 7], 10, false), b'=');
+        assert_eq!(decide(&[8, 8], 10, true), b'+');
+        assert_eq!(decide(&[8, 8], 10, false), b'V');
+        // Stand on 12 against a two known to hide a ten
+        assert_eq!(decide(&[9, 3], 2, true), b'=');
+        assert_eq!(decide(&[9, 3], 2, false), b'+');
+
+        let tell = |ten, accuracy| HoleTell { ten, accuracy };
+        assert!((ten_probability(tell(true, 1.0)) - 1.0).abs() < 1e-6);
+        assert!(ten_probability(tell(false, 1.0)).abs() < 1e-6);
+        assert!((ten_probability(tell(true, 0.8)) - 0.64).abs() < 1e-6);
+    }
+
+    #[test]
+    fn it_plays_the_next_card() {
+        let rules = GameRules::default();
+        let tables = InformedTables::new(&rules, None);
+        let decide = |cards: &[u8], upcard: u8, next_card: Option<u8>| {
+            tables.decision(&rules, Card(upcard), None, &make_hand(cards),
+                            true, next_card.map(Card))
+        };
+
+        // Without the next card, close to basic strategy
+        assert_eq!(decide(&[10, 6], 10, None), b'+');
+        assert_eq!(decide(&[10, 2], 4, None), b'=');
+        assert_eq!(decide(&[6, 5], 6, None), b'D');
+        // Double a 16 drawing a five, stand it drawing a ten
+        assert_eq!(decide(&[10, 6], 10, Some(5)), b'D');
+        assert_eq!(decide(&[10, 6], 10, Some(10)), b'=');
+        assert_eq!(decide(&[8, 8], 10, Some(5)), b'D');
+        assert_eq!(decide(&[6, 5], 6, Some(10)), b'D');
+    }
+}
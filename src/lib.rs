@@ -9,7 +9,14 @@ pub mod hand_stats;
 pub mod game_rules;
 pub mod strategy;
 pub mod basic_strategy;
+pub mod holecarding;
+pub mod tourist_strategy;
 pub mod deviation;
+pub mod composition;
+pub mod dealer_probabilities;
+pub mod analyzer;
+pub mod strategy_file;
+pub mod script;
 pub mod round;
 pub mod shoe;
 pub mod running_stats;
@@ -17,6 +24,9 @@ pub mod betting;
 pub mod simulator;
 pub mod smp_simulator;
 pub mod round_factory;
+pub mod side_bet;
+pub mod config;
+pub mod event;
 
 #[cfg(test)]
 mod test_utils;
@@ -3,116 +3,423 @@ extern crate regex;
 
 mod options;
 mod output;
+mod play;
+mod drill;
+mod chart;
+mod analysis;
 
-use crate::options::Options;
+use crate::options::{Options, Mode};
+use crate::drill::{Drill, DrillSession, DrillStats};
+use crate::chart::Chart;
 use crate::output::ProgramResult;
 
 use freebj::round_factory::RoundFactory;
-use freebj::game_rules::{GameRules, SurrenderPolicy};
+use freebj::game_rules::{GameRules, CharlieRule, DealerPushRule};
 use freebj::basic_strategy::BasicStrategy;
+use freebj::strategy_file::StrategyFile;
+use freebj::analyzer::Analyzer;
 use freebj::betting::{FixedBet, HiloBetting, BettingStrategy};
 use freebj::smp_simulator::SmpSimulator;
 use std::process::exit;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::env;
+use std::time::Duration;
+use rand::SeedableRng;
+use rand::rngs::SmallRng;
 use freebj::shoe::CardShoe;
 use freebj::shoe::file_shoe::FileShoe;
 use freebj::shoe::standard_shoe::StandardShoe;
-use std::collections::VecDeque;
 use freebj::card::Card;
+use freebj::config::{Config, TableConfig, SeatStrategy, CountingSystem,
+                     StrategyConfig, BettingConfig, ShoeConfig, ScenarioConfig,
+                     RunConfig};
+use freebj::tourist_strategy::TouristStrategy;
+use freebj::round::Player;
+use std::fs::{self, File};
+use std::io::{self, BufWriter};
+use freebj::event::EventLog;
 
 fn main() {
     let options = Options::from_argv();
 
-    let game_rules = GameRules {
+    let charlie_pays = options.charlie_pays;
+    let charlie_split_hands = options.charlie_split_hands;
+    let dealer_push_except_bj = options.dealer_push_except_bj;
+    let rules = GameRules {
+        preset: options.preset,
         game_type: options.game_type,
+        peek: options.peek,
+        original_bets_only: options.original_bets_only,
         soft17: options.soft17,
         das: options.das,
-        bj_pays: 1.5,
+        bj_pays: options.bj_pays,
+        insurance: options.insurance,
+        even_money: options.even_money,
         double_down: options.double,
+        double_after_hit: options.double_after_hit,
+        double_max_cards: options.double_max_cards,
+        double_for_less: options.double_for_less,
+        max_redoubles: options.max_redoubles,
         surrender: options.surrender,
+        surrender_any_cards: options.surrender_any_cards,
+        surrender_after_split: options.surrender_after_split,
+        double_rescue: options.double_rescue,
         play_ace_pairs: options.play_split_aces,
         max_splits: options.max_splits,
         decks: options.decks,
         penetration_cards: options.pen_cards,
+        charlie: options.charlie.map(|cards| CharlieRule {
+            cards,
+            pays: charlie_pays,
+            split_hands: charlie_split_hands,
+        }),
+        dealer_push: options.dealer_push.map(|total| DealerPushRule {
+            total,
+            except_naturals: dealer_push_except_bj,
+        }),
     };
 
-    if options.surrender_override.unwrap_or(false)
-        && game_rules.surrender == SurrenderPolicy::NoSurrender {
-        eprintln!("Unable to always surrender");
-        exit(2);
+    let config = Config {
+        rules,
+        table: TableConfig {
+            players: options.players,
+            seat: options.seat,
+            seats: options.seats,
+        },
+        counting: if options.hilo_counting {
+            Some(CountingSystem::Hilo)
+        } else {
+            None
+        },
+        strategy: StrategyConfig {
+            file: options.strategy_file,
+            exact: options.exact_strategy,
+            deviations: options.deviations,
+            add_deviations: options.more_devs,
+            composition: options.composition,
+            insurance_amount: options.insure_for,
+            holecarding: options.holecarding,
+            tell: options.tell,
+            next_card: options.next_card,
+        },
+        betting: BettingConfig {
+            bet: options.bet,
+            bet_per_tc: options.bet_per_tc,
+            bet_neg_tc: options.bet_neg_tc,
+            bet_max_tc: options.bet_max_tc,
+            wongout_under: options.wongout_under,
+            spots: options.spots,
+            wonging: options.wonging,
+            side_bets: options.side_bets,
+        },
+        shoe: match options.shoe_file {
+            Some(path) => ShoeConfig::File { path },
+            None => ShoeConfig::Standard,
+        },
+        scenario: ScenarioConfig {
+            start_cards: options.start_cards.map(Vec::from)
+                .unwrap_or_default(),
+            dealer_cards: options.dealer_cards.map(Vec::from)
+                .unwrap_or_default(),
+            script: options.script,
+        },
+        run: RunConfig {
+            rounds: options.rounds,
+            jobs: options.jobs,
+            seed: Some(options.seed.unwrap_or_else(rand::random)),
+            force_tc: options.force_tc,
+            information_gains: options.info_gains,
+        },
+    };
+    let game_rules = &config.rules;
+
+    if let Some(script) = &config.scenario.script {
+        if let Err(e) = script.check(game_rules) {
+            eprintln!("-a: {}", e);
+            exit(2);
+        }
+    }
+
+    if let Mode::Dealer(format) = options.mode {
+        if let Err(e) = analysis::cards_left(game_rules,
+                                             &options.removed_cards) {
+            eprintln!("--removed: {}", e);
+            exit(2);
+        }
+        if let Err(e) = analysis::write_dealer_outcomes(
+            &mut io::stdout(), game_rules, &options.removed_cards, format
+        ) {
+            eprintln!("dealer: {}", e);
+            exit(1);
+        }
+        return;
+    }
+
+    if let Mode::Calc(format) = options.mode {
+        let upcard = options.upcard.unwrap();
+        let dealt: Vec<Card> = options.removed_cards.iter()
+            .chain(&options.hand_cards)
+            .chain(Some(&upcard))
+            .copied()
+            .collect();
+        if let Err(e) = analysis::cards_left(game_rules, &dealt) {
+            eprintln!("calc: {}", e);
+            exit(2);
+        }
+        if let Err(e) = analysis::write_hand_evs(
+            &mut io::stdout(), game_rules, &options.hand_cards, upcard,
+            &options.removed_cards, format
+        ) {
+            eprintln!("calc: {}", e);
+            exit(1);
+        }
+        return;
     }
 
-    let mut strategy = BasicStrategy::new(options.hilo_counting);
-    if options.deviations {
+    let hilo_counting = config.counting == Some(CountingSystem::Hilo);
+    let mut strategy = BasicStrategy::new(hilo_counting);
+    strategy.set_insurance_amount(config.strategy.insurance_amount);
+    if config.strategy.deviations {
         strategy.set_default_deviations();
     }
-    for dev in options.more_devs {
-        strategy.add_deviation(dev);
+    if let Some(path) = &config.strategy.file {
+        let file = fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|json| StrategyFile::from_json(&json))
+            .unwrap_or_else(|e| {
+                eprintln!("--strategy: {}: {}", path, e);
+                exit(2);
+            });
+        if !file.deviations.is_empty() && !hilo_counting {
+            eprintln!("--strategy: {}: deviations require card counting",
+                      path);
+            exit(2);
+        }
+        strategy.load(file);
+    }
+    if config.strategy.exact {
+        strategy.load(Analyzer::new(game_rules).strategy());
     }
+    for dev in config.strategy.add_deviations.iter() {
+        strategy.add_deviation(dev.clone());
+    }
+    strategy.set_composition(config.strategy.composition.clone());
 
-    let betting: Box<dyn BettingStrategy + Sync>;
-    if options.hilo_counting {
-        betting = Box::new(HiloBetting::new(
-            options.bet,
-            options.bet_per_tc,
-            options.bet_neg_tc,
-            options.bet_max_tc,
-            options.wongout_under,
-        ));
-    } else {
-        betting = Box::new(FixedBet(options.bet));
+    if let Some(path) = &options.export_strategy {
+        let json = serde_json::to_string_pretty(&strategy.export()).unwrap();
+        if let Err(e) = fs::write(path, json + "\n") {
+            eprintln!("--export-strategy: {}: {}", path, e);
+            exit(2);
+        }
+        return;
     }
 
-    let adjust_rc = get_rc_adjust(options.hilo_counting,
-                                  &options.start_cards,
-                                  &options.dealer_cards);
+    if let Mode::Chart(format) = options.mode {
+        if let Err(e) = Chart::new(game_rules, &strategy)
+            .write(&mut io::stdout(), format) {
+            eprintln!("strategy: {}", e);
+            exit(1);
+        }
+        return;
+    }
 
-    let round_factory = RoundFactory::new(
-        &game_rules,
+    let betting: Box<dyn BettingStrategy + Sync> = if hilo_counting {
+        let betting = &config.betting;
+        Box::new(HiloBetting::new(
+            betting.bet,
+            betting.bet_per_tc,
+            betting.bet_neg_tc,
+            betting.bet_max_tc,
+            betting.wongout_under,
+        ).with_spots(betting.spots.iter()
+                         .map(|step| (step.true_count, step.spots))
+                         .collect()))
+    } else {
+        Box::new(FixedBet(config.betting.bet))
+    };
+
+    let basic_strategy = BasicStrategy::new(false);
+    let tourist_strategy = TouristStrategy::new();
+    let flat_bet = FixedBet(config.betting.bet);
+    let players: Vec<Player> = config.table.seats.iter()
+        .map(|seat| match seat {
+            SeatStrategy::Player => Player {
+                strategy: &strategy,
+                betting_strategy: &*betting,
+            },
+            SeatStrategy::Basic => Player {
+                strategy: &basic_strategy,
+                betting_strategy: &flat_bet,
+            },
+            SeatStrategy::Tourist => Player {
+                strategy: &tourist_strategy,
+                betting_strategy: &flat_bet,
+            },
+        })
+        .collect();
+
+    let adjust_rc = get_rc_adjust(hilo_counting,
+                                  &config.scenario.start_cards,
+                                  &config.scenario.dealer_cards);
+
+    let mut round_factory = RoundFactory::new(
+        game_rules,
         &strategy,
         &*betting,
-        1,
-        options.holecarding,
-        options.override_action,
-        options.surrender_override,
-        options.start_cards.unwrap_or_default(),
-        options.dealer_cards.unwrap_or_default(),
-    );
-
-    let shoe_factory: Box<dyn Fn() -> Box<dyn CardShoe + Send>>;
-    if let Some(shoe_file) = options.shoe_file {
-        shoe_factory = Box::new(move || -> Box<dyn CardShoe + Send> {
+        config.table.players,
+        config.strategy.holecarding,
+        config.scenario.start_cards.iter().copied().collect(),
+        config.scenario.dealer_cards.iter().copied().collect(),
+    )
+        .with_side_bets(&config.betting.side_bets)
+        .with_seat(config.table.seat - 1);
+    if !players.is_empty() {
+        round_factory = round_factory.with_players(players);
+    }
+    if let Some(wonging) = config.betting.wonging {
+        round_factory = round_factory.with_wonging(wonging);
+    }
+    if let Some(script) = &config.scenario.script {
+        round_factory = round_factory.with_script(script.clone());
+    }
+    if let Some(accuracy) = config.strategy.tell {
+        round_factory = round_factory.with_tell(accuracy);
+    }
+    if config.strategy.next_card {
+        round_factory = round_factory.with_next_card();
+    }
+
+    let shoe_factory: Box<dyn Fn(u32) -> Box<dyn CardShoe + Send>>;
+    if let ShoeConfig::File { path } = &config.shoe {
+        let shoe_file = path.clone();
+        shoe_factory = Box::new(move |_| -> Box<dyn CardShoe + Send> {
             Box::new(FileShoe::new(Path::new(&shoe_file)).unwrap())
         });
     } else {
-        let num_decks = options.decks;
-        let pen_cards = options.pen_cards;
-        shoe_factory = Box::new(move || -> Box<dyn CardShoe + Send> {
-            Box::new(StandardShoe::shuffled(num_decks, pen_cards))
+        let num_decks = game_rules.decks;
+        let pen_cards = game_rules.penetration_cards;
+        // Each job gets its own shoe, seeded from the seed plus its index
+        let seed = config.run.seed.unwrap();
+        shoe_factory = Box::new(move |job| -> Box<dyn CardShoe + Send> {
+            let seed = seed.wrapping_add(job as u64);
+            Box::new(StandardShoe::seeded(num_decks, pen_cards, seed))
         });
     }
 
-    let real_num_rounds;
-    if options.dry_run {
-        real_num_rounds = 0;
-    } else {
-        real_num_rounds = options.rounds;
+    if options.mode == Mode::Play {
+        // A shoe file never reshuffles: the rounds are dealt from a shuffled
+        // shoe whatever the shoe of the simulations
+        let mut shoe = StandardShoe::seeded(game_rules.decks,
+                                            game_rules.penetration_cards,
+                                            config.run.seed.unwrap());
+        let stdin = io::stdin();
+        if let Err(e) = play::play(game_rules, &strategy, &*betting,
+                                   &mut shoe, hilo_counting,
+                                   config.strategy.holecarding,
+                                   &mut stdin.lock(), &mut io::stdout()) {
+            eprintln!("play: {}", e);
+            exit(1);
+        }
+        return;
     }
 
-    let simulator = SmpSimulator::new(
+    if let Mode::Drill(kind) = options.mode {
+        let path = match &options.drill_stats {
+            Some(path) => PathBuf::from(path),
+            None => env::var_os("HOME").map(PathBuf::from).unwrap_or_default()
+                .join(".freebj_drills.json"),
+        };
+        let mut stats = DrillStats::load(&path).unwrap_or_else(|e| {
+            eprintln!("--stats: {}", e);
+            exit(2);
+        });
+
+        // The drills need the count, which only standard shoes keep
+        let mut shoe = StandardShoe::seeded(game_rules.decks,
+                                            game_rules.penetration_cards,
+                                            config.run.seed.unwrap());
+        let mut rng = SmallRng::seed_from_u64(config.run.seed.unwrap());
+        let stdin = io::stdin();
+        let mut input = stdin.lock();
+        let mut output = io::stdout();
+
+        let mut session = DrillSession::new(&mut input, &mut output);
+        let drilled = match kind {
+            Drill::Count => drill::count_drill(
+                &mut session, &mut shoe, &mut rng,
+                Duration::from_millis(options.flash_ms),
+            ),
+            Drill::TrueCount => drill::true_count_drill(
+                &mut session, &mut shoe, &mut rng,
+            ),
+            Drill::Deviations => drill::deviation_drill(
+                &mut session, game_rules, &strategy, &mut rng,
+            ),
+        };
+        let session = session.finish();
+        if let Err(e) = drilled {
+            eprintln!("drill: {}", e);
+            exit(1);
+        }
+
+        let total = stats.add(kind, &session);
+        if let Err(e) = stats.save(&path) {
+            eprintln!("--stats: {}", e);
+            exit(2);
+        }
+        drill::report(&mut io::stdout(), &session, &total)
+            .expect("Couldn't write the drill's report");
+        return;
+    }
+
+    // The side bets are settled on the cards' suits
+    if !config.betting.side_bets.is_empty() && !shoe_factory(0).is_suited() {
+        eprintln!("--side-bet: the shoe's cards have no suits");
+        exit(2);
+    }
+
+    let real_num_rounds = if options.dry_run { 0 } else { config.run.rounds };
+
+    let mut simulator = SmpSimulator::new(
         real_num_rounds,
         round_factory,
         shoe_factory,
-        options.force_tc,
+        config.run.force_tc,
         adjust_rc,
-        options.jobs,
+        config.run.jobs,
         options.verbose,
-    );
+    )
+        // The tells are misread apart from the shoes' shuffles
+        .with_seed(!config.run.seed.unwrap());
+    if config.run.information_gains {
+        simulator = simulator.with_information_gains();
+    }
+
+    if let Some(path) = &options.event_log {
+        match File::create(path) {
+            Ok(file) => {
+                let writer = Box::new(BufWriter::new(file));
+                simulator = simulator.with_event_log(EventLog::new(writer));
+            },
+            Err(e) => {
+                eprintln!("--event-log: {}: {}", path, e);
+                exit(2);
+            },
+        }
+    }
+
+    let simulation = match simulator.run() {
+        Ok(simulation) => simulation,
+        Err(e) => {
+            eprintln!("-a: {}", e);
+            exit(2);
+        },
+    };
 
     let result = ProgramResult {
-        rounds: options.rounds,
-        rules: &game_rules,
-        simulation: simulator.run(),
+        rounds: config.run.rounds,
+        config: &config,
+        simulation,
     };
 
     let json = serde_json::to_string_pretty(&result).unwrap();
@@ -125,7 +432,7 @@ fn main() {
 /// # Parameters
 ///
 ///  * `hilo_counting` - Whether hi-lo card counting is enabled, if false no
-///                      adjustement is required;
+///    adjustement is required;
 ///  * `start_cards` - Players' starting cards;
 ///  * `dealer_cards` - Dealer's starting cards.
 ///
@@ -134,26 +441,15 @@ fn main() {
 ///
 /// FIXME: return `i32` only
 fn get_rc_adjust(hilo_counting: bool,
-                 start_cards: &Option<VecDeque<Card>>,
-                 dealer_cards: &Option<VecDeque<Card>>) -> Option<i32> {
+                 start_cards: &[Card],
+                 dealer_cards: &[Card]) -> Option<i32> {
     if hilo_counting {
         let mut rel_rc = 0;
-        if let Some(cards) = start_cards {
-            for c in cards.iter() {
-                if c.0 == 1 || c.0 == 10 {
-                    rel_rc += 1;
-                } else if c.0 < 7 {
-                    rel_rc -= 1;
-                }
-            }
-        }
-        if let Some(cards) = dealer_cards {
-            for c in cards.iter() {
-                if c.0 == 1 || c.0 == 10 {
-                    rel_rc += 1;
-                } else if c.0 < 7 {
-                    rel_rc -= 1;
-                }
+        for c in start_cards.iter().chain(dealer_cards.iter()) {
+            if c.0 == 1 || c.0 == 10 {
+                rel_rc += 1;
+            } else if c.0 < 7 {
+                rel_rc -= 1;
             }
         }
         if rel_rc != 0 {
@@ -1,33 +1,91 @@
 use freebj::card::Card;
-use freebj::game_rules::{SurrenderPolicy, DoublePolicy, GameType, Soft17};
+use freebj::hand::Hand;
+use freebj::game_rules::{GameRules, SurrenderPolicy, DoublePolicy, GameType,
+                         Soft17, PeekPolicy, PRESETS};
 use freebj::game_rules::SurrenderPolicy::NoSurrender;
 use freebj::game_rules::DoublePolicy::AnyTwo;
 use std::process::exit;
 use clap::{ArgMatches, crate_version};
 use freebj::game_rules::GameType::{Ahc, Enhc};
 use freebj::game_rules::Soft17::{S17, H17};
-use freebj::strategy::Decision;
 use std::collections::VecDeque;
 use std::convert::TryFrom;
 use regex::Regex;
 use freebj::deviation::Deviation;
+use freebj::composition::{CompositionRule, COMPOSITION_TABLES};
+use freebj::side_bet::SideBetWager;
 use std::str::FromStr;
+use std::fs;
+use freebj::betting::Wonging;
+use freebj::script::Script;
+use freebj::config::{Config, CountingSystem, ShoeConfig, SeatStrategy,
+                     SpotsStep};
+use crate::drill::Drill;
+use crate::chart::ChartFormat;
+use crate::analysis::AnalysisFormat;
+
+/// What the program does with the game it is given
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Mode {
+    /// Simulate rounds and print the results
+    Simulate,
+    /// Play rounds interactively, each decision checked against the strategy
+    Play,
+    /// Train with a drill
+    Drill(Drill),
+    /// Write a chart of the strategy
+    Chart(ChartFormat),
+    /// Write the exact probabilities of the dealer's outcomes
+    Dealer(AnalysisFormat),
+    /// Write the exact expected values of the actions of a hand
+    Calc(AnalysisFormat),
+}
 
 #[derive(Debug)]
 pub struct Options {
+    pub mode:           Mode,
+    /// The file keeping the records of the drills, by default in the home
+    pub drill_stats:    Option<String>,
+    /// How long each card is flashed in the running count drill
+    pub flash_ms:       u64,
+    /// The cards dealt from the shoe before an exact analysis
+    pub removed_cards:  Vec<Card>,
+    /// The player's cards and the dealer's upcard of an exact analysis
+    pub hand_cards:     Vec<Card>,
+    pub upcard:         Option<Card>,
+
     /// The number of playing rounds to simulate
     pub rounds:         u64,
 
     /// The number of processing jobs (threads) the simulator will use
     pub jobs:           u32,
 
+    /// The number of players at the table
+    pub players:        u8,
+
+    /// The player's seat at the table, from 1 (first base)
+    pub seat:           u8,
+
+    /// The strategy played at each seat, empty if all seats play the player's
+    pub seats:          Vec<SeatStrategy>,
+
+    /// The name of the rule-set preset the rules are based on, if any
+    pub preset:         Option<String>,
+
     /// Whether to play American holecard (AHC) or European no-holecard (ENHC)
     pub game_type:      GameType,
 
+    /// The upcards the dealer peeks under for a natural (AHC only)
+    pub peek:           PeekPolicy,
+
+    /// Whether only original bets are lost to a dealer natural not peeked
+    pub original_bets_only: bool,
+
     /// Whether to hit dealer soft 17, or stand on dealer soft 17
     pub soft17:         Soft17,
 
     /// The amount of money the player starts with
+    #[allow(dead_code)]
     pub start_bankroll: u64,
 
     /// The maximum number of hands a player can have by splitting pairs
@@ -43,8 +101,18 @@ pub struct Options {
     /// Double after split, allows player to double split hands
     pub das:            bool,
 
+    /// How much a player's natural pays for a bet of 1.0
+    pub bj_pays:        f64,
+
     pub surrender:      SurrenderPolicy,
+    pub surrender_any_cards: bool,
+    pub surrender_after_split: bool,
+    pub double_rescue:  bool,
     pub double:         DoublePolicy,
+    pub double_after_hit: DoublePolicy,
+    pub double_max_cards: Option<u8>,
+    pub double_for_less: bool,
+    pub max_redoubles:  u8,
     pub pen_cards:      u32,
     pub hilo_counting:  bool,
     pub bet:            f64,
@@ -52,51 +120,120 @@ pub struct Options {
     pub bet_neg_tc:     Option<f64>,
     pub bet_max_tc:     Option<f32>,
     pub wongout_under:  Option<f32>,
+    /// The number of spots played from a true count on
+    pub spots:          Vec<SpotsStep>,
+    pub wonging:        Option<Wonging>,
     pub deviations:     bool,
     pub more_devs:      Vec<Deviation>,
+    /// The composition-dependent exceptions to the strategy tables
+    pub composition:    Vec<CompositionRule>,
+    /// The strategy file played instead of the built-in tables
+    pub strategy_file:  Option<String>,
+    /// Whether to play the exact strategy of the rules instead of the
+    /// built-in tables
+    pub exact_strategy: bool,
+    /// The file the strategy played is written to, instead of simulating
+    pub export_strategy: Option<String>,
+    pub side_bets:      Vec<SideBetWager>,
     pub force_tc:       Option<f32>,
+    /// Whether to measure the EV gained from each information source
+    pub info_gains:     bool,
     pub holecarding:    bool,
+    /// The accuracy of a tell on whether the holecard is a ten
+    pub tell:           Option<f32>,
+    pub next_card:      bool,
+    pub charlie:        Option<u8>,
+    pub charlie_pays:   f64,
+    pub charlie_split_hands: bool,
+    pub dealer_push:    Option<u8>,
+    pub dealer_push_except_bj: bool,
+    pub insurance:      bool,
+    pub even_money:     bool,
+    pub insure_for:     f64,
     pub start_cards:    Option<VecDeque<Card>>,
     pub dealer_cards:   Option<VecDeque<Card>>,
-    pub override_action: Option<Decision>,
-    pub surrender_override: Option<bool>,
+    /// The script played instead of the player's strategy
+    pub script:         Option<Script>,
     pub verbose:        bool,
+    /// The file the events of each round are written to, as JSON Lines
+    pub event_log:      Option<String>,
     pub dry_run:        bool,
     pub shoe_file:      Option<String>,
+    pub seed:           Option<u64>,
 }
 
 impl Default for Options {
+    #[allow(clippy::inconsistent_digit_grouping)]
     fn default() -> Self {
         Options {
+            mode:           Mode::Simulate,
+            drill_stats:    None,
+            flash_ms:       700,
+            removed_cards:  Vec::new(),
+            hand_cards:     Vec::new(),
+            upcard:         None,
             rounds:         1_000_000,
             jobs:           4,
+            players:        1,
+            seat:           1,
+            seats:          Vec::new(),
+            preset:         None,
             game_type:      Ahc,
+            peek:           PeekPolicy::PeekAceTen,
+            original_bets_only: false,
             soft17:         S17,
             start_bankroll: 1_000_00,
             max_splits:     4,
             decks:          6,
             play_split_aces: false,
             das:            false,
+            bj_pays:        1.5,
             surrender:      NoSurrender,
+            surrender_any_cards: false,
+            surrender_after_split: false,
+            double_rescue:  false,
             double:         AnyTwo,
-            pen_cards:      5 * 52,
+            double_after_hit: DoublePolicy::NoDouble,
+            double_max_cards: None,
+            double_for_less: false,
+            max_redoubles:  0,
+            pen_cards:      250, // 80 % of 6 decks
             hilo_counting:  false,
             bet:            1.0,
             bet_per_tc:     1.0,
             bet_neg_tc:     None,
             bet_max_tc:     None,
             wongout_under:  None,
+            spots:          Vec::new(),
+            wonging:        None,
             deviations:     false,
             more_devs:      Vec::new(),
+            composition:    Vec::new(),
+            strategy_file:  None,
+            exact_strategy: false,
+            export_strategy: None,
+            side_bets:      Vec::new(),
             force_tc:       None,
+            info_gains:     false,
             holecarding:    false,
+            tell:           None,
+            next_card:      false,
+            charlie:        None,
+            charlie_pays:   1.0,
+            charlie_split_hands: true,
+            dealer_push:    None,
+            dealer_push_except_bj: false,
+            insurance:      true,
+            even_money:     true,
+            insure_for:     0.5,
             start_cards:    None,
             dealer_cards:   None,
-            override_action: None,
-            surrender_override: None,
+            script:         None,
             verbose:        false,
+            event_log:      None,
             dry_run:        false,
             shoe_file:      None,
+            seed:           None,
         }
     }
 }
@@ -115,9 +252,14 @@ impl Options {
             .arg(
                 clap::Arg::with_name("action").short("a")
                     .takes_value(true)
-                    .help("Always play ACTION as the first action on each \
-                       hand, bypassing strategies. Possible actions: +: hit; \
-                       =: stand; D: double; V: split; #: surrender.")
+                    .value_name("SCRIPT")
+                    .help("Play the decisions of SCRIPT on each of the \
+                       player's hands, bypassing strategies: the decisions of \
+                       each hand, separated by commas in the order the hands \
+                       are played, the strategy taking over once done. \
+                       Possible actions: +: hit; =: stand; D: double; V: \
+                       split; or # alone: surrender. Example: \"VD,=\" splits, \
+                       doubles the first hand, and stands the second.")
             )
             .arg(
                 clap::Arg::with_name("rounds").short("n")
@@ -125,6 +267,14 @@ impl Options {
                     .help("The number of game rounds to simulate.\n\
                         Accepts 'k', 'M', and 'G' suffixes. Example: \"100M\".")
             )
+            .arg(
+                clap::Arg::with_name("preset").long("preset")
+                    .takes_value(true)
+                    .value_name("PRESET")
+                    .possible_values(&PRESETS)
+                    .help("Use the complete rules of a well-known table, \
+                    which other rule options override.")
+            )
             .arg(
                 clap::Arg::with_name("ahc").long("ahc")
                     .help("Use the American holecard game rule.")
@@ -133,6 +283,22 @@ impl Options {
                 clap::Arg::with_name("enhc").long("enhc")
                     .help("Use the European no-holecard game rule.")
             )
+            .arg(
+                clap::Arg::with_name("peek").long("peek")
+                    .takes_value(true)
+                    .value_name("POLICY")
+                    .possible_values(&["ace-ten", "ace", "ten", "none"])
+                    .help("The upcards the dealer peeks under for a natural \
+                    in an AHC game: ace or ten, ace only, ten only, or none \
+                    (the holecard is dealt but not checked). Incompatible \
+                    with --enhc. Default: \"ace-ten\".")
+            )
+            .arg(
+                clap::Arg::with_name("obo").long("obo")
+                    .help("Only lose original bets, not doubles and splits, \
+                    to a dealer natural which was not peeked (ENHC or no \
+                    peek).")
+            )
             .arg(
                 clap::Arg::with_name("s17").long("s17")
                     .help("Stand on dealer soft 17 (S17).")
@@ -151,12 +317,47 @@ impl Options {
                     .help("Disallow doubling down on split hands (double after \
                     split - DAS).")
             )
+            .arg(
+                clap::Arg::with_name("bj_pays").long("bj-pays")
+                    .takes_value(true)
+                    .value_name("PAYS")
+                    .help("How much a player's natural pays for a bet of 1, \
+                    e.g. \"1.2\" for 6:5. Default: \"1.5\".")
+            )
             .arg(
                 clap::Arg::with_name("jobs").short("j")
                     .takes_value(true)
                     .help("The number of processing jobs, should be equal to \
                     the number of CPUs.")
             )
+            .arg(
+                clap::Arg::with_name("players").long("players")
+                    .takes_value(true)
+                    .value_name("PLAYERS")
+                    .help("The number of players at the table, from 1 to 7, \
+                    all playing the same strategy. Default: 1.")
+            )
+            .arg(
+                clap::Arg::with_name("seat").long("seat")
+                    .takes_value(true)
+                    .value_name("SEAT")
+                    .help("The player's seat at the table, from 1 (first \
+                    base, played first) to the number of players; the other \
+                    seats are reported separately. Default: 1.")
+            )
+            .arg(
+                clap::Arg::with_name("seats").long("seats")
+                    .takes_value(true)
+                    .value_name("STRATEGIES")
+                    .help("The comma-separated strategies played at each \
+                    seat, from first base, which sets the number of players: \
+                    \"player\" for the player's strategy and bets as set by \
+                    the other options, \"basic\" for basic strategy with a \
+                    flat bet, and \"tourist\" for the common mistakes of a \
+                    tourist with a flat bet. The player's seat defaults to the \
+                    first \"player\" seat.\n\
+                    Example: \"tourist,basic,basic,player\".")
+            )
             .arg(
                 clap::Arg::with_name("decks").short("d")
                     .takes_value(true)
@@ -193,6 +394,10 @@ impl Options {
                 clap::Arg::with_name("esurr").long("esurr")
                     .help("Allow early surrender.")
             )
+            .arg(
+                clap::Arg::with_name("esurr_10").long("esurr-10")
+                    .help("Allow early surrender against a dealer ten only.")
+            )
             .arg(
                 clap::Arg::with_name("lsurr").long("lsurr")
                     .help("Allow late surrender.")
@@ -201,6 +406,17 @@ impl Options {
                 clap::Arg::with_name("no_surr").long("no-surr")
                     .help("Disallow any form of surrender.")
             )
+            .arg(
+                clap::Arg::with_name("surr_any_cards").long("surr-any-cards")
+                    .help("Allow surrendering after hitting, on any number of \
+                    cards. Requires a surrender policy.")
+            )
+            .arg(
+                clap::Arg::with_name("surr_after_split")
+                    .long("surr-after-split")
+                    .help("Allow surrendering split hands. Requires a \
+                    surrender policy.")
+            )
             .arg(
                 clap::Arg::with_name("double_any").long("db-any")
                     .help("Allow doubling down on any hand regardless of the \
@@ -225,10 +441,123 @@ impl Options {
                 clap::Arg::with_name("double_none").long("db-none")
                     .help("Disallow doubling down on all hands.")
             )
+            .arg(
+                clap::Arg::with_name("double_after_hit").long("db-after-hit")
+                    .takes_value(true)
+                    .value_name("POLICY")
+                    .possible_values(&["any", "hard-9-11", "hard-10-11", "none"])
+                    .help("Allow doubling down on hands of three cards or \
+                    more: any hand, hard hands of 9 to 11, or hard hands of 10 \
+                    to 11; the same policy applies whatever the number of \
+                    cards, up to --db-max-cards. Default: \"none\".")
+            )
+            .arg(
+                clap::Arg::with_name("double_max_cards").long("db-max-cards")
+                    .takes_value(true)
+                    .help("The maximum number of cards a hand may have to be \
+                    doubled down.")
+            )
+            .arg(
+                clap::Arg::with_name("double_for_less").long("db-for-less")
+                    .help("Allow doubling down for less than the hand's \
+                    stake.")
+            )
+            .arg(
+                clap::Arg::with_name("double_rescue").long("db-rescue")
+                    .help("Allow surrendering a doubled-down hand after \
+                    receiving its card, losing the original bet (double-down \
+                    rescue). The built-in strategy rescues hard 16 and under \
+                    against an 8, 9, 10 or ace, an approximation which \
+                    ignores the strategy tables, deviations and the exact \
+                    expected values.")
+            )
+            .arg(
+                clap::Arg::with_name("redoubles").long("redoubles")
+                    .takes_value(true)
+                    .help("The number of times a doubled-down hand may be \
+                    doubled again (re-doubling). Default: 0.")
+            )
             .arg(
                 clap::Arg::with_name("holecarding").long("holecarding")
                     .help("Use holecarding strategy where the dealer's \
-                    holecard is known to the players. Requires --ahc.")
+                    holecard is known to the players: each hand is played \
+                    against the dealer's two cards. Requires --ahc.")
+            )
+            .arg(
+                clap::Arg::with_name("tell").long("tell")
+                    .takes_value(true)
+                    .value_name("ACCURACY")
+                    .conflicts_with("warp")
+                    .help("The players read from a tell whether the dealer's \
+                    holecard is a ten, right with probability ACCURACY, \
+                    above 0.5 and up to 1. Requires --ahc.")
+            )
+            .arg(
+                clap::Arg::with_name("warp").long("warp")
+                    .help("The players read from a warp whether the dealer's \
+                    holecard is a ten, always right; same as --tell 1. \
+                    Requires --ahc.")
+            )
+            .arg(
+                clap::Arg::with_name("next_card").long("next-card")
+                    .help("The players know the next card off the shoe \
+                    before each of their decisions (first-basing).")
+            )
+            .arg(
+                clap::Arg::with_name("info_gains").long("info-gains")
+                    .help("Measure the EV gained from each information source \
+                    of --holecarding, --tell, and --next-card, by simulating \
+                    the rounds again on the same shuffles knowing each source \
+                    alone, and knowing none.")
+            )
+            .arg(
+                clap::Arg::with_name("charlie").long("charlie")
+                    .takes_value(true)
+                    .value_name("CARDS")
+                    .help("Enable the Charlie rule: a player hand of CARDS \
+                    cards that has not busted wins automatically, unless the \
+                    dealer has a natural. Typically 5, 6, or 7.")
+            )
+            .arg(
+                clap::Arg::with_name("charlie_pays").long("charlie-pays")
+                    .takes_value(true)
+                    .help("How much a Charlie pays for a bet of 1.0, requires \
+                    --charlie. Default: 1.0.")
+            )
+            .arg(
+                clap::Arg::with_name("charlie_no_split").long("charlie-no-split")
+                    .help("Only apply the Charlie rule to non-split hands, \
+                    requires --charlie.")
+            )
+            .arg(
+                clap::Arg::with_name("dealer_push").long("dealer-push")
+                    .takes_value(true)
+                    .value_name("TOTAL")
+                    .help("A dealer busting with exactly TOTAL pushes all \
+                    non-busted player hands instead of losing; typically 22.")
+            )
+            .arg(
+                clap::Arg::with_name("dealer_push_except_bj")
+                    .long("dealer-push-except-bj")
+                    .help("Player naturals still win against the dealer push \
+                    total, requires --dealer-push.")
+            )
+            .arg(
+                clap::Arg::with_name("no_insurance").long("no-insurance")
+                    .help("Do not offer insurance against a dealer ace.")
+            )
+            .arg(
+                clap::Arg::with_name("no_even_money").long("no-even-money")
+                    .help("Do not offer even money to naturals against a \
+                    dealer ace.")
+            )
+            .arg(
+                clap::Arg::with_name("insure_for").long("insure-for")
+                    .takes_value(true)
+                    .value_name("RATIO")
+                    .help("How much of the bet the player insures for when \
+                    taking insurance, up to 0.5 (full insurance). \
+                    Default: 0.5.")
             )
             .arg(
                 clap::Arg::with_name("start_cards").short("c")
@@ -267,6 +596,38 @@ impl Options {
                     .help("The maximum true count value to take into account \
                     for the betting strategy, TC above won't increase the bet.")
             )
+            .arg(
+                clap::Arg::with_name("spots").long("spots")
+                    .takes_value(true)
+                    .value_name("TC:SPOTS,...")
+                    .help("Spread to several spots at high counts, e.g. \
+                    \"2:2,4:3\" plays two spots from a true count of 2 and \
+                    three from 4, each with the bet of the ramp; the \
+                    results are summed over the spots. Requires card \
+                    counting.")
+            )
+            .arg(
+                clap::Arg::with_name("wong_in").long("wong-in")
+                    .takes_value(true)
+                    .allow_hyphen_values(true)
+                    .value_name("TC")
+                    .help("Back-count: watch the rounds without playing until \
+                    the true count reaches TC. Requires card counting.")
+            )
+            .arg(
+                clap::Arg::with_name("wong_out").long("wong-out")
+                    .takes_value(true)
+                    .allow_hyphen_values(true)
+                    .value_name("TC")
+                    .help("Leave the shoe when the true count falls under TC, \
+                    then watch the rounds until entering again. Requires card \
+                    counting.")
+            )
+            .arg(
+                clap::Arg::with_name("no_mid_shoe_entry")
+                    .long("no-mid-shoe-entry")
+                    .help("When wonging, only enter at the start of a shoe.")
+            )
             .arg(
                 clap::Arg::with_name("hilo").long("hilo")
                     .help("Count cards using hilo system, this will adapt the \
@@ -299,6 +660,71 @@ impl Options {
                     Example: \"16vs10:>+1=\" (stand at TC 1 or above with a \
                     hard 16 against a dealer 10).")
             )
+            .arg(
+                clap::Arg::with_name("strategy").long("strategy")
+                    .takes_value(true)
+                    .value_name("FILE")
+                    .help("Play the strategy of a JSON strategy file: its \
+                    hard, soft and pair tables replace the built-in ones, \
+                    and its deviations and composition rules are added; see \
+                    --export-strategy.")
+            )
+            .arg(
+                clap::Arg::with_name("exact_strategy")
+                    .long("exact-strategy")
+                    .conflicts_with("strategy")
+                    .help("Play the optimal total-dependent strategy for the \
+                    game rules and number of decks, computed by \
+                    combinatorial analysis, instead of the built-in tables; \
+                    pairs are resplit up to --max-splits hands, and hands \
+                    doubled down on their first cards only.")
+            )
+            .arg(
+                clap::Arg::with_name("export_strategy")
+                    .long("export-strategy")
+                    .takes_value(true)
+                    .value_name("FILE")
+                    .help("Write the player's strategy to FILE as a JSON \
+                    strategy file, tables, deviations and composition rules \
+                    included, instead of simulating.")
+            )
+            .arg(
+                clap::Arg::with_name("composition").long("composition")
+                    .takes_value(true)
+                    .value_name("TABLE")
+                    .help("Play a composition-dependent strategy: the hands \
+                    of TABLE's rules are played by their cards rather than \
+                    their total. TABLE is single-deck or double-deck for the \
+                    built-in exceptions of these games, or a file of rules, \
+                    one per line, as \"<HAND>vs<DEALER>:<ACTION>\"; HAND is \
+                    cards (\"10-6\", \"8-4-4\"), or a hard or soft total \
+                    (\"16\", \"A7\") of at least N cards with \"(N+)\"; \
+                    ACTION is +, =, D, d, V, S or s, as in the strategy \
+                    tables.\n\
+                    Example: \"16(3+)vs10:=\" (stand with a hard 16 of three \
+                    cards or more against a dealer 10).")
+            )
+            .arg(
+                clap::Arg::with_name("side_bet").long("side-bet")
+                    .takes_value(true)
+                    .multiple(true)
+                    .number_of_values(1)
+                    .value_name("SIDE_BET")
+                    .help("Play a side bet using the SIDE_BET directive; its \
+                    syntax is \"<NAME>[:<PAYS>][@<TC>]\", NAME is one of \
+                    \"21+3\", \"perfect-pairs\", \"lucky-ladies\", or \
+                    \"buster\"; PAYS is the side bet's comma-separated \
+                    paytable; TC is the true count from which to place the \
+                    side bet, which requires card counting. This option can be \
+                    repeated to play more side bets.\n\
+                    Example: \"perfect-pairs:5,10,30\".")
+            )
+            .arg(
+                clap::Arg::with_name("side_bet_amount").long("side-bet-amount")
+                    .takes_value(true)
+                    .value_name("BET")
+                    .help("The amount wagered on each side bet. Default: 1.0.")
+            )
             .arg(
                 clap::Arg::with_name("force_tc").long("force-tc")
                     .takes_value(true)
@@ -313,6 +739,21 @@ impl Options {
                     .help("Provide a binary file of cards to load into the \
                     card shoe. The file contains bytes from 1 to 10 included.")
             )
+            .arg(
+                clap::Arg::with_name("seed").long("seed")
+                    .takes_value(true)
+                    .help("The seed of the shoe shuffles, to reproduce a \
+                    simulation with the same number of jobs. Default: a \
+                    random seed, reported in the results.")
+            )
+            .arg(
+                clap::Arg::with_name("config").long("config")
+                    .takes_value(true)
+                    .value_name("FILE")
+                    .help("Load a whole simulation from a JSON experiment \
+                    configuration file, as included in the results; other \
+                    options override it.")
+            )
             .arg(
                 clap::Arg::with_name("dry_run").long("dry-run")
                     .help("Do not perform any actual work; useful to extract \
@@ -322,6 +763,140 @@ impl Options {
                 clap::Arg::with_name("verbose").short("v")
                     .help("Print verbose details on each round.")
             )
+            .arg(
+                clap::Arg::with_name("event_log").long("event-log")
+                    .takes_value(true)
+                    .value_name("FILE")
+                    .help("Write the events of each round to FILE as JSON \
+                    Lines: the shoe's state, the cards dealt, every decision \
+                    and its context, the dealer's draws, and the settlement \
+                    of each hand. Use -j1 for the rounds to be in order.")
+            )
+            .subcommand(
+                clap::SubCommand::with_name("play")
+                    .about("Play rounds heads-up against the dealer with the \
+                    game given by the options, which go before the command: \
+                    the player's decisions are read from the standard input, \
+                    each one checked against the strategy, deviations and \
+                    count included.")
+            )
+            .subcommand(
+                clap::SubCommand::with_name("strategy")
+                    .about("Print a chart of the player's strategy for the \
+                    game given by the options, which go before the command: \
+                    the action played with each starting hand against each \
+                    dealer upcard, resolved for the rules, with the \
+                    deviations' true count indices overlaid.")
+                    .arg(
+                        clap::Arg::with_name("format").long("format")
+                            .takes_value(true)
+                            .value_name("FORMAT")
+                            .possible_values(&["text", "markdown", "html",
+                                               "svg"])
+                            .help("The format of the chart: plain text, \
+                            Markdown, or colour-coded HTML or SVG. Default: \
+                            text.")
+                    )
+            )
+            .subcommand(
+                clap::SubCommand::with_name("dealer")
+                    .about("Print the exact probabilities of the dealer's \
+                    final totals, 17 to 21, blackjack and bust, from each \
+                    upcard, for the shoe and rules given by the options, \
+                    which go before the command. Under the upcards the \
+                    dealer peeks under, they are once the dealer has no \
+                    natural.")
+                    .arg(
+                        clap::Arg::with_name("format").long("format")
+                            .takes_value(true)
+                            .value_name("FORMAT")
+                            .possible_values(&["text", "json"])
+                            .help("The format of the probabilities: a plain \
+                            text table, or JSON. Default: text.")
+                    )
+                    .arg(
+                        clap::Arg::with_name("removed").long("removed")
+                            .takes_value(true)
+                            .value_name("CARDS")
+                            .help("The cards already dealt from the shoe, \
+                            comma-separated (\"10,6\"); the upcard is always \
+                            removed.")
+                    )
+            )
+            .subcommand(
+                clap::SubCommand::with_name("calc")
+                    .about("Print the exact expected value of each action of \
+                    a player hand against a dealer upcard, for the shoe and \
+                    rules given by the options, which go before the command: \
+                    standing, hitting, doubling down, splitting with \
+                    resplits, and surrendering, once the dealer has no \
+                    natural when they peek.")
+                    .arg(
+                        clap::Arg::with_name("hand")
+                            .required(true)
+                            .multiple(true)
+                            .use_delimiter(true)
+                            .min_values(2)
+                            .value_name("CARD")
+                            .help("The player's cards (\"10 6\" or \
+                            \"10,6\").")
+                    )
+                    .arg(
+                        clap::Arg::with_name("vs").long("vs")
+                            .required(true)
+                            .takes_value(true)
+                            .value_name("UPCARD")
+                            .help("The dealer's upcard.")
+                    )
+                    .arg(
+                        clap::Arg::with_name("format").long("format")
+                            .takes_value(true)
+                            .value_name("FORMAT")
+                            .possible_values(&["text", "json"])
+                            .help("The format of the expected values: plain \
+                            text, or JSON. Default: text.")
+                    )
+                    .arg(
+                        clap::Arg::with_name("removed").long("removed")
+                            .takes_value(true)
+                            .value_name("CARDS")
+                            .help("The other cards already dealt from the \
+                            shoe, comma-separated (\"10,6\"); the hand and \
+                            the upcard are always removed.")
+                    )
+            )
+            .subcommand(
+                clap::SubCommand::with_name("drill")
+                    .about("Train with a drill on the game given by the \
+                    options, which go before the command: keep the running \
+                    count of cards flashed from the shoe (count), convert \
+                    running counts to true counts (true-count), or play the \
+                    hands of the strategy's deviations (deviations). Enter q \
+                    to stop.")
+                    .arg(
+                        clap::Arg::with_name("drill")
+                            .required(true)
+                            .possible_values(&["count", "true-count",
+                                               "deviations"])
+                            .help("The drill to train with.")
+                    )
+                    .arg(
+                        clap::Arg::with_name("stats").long("stats")
+                            .takes_value(true)
+                            .value_name("FILE")
+                            .help("The file keeping the accuracy and speed \
+                            of each drill across sessions. Default: \
+                            ~/.freebj_drills.json.")
+                    )
+                    .arg(
+                        clap::Arg::with_name("flash").long("flash")
+                            .takes_value(true)
+                            .value_name("MS")
+                            .help("How long each card is shown in the count \
+                            drill, in milliseconds; 0 shows the cards \
+                            together. Default: 700.")
+                    )
+            )
             .get_matches();
 
         if let Err(err) = options.hydrate_options(&matches) {
@@ -334,23 +909,84 @@ impl Options {
     }
 
     fn hydrate_options(&mut self, matches: &ArgMatches) -> Result<(), String> {
-        if let Some(action_str) = matches.value_of("action") {
-            match action_str {
-                "+" => self.override_action = Some(Decision::Hit),
-                "=" => self.override_action = Some(Decision::Stand),
-                "D" => self.override_action = Some(Decision::Double),
-                "V" => self.override_action = Some(Decision::Split),
-                "#" => self.surrender_override = Some(true),
-                _ => return Err("-a: invalid action".into()),
+        if let Some(path) = matches.value_of("config") {
+            let json = fs::read_to_string(path)
+                .map_err(|e| format!("--config: {}: {}", path, e))?;
+            let config = Config::from_json(&json)
+                .map_err(|e| format!("--config: {}: {}", path, e))?;
+            self.apply_config(config)
+                .map_err(|e| format!("--config: {}: {}", path, e))?;
+        }
+
+        if matches.subcommand_matches("play").is_some() {
+            self.mode = Mode::Play;
+        }
+
+        if let Some(chart) = matches.subcommand_matches("strategy") {
+            self.mode = Mode::Chart(match chart.value_of("format") {
+                Some("markdown") => ChartFormat::Markdown,
+                Some("html") => ChartFormat::Html,
+                Some("svg") => ChartFormat::Svg,
+                _ => ChartFormat::Text,
+            });
+        }
+
+        if let Some(dealer) = matches.subcommand_matches("dealer") {
+            self.mode = Mode::Dealer(match dealer.value_of("format") {
+                Some("json") => AnalysisFormat::Json,
+                _ => AnalysisFormat::Text,
+            });
+            if let Some(cards) = dealer.value_of("removed") {
+                self.removed_cards = parse_card_list(cards)
+                    .map_err(|e| format!("--removed: {}", e))?
+                    .into();
             }
-            if self.override_action.is_some() {
-                self.surrender_override = Some(false);
+        }
+
+        if let Some(calc) = matches.subcommand_matches("calc") {
+            self.mode = Mode::Calc(match calc.value_of("format") {
+                Some("json") => AnalysisFormat::Json,
+                _ => AnalysisFormat::Text,
+            });
+            for card in calc.values_of("hand").unwrap() {
+                self.hand_cards.push(Card::try_from(card)
+                    .map_err(|e| format!("calc: {}", e))?);
             }
-            if self.surrender_override.unwrap_or(false) {
-                self.override_action = None;
+            let hand = Hand::from(&self.hand_cards[..]);
+            if hand.is_bj() {
+                return Err("calc: a natural has no decision".into());
+            }
+            if hand.is_busted() {
+                return Err("calc: the hand is busted".into());
+            }
+            self.upcard = Some(Card::try_from(calc.value_of("vs").unwrap())
+                .map_err(|e| format!("--vs: {}", e))?);
+            if let Some(cards) = calc.value_of("removed") {
+                self.removed_cards = parse_card_list(cards)
+                    .map_err(|e| format!("--removed: {}", e))?
+                    .into();
+            }
+        }
+
+        if let Some(drill) = matches.subcommand_matches("drill") {
+            self.mode = Mode::Drill(match drill.value_of("drill") {
+                Some("count") => Drill::Count,
+                Some("true-count") => Drill::TrueCount,
+                Some("deviations") => Drill::Deviations,
+                _ => unreachable!(),
+            });
+            self.drill_stats = drill.value_of("stats").map(String::from);
+            if let Some(flash) = drill.value_of("flash") {
+                self.flash_ms = flash.parse()
+                    .map_err(|e| format!("--flash: {}", e))?;
             }
         }
 
+        if let Some(script) = matches.value_of("action") {
+            self.script = Some(Script::from_str(script)
+                .map_err(|e| format!("-a: {}", e))?);
+        }
+
         if let Some(rounds_str) = matches.value_of("rounds") {
             self.rounds = match parse_suffix_int(rounds_str) {
                 Ok(n) if n > 0 => n,
@@ -358,6 +994,10 @@ impl Options {
             };
         }
 
+        if let Some(name) = matches.value_of("preset") {
+            self.apply_rules(GameRules::preset(name).unwrap());
+        }
+
         if let Some(decks) = matches.value_of("decks") {
             self.decks = match decks.parse() {
                 Ok(n) if n > 0 => n,
@@ -368,7 +1008,7 @@ impl Options {
         if let Some(pen_cards) = matches.value_of("penetration") {
             self.pen_cards = parse_penetration(pen_cards, self.decks)
                 .map_err(|_| "-p: invalid penetration")?;
-        } else {
+        } else if matches.is_present("decks") {
             self.pen_cards = parse_penetration("80%", self.decks).unwrap();
         }
 
@@ -379,6 +1019,58 @@ impl Options {
             };
         }
 
+        if let Some(players) = matches.value_of("players") {
+            self.players = match players.parse() {
+                Ok(n) if (1..=7).contains(&n) => n,
+                _ => return Err("--players: invalid number of players, must \
+                be from 1 to 7".into()),
+            };
+        }
+
+        if let Some(seats) = matches.value_of("seats") {
+            let seats = seats.split(',')
+                .map(|seat| match seat {
+                    "player" => Ok(SeatStrategy::Player),
+                    "basic" => Ok(SeatStrategy::Basic),
+                    "tourist" => Ok(SeatStrategy::Tourist),
+                    _ => Err(format!("--seats: unknown strategy \"{}\"",
+                                     seat)),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            if matches.is_present("players")
+               && seats.len() != self.players as usize {
+                return Err("--seats: one strategy per player is required"
+                    .into());
+            }
+            if seats.len() > 7 {
+                return Err("--seats: at most 7 players".into());
+            }
+            if !matches.is_present("seat") {
+                if let Some(i) = seats.iter()
+                                      .position(|&s| s == SeatStrategy::Player) {
+                    self.seat = i as u8 + 1;
+                }
+            }
+            self.players = seats.len() as u8;
+            self.seats = seats;
+        }
+
+        if let Some(seat) = matches.value_of("seat") {
+            self.seat = seat.parse().map_err(|_| "--seat: invalid seat")?;
+        }
+
+        if !(1..=7).contains(&self.players) {
+            return Err("invalid number of players, must be from 1 to 7"
+                .into());
+        }
+        if !self.seats.is_empty() && self.seats.len() != self.players as usize {
+            return Err("--seats: one strategy per player is required".into());
+        }
+        if self.seat == 0 || self.seat > self.players {
+            return Err("--seat: the seat must be from 1 to the number of \
+            players".into());
+        }
+
         if let Some(max_splits) = matches.value_of("max_splits") {
             self.max_splits = match max_splits.parse() {
                 Ok(n) if n > 0 => n,
@@ -394,6 +1086,23 @@ impl Options {
             self.game_type = Ahc;
         }
 
+        if let Some(peek) = matches.value_of("peek") {
+            if self.game_type != Ahc {
+                return Err("--peek: incompatible with --enhc".into());
+            }
+            self.peek = match peek {
+                "ace-ten" => PeekPolicy::PeekAceTen,
+                "ace" => PeekPolicy::PeekAce,
+                "ten" => PeekPolicy::PeekTen,
+                "none" => PeekPolicy::NoPeek,
+                _ => unreachable!(),
+            };
+        }
+
+        if matches.is_present("obo") {
+            self.original_bets_only = true;
+        }
+
         if matches.is_present("s17") && matches.is_present("h17") {
             return Err("--s17 and --h17 are mutually exclusive".into());
         } else if matches.is_present("s17") {
@@ -402,6 +1111,13 @@ impl Options {
             self.soft17 = H17;
         }
 
+        if let Some(bj_pays) = matches.value_of("bj_pays") {
+            self.bj_pays = match bj_pays.parse() {
+                Ok(n) if n > 0.0 => n,
+                _ => return Err("--bj-pays: invalid payout".into()),
+            };
+        }
+
         if matches.is_present("play_aa") && matches.is_present("no_play_aa") {
             return Err("--playAA and --no-playAA are mutually exclusive".into());
         } else if matches.is_present("play_aa") {
@@ -437,22 +1153,76 @@ impl Options {
             self.double = DoublePolicy::NoDouble;
         }
 
+        if let Some(policy) = matches.value_of("double_after_hit") {
+            self.double_after_hit = match policy {
+                "any" => DoublePolicy::AnyHand,
+                "hard-9-11" => DoublePolicy::Hard9To11,
+                "hard-10-11" => DoublePolicy::Hard10To11,
+                "none" => DoublePolicy::NoDouble,
+                _ => return Err("--db-after-hit: invalid policy".into()),
+            };
+        }
+
+        if let Some(max_cards) = matches.value_of("double_max_cards") {
+            self.double_max_cards = match max_cards.parse() {
+                Ok(n) if n >= 2 => Some(n),
+                _ => return Err("--db-max-cards: invalid number of cards".into()),
+            };
+        }
+
+        if matches.is_present("double_for_less") {
+            self.double_for_less = true;
+        }
+
+        if let Some(redoubles) = matches.value_of("redoubles") {
+            self.max_redoubles = redoubles.parse()
+                .map_err(|_| "--redoubles: invalid number of re-doubles")?;
+        }
+
+        if matches.is_present("double_rescue") {
+            self.double_rescue = true;
+        }
+
         if matches.is_present("esurr") as u32
+           + matches.is_present("esurr_10") as u32
            + matches.is_present("lsurr") as u32
            + matches.is_present("no_surr") as u32 > 1 {
-            return Err("--esurr, --lsurr, and --no-surr are mutually \
-            exclusive".into());
+            return Err("--esurr, --esurr-10, --lsurr, and --no-surr are \
+            mutually exclusive".into());
         } else if matches.is_present("esurr") {
             self.surrender = SurrenderPolicy::EarlySurrender;
+        } else if matches.is_present("esurr_10") {
+            self.surrender = SurrenderPolicy::EarlySurrenderTens;
         } else if matches.is_present("lsurr") {
-            if self.game_type == GameType::Enhc {
-                return Err("--lsurr: incompatible with --enhc".into());
-            }
             self.surrender = SurrenderPolicy::LateSurrender;
         } else if matches.is_present("no_surr") {
             self.surrender = SurrenderPolicy::NoSurrender;
         }
 
+        // The rules of a preset or a configuration file may be overridden:
+        // check the resulting ones
+        if self.surrender == SurrenderPolicy::LateSurrender
+           && self.game_type == GameType::Enhc {
+            return Err("late surrender (--lsurr) is incompatible with ENHC \
+            (--enhc)".into());
+        }
+
+        if matches.is_present("surr_any_cards") {
+            if self.surrender == SurrenderPolicy::NoSurrender {
+                return Err("--surr-any-cards: requires a surrender policy"
+                    .into());
+            }
+            self.surrender_any_cards = true;
+        }
+
+        if matches.is_present("surr_after_split") {
+            if self.surrender == SurrenderPolicy::NoSurrender {
+                return Err("--surr-after-split: requires a surrender policy"
+                    .into());
+            }
+            self.surrender_after_split = true;
+        }
+
         if matches.is_present("holecarding") {
             if self.game_type != GameType::Ahc {
                 return Err("--holecarding: requires --ahc".into());
@@ -460,6 +1230,95 @@ impl Options {
             self.holecarding = true;
         }
 
+        let tell = if matches.is_present("warp") {
+            Some(("--warp", "1"))
+        } else {
+            matches.value_of("tell").map(|accuracy| ("--tell", accuracy))
+        };
+        if let Some((name, accuracy)) = tell {
+            if self.game_type != GameType::Ahc {
+                return Err(format!("{}: requires --ahc", name));
+            }
+            self.tell = match accuracy.parse() {
+                Ok(accuracy) if accuracy > 0.5 && accuracy <= 1.0 => {
+                    Some(accuracy)
+                },
+                _ => return Err("--tell: invalid accuracy, must be in \
+                                 (0.5, 1]".into()),
+            };
+        }
+
+        if matches.is_present("next_card") {
+            self.next_card = true;
+        }
+
+        if matches.is_present("info_gains") {
+            self.info_gains = true;
+        }
+        if self.info_gains && !self.holecarding && self.tell.is_none()
+           && !self.next_card {
+            return Err("--info-gains: requires --holecarding, --tell, or \
+            --next-card".into());
+        }
+
+        if let Some(charlie) = matches.value_of("charlie") {
+            self.charlie = match charlie.parse() {
+                Ok(n) if (3..=16).contains(&n) => Some(n),
+                _ => return Err("--charlie: invalid number of cards".into()),
+            };
+        }
+
+        if let Some(charlie_pays) = matches.value_of("charlie_pays") {
+            if self.charlie.is_none() {
+                return Err("--charlie-pays: requires --charlie".into());
+            }
+            self.charlie_pays = match charlie_pays.parse() {
+                Ok(n) if n > 0.0 => n,
+                _ => return Err("--charlie-pays: invalid payout".into()),
+            };
+        }
+
+        if matches.is_present("charlie_no_split") {
+            if self.charlie.is_none() {
+                return Err("--charlie-no-split: requires --charlie".into());
+            }
+            self.charlie_split_hands = false;
+        }
+
+        if let Some(total) = matches.value_of("dealer_push") {
+            self.dealer_push = match total.parse() {
+                Ok(n) if (22..=26).contains(&n) => Some(n),
+                _ => return Err("--dealer-push: invalid total".into()),
+            };
+        }
+
+        if matches.is_present("dealer_push_except_bj") {
+            if self.dealer_push.is_none() {
+                return Err("--dealer-push-except-bj: requires \
+                --dealer-push".into());
+            }
+            self.dealer_push_except_bj = true;
+        }
+
+        if matches.is_present("no_insurance") {
+            self.insurance = false;
+        }
+        if matches.is_present("no_even_money") {
+            self.even_money = false;
+        }
+
+        if let Some(insure_for) = matches.value_of("insure_for") {
+            if !self.insurance {
+                return Err("--insure-for: incompatible with --no-insurance"
+                    .into());
+            }
+            self.insure_for = match insure_for.parse() {
+                Ok(n) if n > 0.0 && n <= 0.5 => n,
+                _ => return Err("--insure-for: invalid ratio, must be in \
+                (0, 0.5]".into()),
+            };
+        }
+
         if let Some(start_cards) = matches.value_of("start_cards") {
             let cards = parse_card_list(start_cards)
                 .map_err(|_| "-c: invalid card list")?;
@@ -475,7 +1334,9 @@ impl Options {
             self.dealer_cards = Some(cards);
         }
 
-        self.hilo_counting = matches.is_present("hilo");
+        if matches.is_present("hilo") {
+            self.hilo_counting = true;
+        }
 
         if let Some(bet) = matches.value_of("bet") {
             self.bet = match bet.parse() {
@@ -514,6 +1375,68 @@ impl Options {
             };
         }
 
+        if let Some(spots) = matches.value_of("spots") {
+            if !self.hilo_counting {
+                return Err("--spots: requires card counting".into());
+            }
+            self.spots = spots.split(',')
+                .map(|step| {
+                    let (tc, spots) = step.split_once(':')?;
+                    Some(SpotsStep {
+                        true_count: tc.parse().ok()?,
+                        spots: spots.parse().ok()
+                            .filter(|n| (1..=7).contains(n))?,
+                    })
+                })
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(|| format!("--spots: invalid spots \"{}\"",
+                                       spots))?;
+        }
+
+        let max_spots = self.spots.iter().map(|step| step.spots).max()
+            .unwrap_or(1);
+        let player_seats = if self.seats.is_empty() {
+            self.players
+        } else {
+            self.seats.iter().filter(|&&s| s == SeatStrategy::Player).count()
+                as u8
+        };
+        if self.players + player_seats * (max_spots - 1) > 7 {
+            return Err("--spots: more than the 7 spots of the table".into());
+        }
+
+        if let Some(tc) = matches.value_of("wong_in") {
+            if !self.hilo_counting {
+                return Err("--wong-in: requires card counting".into());
+            }
+            self.wonging.get_or_insert_with(Wonging::default).enter_tc =
+                Some(tc.parse().map_err(|_| "--wong-in: invalid true count")?);
+        }
+
+        if let Some(tc) = matches.value_of("wong_out") {
+            if !self.hilo_counting {
+                return Err("--wong-out: requires card counting".into());
+            }
+            self.wonging.get_or_insert_with(Wonging::default).exit_tc =
+                Some(tc.parse().map_err(|_| "--wong-out: invalid true count")?);
+        }
+
+        if matches.is_present("no_mid_shoe_entry") {
+            match self.wonging.as_mut() {
+                Some(wonging) => wonging.no_mid_shoe_entry = true,
+                None => return Err("--no-mid-shoe-entry: requires --wong-in \
+                or --wong-out".into()),
+            }
+        }
+
+        if let Some(Wonging { enter_tc: Some(enter), exit_tc: Some(exit), .. })
+            = self.wonging {
+            if exit > enter {
+                return Err("--wong-out: must not be above the --wong-in true \
+                count".into());
+            }
+        }
+
         if matches.is_present("deviations") {
             if !self.hilo_counting {
                 return Err("--deviations: requires card counting".into());
@@ -533,18 +1456,242 @@ impl Options {
             }
         }
 
+        if let Some(path) = matches.value_of("strategy") {
+            self.strategy_file = Some(path.to_string());
+            self.exact_strategy = false;
+        }
+        if matches.is_present("exact_strategy") {
+            self.exact_strategy = true;
+            self.strategy_file = None;
+        }
+        self.export_strategy = matches.value_of("export_strategy")
+            .map(String::from);
+
+        if let Some(table) = matches.value_of("composition") {
+            self.composition = if COMPOSITION_TABLES.contains(&table) {
+                CompositionRule::table(table, &self.soft17).unwrap()
+            } else {
+                let text = fs::read_to_string(table)
+                    .map_err(|e| format!("--composition: {}: {}", table, e))?;
+                CompositionRule::parse_file(&text)
+                    .map_err(|e| format!("--composition: {}: {}", table, e))?
+            };
+        }
+
+        let side_bet_amount = match matches.value_of("side_bet_amount") {
+            Some(bet) => match bet.parse() {
+                Ok(n) if n > 0.0 => n,
+                _ => return Err("--side-bet-amount: invalid bet".into()),
+            },
+            None => 1.0,
+        };
+
+        if let Some(iter) = matches.values_of("side_bet") {
+            for spec in iter {
+                let wager = SideBetWager::parse(spec, side_bet_amount)
+                    .map_err(|e| format!("--side-bet: {}", e))?;
+                if wager.min_true_count.is_some() && !self.hilo_counting {
+                    return Err("--side-bet: a true count requires card \
+                    counting".into());
+                }
+                self.side_bets.push(wager);
+            }
+        }
+
         if let Some(tc) = matches.value_of("force_tc") {
             self.force_tc = Some(tc.parse()
                 .map_err(|_| "--force-tc: invalid true count")?);
         }
 
-        self.shoe_file = matches.value_of("shoe_file").map(|s| s.to_string());
+        if let Some(shoe_file) = matches.value_of("shoe_file") {
+            self.shoe_file = Some(shoe_file.to_string());
+        }
+
+        if let Some(seed) = matches.value_of("seed") {
+            self.seed = Some(seed.parse()
+                .map_err(|_| "--seed: invalid seed")?);
+        }
 
         self.dry_run = matches.is_present("dry_run");
         self.verbose = matches.is_present("verbose");
+        if let Some(path) = matches.value_of("event_log") {
+            self.event_log = Some(path.to_string());
+        }
+
+        self.check_values()
+    }
+
+    /// Checks the values of the options once those of a configuration file,
+    /// a preset, and the command line are merged, the same way each option
+    /// checks its own value.
+    fn check_values(&self) -> Result<(), String> {
+        if self.decks == 0 {
+            return Err("invalid number of decks".into());
+        }
+        if self.pen_cards == 0 || self.pen_cards > self.decks * 52 {
+            return Err(format!("invalid penetration, must be from 1 to the \
+            {} cards of the shoe", self.decks * 52));
+        }
+        if self.rounds == 0 {
+            return Err("invalid number of rounds".into());
+        }
+        if self.jobs == 0 {
+            return Err("invalid number of jobs".into());
+        }
+        if self.max_splits == 0 {
+            return Err("invalid maximum number of split hands".into());
+        }
+        if self.double_max_cards.is_some_and(|n| n < 2) {
+            return Err("invalid maximum number of cards to double down"
+                .into());
+        }
+        if self.bj_pays <= 0.0 {
+            return Err("invalid blackjack payout".into());
+        }
+        if self.bet <= 0.0 {
+            return Err("invalid bet".into());
+        }
+        if self.bet_per_tc < 0.0
+           || self.bet_neg_tc.is_some_and(|n| n < 0.0) {
+            return Err("invalid bet ramp".into());
+        }
+        if self.charlie.is_some_and(|n| !(3..=16).contains(&n)) {
+            return Err("invalid number of cards of a Charlie".into());
+        }
+        if self.charlie_pays <= 0.0 {
+            return Err("invalid Charlie payout".into());
+        }
+        if self.dealer_push.is_some_and(|n| !(22..=26).contains(&n)) {
+            return Err("invalid dealer push total".into());
+        }
 
         Ok(())
     }
+
+    /// Sets all the options from an experiment configuration, before the
+    /// other options override them.
+    fn apply_config(&mut self, config: Config) -> Result<(), String> {
+        if config.counting.is_none()
+           && (config.strategy.deviations
+               || !config.strategy.add_deviations.is_empty()) {
+            return Err("deviations require card counting".into());
+        }
+        if config.counting.is_none()
+           && (config.betting.wonging.is_some()
+               || !config.betting.spots.is_empty()) {
+            return Err("wonging and spots require card counting".into());
+        }
+        if config.betting.spots.iter()
+           .any(|step| !(1..=7).contains(&step.spots)) {
+            return Err("invalid number of spots, must be from 1 to 7".into());
+        }
+        if config.strategy.exact && config.strategy.file.is_some() {
+            return Err("an exact strategy excludes a strategy file".into());
+        }
+        if config.strategy.holecarding && config.rules.game_type != Ahc {
+            return Err("holecarding requires an AHC game".into());
+        }
+        if let Some(accuracy) = config.strategy.tell {
+            if config.rules.game_type != Ahc {
+                return Err("a tell requires an AHC game".into());
+            }
+            if !(accuracy > 0.5 && accuracy <= 1.0) {
+                return Err("invalid tell accuracy, must be in (0.5, 1]"
+                    .into());
+            }
+        }
+        if config.run.information_gains && !config.strategy.holecarding
+           && config.strategy.tell.is_none() && !config.strategy.next_card {
+            return Err("information gains require holecarding, a tell, or \
+            the next card".into());
+        }
+        if config.counting.is_none()
+           && config.betting.side_bets.iter()
+               .any(|wager| wager.min_true_count.is_some()) {
+            return Err("a side bet on the true count requires card counting"
+                .into());
+        }
+        if config.scenario.start_cards.len() == 1 {
+            return Err("the start cards must be at least two".into());
+        }
+        if !(config.strategy.insurance_amount > 0.0
+             && config.strategy.insurance_amount <= 0.5) {
+            return Err("invalid insurance amount, must be in (0, 0.5]".into());
+        }
+
+        self.apply_rules(config.rules);
+        self.players = config.table.players;
+        self.seat = config.table.seat;
+        self.seats = config.table.seats;
+        self.hilo_counting = config.counting == Some(CountingSystem::Hilo);
+        self.deviations = config.strategy.deviations;
+        self.more_devs = config.strategy.add_deviations;
+        self.composition = config.strategy.composition;
+        self.strategy_file = config.strategy.file;
+        self.exact_strategy = config.strategy.exact;
+        self.insure_for = config.strategy.insurance_amount;
+        self.holecarding = config.strategy.holecarding;
+        self.tell = config.strategy.tell;
+        self.next_card = config.strategy.next_card;
+        self.bet = config.betting.bet;
+        self.bet_per_tc = config.betting.bet_per_tc;
+        self.bet_neg_tc = config.betting.bet_neg_tc;
+        self.bet_max_tc = config.betting.bet_max_tc;
+        self.wongout_under = config.betting.wongout_under;
+        self.spots = config.betting.spots;
+        self.wonging = config.betting.wonging;
+        self.side_bets = config.betting.side_bets;
+        self.start_cards = non_empty(config.scenario.start_cards);
+        self.dealer_cards = non_empty(config.scenario.dealer_cards);
+        self.script = config.scenario.script;
+        self.shoe_file = match config.shoe {
+            ShoeConfig::Standard => None,
+            ShoeConfig::File { path } => Some(path),
+        };
+        self.rounds = config.run.rounds;
+        self.jobs = config.run.jobs;
+        self.seed = config.run.seed;
+        self.force_tc = config.run.force_tc;
+        self.info_gains = config.run.information_gains;
+
+        Ok(())
+    }
+
+    /// Sets the game rules options from complete rules, of a preset or an
+    /// experiment configuration, before the other rule options override them.
+    fn apply_rules(&mut self, rules: GameRules) {
+        self.preset = rules.preset;
+        self.game_type = rules.game_type;
+        self.peek = rules.peek;
+        self.original_bets_only = rules.original_bets_only;
+        self.soft17 = rules.soft17;
+        self.das = rules.das;
+        self.bj_pays = rules.bj_pays;
+        self.insurance = rules.insurance;
+        self.even_money = rules.even_money;
+        self.double = rules.double_down;
+        self.double_after_hit = rules.double_after_hit;
+        self.double_max_cards = rules.double_max_cards;
+        self.double_for_less = rules.double_for_less;
+        self.max_redoubles = rules.max_redoubles;
+        self.surrender = rules.surrender;
+        self.surrender_any_cards = rules.surrender_any_cards;
+        self.surrender_after_split = rules.surrender_after_split;
+        self.double_rescue = rules.double_rescue;
+        self.play_split_aces = rules.play_ace_pairs;
+        self.max_splits = rules.max_splits;
+        self.decks = rules.decks;
+        self.pen_cards = rules.penetration_cards;
+        self.charlie = rules.charlie.map(|charlie| charlie.cards);
+        if let Some(charlie) = rules.charlie {
+            self.charlie_pays = charlie.pays;
+            self.charlie_split_hands = charlie.split_hands;
+        }
+        self.dealer_push = rules.dealer_push.map(|push| push.total);
+        if let Some(dealer_push) = rules.dealer_push {
+            self.dealer_push_except_bj = dealer_push.except_naturals;
+        }
+    }
 }
 
 fn parse_suffix_int(str: &str) -> Result<u64, String> {
@@ -558,12 +1705,21 @@ fn parse_suffix_int(str: &str) -> Result<u64, String> {
         _ => return Err("Unknown suffix".to_string()),
     };
 
-    let str_slice = if scale > 1 { &str[0..str.len() - 1] } else { &str[..] };
+    let str_slice = if scale > 1 { &str[0..str.len() - 1] } else { str };
     let base = str_slice.parse::<u64>().map_err(|e| e.to_string())?;
 
     Ok(base * scale)
 }
 
+/// Returns the cards of a configuration, `None` when there are none.
+fn non_empty(cards: Vec<Card>) -> Option<VecDeque<Card>> {
+    if cards.is_empty() {
+        None
+    } else {
+        Some(cards.into())
+    }
+}
+
 fn parse_card_list(str: &str) -> Result<VecDeque<Card>, &'static str> {
     let mut vec = VecDeque::new();
 
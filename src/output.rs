@@ -1,12 +1,14 @@
 use std::collections::BTreeMap;
 use serde::{Serialize, Serializer};
 use serde::ser::SerializeMap;
-use freebj::game_rules::GameRules;
+use freebj::config::{Config, SeatStrategy};
 use freebj::simulator::SimulationResult;
+use freebj::hand_stats::HandStats;
 
 pub struct ProgramResult<'a> {
     pub rounds: u64,
-    pub rules: &'a GameRules,
+    /// The resolved configuration of the simulation
+    pub config: &'a Config,
     pub simulation: SimulationResult,
 }
 
@@ -15,7 +17,7 @@ struct WinningDistrib<'a> {
 }
 
 impl<'a> WinningDistrib<'a> {
-    pub fn new(distrib: &'a BTreeMap<i32, u64>) -> WinningDistrib {
+    pub fn new(distrib: &'a BTreeMap<i32, u64>) -> WinningDistrib<'a> {
         WinningDistrib {
             distrib,
         }
@@ -35,19 +37,105 @@ impl Serialize for WinningDistrib<'_> {
     }
 }
 
+/// The insurance and even money P&L, included in the overall EV.
+#[derive(Serialize)]
+struct InsuranceResult {
+    /// The insurance P&L per round, relative to the base bet
+    pub ev: f64,
+    /// The total insurance P&L over all rounds
+    pub total: f64,
+}
+
+/// The results of a side bet, for the amount wagered; not included in the EV.
+#[derive(Serialize)]
+struct SideBetResult {
+    pub name: &'static str,
+    /// The number of rounds the side bet was placed
+    pub placed: usize,
+    pub ev: f64,
+    pub variance: f64,
+}
+
+/// The results of a seat at the table.
+#[derive(Serialize)]
+struct SeatResult<'a> {
+    /// The seat, from 1 (first base)
+    pub seat: usize,
+    pub strategy: SeatStrategy,
+    pub ev: f64,
+    pub stddev: f64,
+    pub hands: &'a HandStats,
+}
+
 impl Serialize for ProgramResult<'_> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
         where S: Serializer {
         let mut map = serializer.serialize_map(None)?;
 
         map.serialize_entry("rounds", &self.rounds)?;
-        map.serialize_entry("rules", self.rules)?;
+        map.serialize_entry("rules", &self.config.rules)?;
         map.serialize_entry("ev", &self.simulation.winnings.mean())?;
         map.serialize_entry("stddev", &self.simulation.winnings.stddev())?;
+        let insurance = &self.simulation.insurance;
+        map.serialize_entry("insurance", &InsuranceResult {
+            ev: insurance.mean(),
+            total: insurance.mean() * insurance.count() as f64,
+        })?;
         let distrib = WinningDistrib::new(&self.simulation.winning_distrib);
         map.serialize_entry("winning_distrib", &distrib)?;
         map.serialize_entry("hands", &self.simulation.hand_stats)?;
 
+        let played = self.simulation.winnings.count() as u64;
+        let observed = played + self.simulation.watched;
+        map.serialize_entry("rounds_played", &played)?;
+        map.serialize_entry("rounds_watched", &self.simulation.watched)?;
+        map.serialize_entry("ev_per_round_observed", &if played > 0 {
+            self.simulation.winnings.mean() * played as f64 / observed as f64
+        } else {
+            0.0
+        })?;
+
+        let shoes = self.simulation.shoes;
+        map.serialize_entry("shoes", &shoes)?;
+        map.serialize_entry("rounds_per_shoe", &if shoes > 0 {
+            Some(observed as f64 / shoes as f64)
+        } else {
+            None
+        })?;
+
+        if self.simulation.seats.len() > 1 {
+            let seats: Vec<_> = self.simulation.seats.iter()
+                .enumerate()
+                .map(|(i, stats)| SeatResult {
+                    seat: i + 1,
+                    strategy: self.config.table.seats.get(i).copied()
+                        .unwrap_or(SeatStrategy::Player),
+                    ev: stats.winnings.mean(),
+                    stddev: stats.winnings.stddev(),
+                    hands: &stats.hand_stats,
+                })
+                .collect();
+            map.serialize_entry("seats", &seats)?;
+        }
+
+        if !self.simulation.side_bets.is_empty() {
+            let side_bets: Vec<_> = self.simulation.side_bets.iter()
+                .map(|stats| SideBetResult {
+                    name: stats.name,
+                    placed: stats.results.count(),
+                    ev: stats.results.mean(),
+                    variance: stats.results.variance(),
+                })
+                .collect();
+            map.serialize_entry("side_bets", &side_bets)?;
+        }
+
+        if let Some(information) = &self.simulation.information {
+            map.serialize_entry("information", information)?;
+        }
+
+        map.serialize_entry("config", self.config)?;
+
         map.end()
     }
 }
@@ -0,0 +1,454 @@
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::io::{self, BufRead, Write};
+
+use freebj::basic_strategy::BasicStrategy;
+use freebj::betting::BettingStrategy;
+use freebj::card::Card;
+use freebj::event::Event;
+use freebj::game_rules::GameRules;
+use freebj::hand::Hand;
+use freebj::hand_logic::HandOutcome;
+use freebj::round::Round;
+use freebj::shoe::CardShoe;
+use freebj::strategy::{Strategy, GameContext, Decision};
+
+/// The statistics of an interactive session.
+#[derive(Debug, Default, PartialEq)]
+pub struct PlayStats {
+    pub rounds:     u64,
+    /// The decisions taken by the player, insurance and surrender included
+    pub decisions:  u64,
+    /// The decisions matching the strategy's
+    pub correct:    u64,
+    /// The decisions where the strategy deviates from basic strategy
+    pub deviations: u64,
+    pub correct_deviations: u64,
+    /// The player's net result, in betting units
+    pub result:     f64,
+}
+
+/// A strategy asking the player for their decisions, and telling them
+/// whether each one matches the strategy's.
+struct Trainer<'a> {
+    strategy:   &'a dyn Strategy,
+    /// Basic strategy without the count, to tell the strategy's deviations
+    basic:      BasicStrategy,
+    counting:   bool,
+    input:      RefCell<&'a mut dyn BufRead>,
+    output:     RefCell<&'a mut dyn Write>,
+    stats:      RefCell<PlayStats>,
+    /// Whether the input ended, the strategy then plays the round on
+    ended:      Cell<bool>,
+}
+
+impl Trainer<'_> {
+    /// Asks a question until answered with one of `choices`; returns `None`
+    /// once the input ended.
+    fn ask(&self, question: &str, choices: &[char]) -> Option<char> {
+        if self.ended.get() {
+            return None;
+        }
+
+        let mut input = self.input.borrow_mut();
+        let mut output = self.output.borrow_mut();
+
+        loop {
+            let _ = write!(output, "{} ", question);
+            let _ = output.flush();
+
+            let mut line = String::new();
+            if !matches!(input.read_line(&mut line), Ok(n) if n > 0) {
+                let _ = writeln!(output);
+                self.ended.set(true);
+                return None;
+            }
+
+            let mut chars = line.trim().chars();
+            if let (Some(c), None) = (chars.next(), chars.next()) {
+                let c = c.to_ascii_lowercase();
+                if choices.contains(&c) {
+                    return Some(c);
+                }
+            }
+        }
+    }
+
+    /// Asks whether to deal the next round, or quit; the input ending quits.
+    fn ask_to_deal(&self) -> bool {
+        let mut input = self.input.borrow_mut();
+        let mut output = self.output.borrow_mut();
+
+        let _ = write!(output, "Press Enter to deal, or q to quit: ");
+        let _ = output.flush();
+
+        let mut line = String::new();
+        if !matches!(input.read_line(&mut line), Ok(n) if n > 0) {
+            let _ = writeln!(output);
+            self.ended.set(true);
+            return false;
+        }
+
+        !line.trim().eq_ignore_ascii_case("q")
+    }
+
+    /// Asks a yes or no question; returns `None` once the input ended.
+    fn ask_yes_no(&self, question: &str) -> Option<bool> {
+        self.ask(&format!("{} [y]es, [n]o?", question), &['y', 'n'])
+            .map(|c| c == 'y')
+    }
+
+    /// Records the player's decision against the strategy's, and tells them
+    /// how it went.
+    fn check<T: PartialEq + Copy>(&self,
+                                  game: &GameContext,
+                                  chosen: T,
+                                  expected: T,
+                                  basic: T,
+                                  verb: fn(T) -> &'static str) {
+        let correct = chosen == expected;
+        let deviation = expected != basic;
+
+        let mut stats = self.stats.borrow_mut();
+        stats.decisions += 1;
+        stats.correct += correct as u64;
+        if deviation {
+            stats.deviations += 1;
+            stats.correct_deviations += correct as u64;
+        }
+
+        let mut feedback = if correct {
+            String::from("Correct")
+        } else {
+            format!("Wrong, the strategy would {}", verb(expected))
+        };
+        if deviation {
+            feedback += &format!(": a deviation from basic strategy, which \
+                                  would {}", verb(basic));
+        }
+        if self.counting {
+            feedback += &format!(" (true count {:+.1})", game.true_count);
+        }
+
+        let _ = writeln!(self.output.borrow_mut(), "{}.", feedback);
+    }
+}
+
+impl Strategy for Trainer<'_> {
+    fn player_turn(&self,
+                   game: &GameContext,
+                   dealer: Card,
+                   me: &Hand) -> Decision {
+        let expected = self.strategy.player_turn(game, dealer, me);
+        if me.value() == 21 {
+            return expected;
+        }
+
+        let mut question = format!("{} against {}:", describe(me), dealer);
+        let mut choices = Vec::new();
+        if !me.is_doubled() {
+            question += " [h]it,";
+            choices.push('h');
+        }
+        question += " [s]tand";
+        choices.push('s');
+        if game.may_double {
+            question += ", [d]ouble";
+            choices.push('d');
+        }
+        if game.may_split {
+            question += ", s[p]lit";
+            choices.push('p');
+        }
+
+        let chosen = match self.ask(&(question + "?"), &choices) {
+            Some('h') => Decision::Hit,
+            Some('s') => Decision::Stand,
+            Some('d') => Decision::Double,
+            Some('p') => Decision::Split,
+            _ => return expected,
+        };
+
+        let basic = self.basic.player_turn(game, dealer, me);
+        self.check(game, chosen, expected, basic, |decision| match decision {
+            Decision::Hit => "hit",
+            Decision::Stand => "stand",
+            Decision::Double => "double down",
+            Decision::Split => "split",
+        });
+
+        chosen
+    }
+
+    fn double_amount(&self,
+                     game: &GameContext,
+                     dealer: Card,
+                     me: &Hand) -> f64 {
+        self.strategy.double_amount(game, dealer, me)
+    }
+
+    fn surrender(&self,
+                 game: &GameContext,
+                 dealer: Card,
+                 me: &Hand,
+                 is_early: bool) -> bool {
+        let expected = self.strategy.surrender(game, dealer, me, is_early);
+        let question = format!("{} against {}: surrender?", describe(me),
+                               dealer);
+
+        match self.ask_yes_no(&question) {
+            Some(chosen) => {
+                let basic = self.basic.surrender(game, dealer, me, is_early);
+                self.check(game, chosen, expected, basic, |surrender| {
+                    if surrender { "surrender" } else { "not surrender" }
+                });
+                chosen
+            },
+            None => expected,
+        }
+    }
+
+    fn take_insurance(&self, game: &GameContext, me: &Hand) -> bool {
+        let expected = self.strategy.take_insurance(game, me);
+        let question = format!("{} against an ace: insurance?", describe(me));
+
+        match self.ask_yes_no(&question) {
+            Some(chosen) => {
+                let basic = self.basic.take_insurance(game, me);
+                self.check(game, chosen, expected, basic, |insure| {
+                    if insure { "take insurance" } else { "decline insurance" }
+                });
+                chosen
+            },
+            None => expected,
+        }
+    }
+
+    fn insurance_amount(&self, game: &GameContext, me: &Hand) -> f64 {
+        self.strategy.insurance_amount(game, me)
+    }
+
+    fn take_even_money(&self, game: &GameContext, me: &Hand) -> bool {
+        let expected = self.strategy.take_even_money(game, me);
+
+        match self.ask_yes_no("Blackjack against an ace: even money?") {
+            Some(chosen) => {
+                let basic = self.basic.take_even_money(game, me);
+                self.check(game, chosen, expected, basic, |take| {
+                    if take { "take even money" } else { "decline even money" }
+                });
+                chosen
+            },
+            None => expected,
+        }
+    }
+}
+
+/// Plays rounds heads-up against the dealer until the player quits or the
+/// input ends, the player taking their decisions on `input` and being told
+/// on `output` whether each one matches `strategy`, which may count cards
+/// and deviate. Returns the session's statistics, also written on `output`.
+#[allow(clippy::too_many_arguments)]
+pub fn play(rules: &GameRules,
+            strategy: &dyn Strategy,
+            betting_strategy: &dyn BettingStrategy,
+            shoe: &mut dyn CardShoe,
+            counting: bool,
+            holecarding: bool,
+            input: &mut dyn BufRead,
+            output: &mut dyn Write) -> io::Result<PlayStats> {
+    let trainer = Trainer {
+        strategy,
+        basic: BasicStrategy::new(false),
+        counting,
+        input: RefCell::new(input),
+        output: RefCell::new(output),
+        stats: RefCell::new(PlayStats::default()),
+        ended: Cell::new(false),
+    };
+    let no_cards = VecDeque::new();
+
+    loop {
+        let rounds = trainer.stats.borrow().rounds;
+        {
+            let mut output = trainer.output.borrow_mut();
+            write!(output, "\nRound {}", rounds + 1)?;
+            if counting {
+                write!(output, ", running count {:+}, true count {:+.1}",
+                       shoe.running_count(), shoe.true_count())?;
+            }
+            writeln!(output)?;
+        }
+        if !trainer.ask_to_deal() {
+            break;
+        }
+
+        let round = Round::new(rules, &trainer, betting_strategy, shoe, 1,
+                               holecarding, &no_cards, &no_cards)
+            .with_event_log();
+        let (_, result) = round.run()
+            .expect("A round without a script cannot fail");
+
+        let mut stats = trainer.stats.borrow_mut();
+        let mut output = trainer.output.borrow_mut();
+
+        for event in result.events.iter() {
+            match event {
+                Event::Settle { cards, stake, outcome, result, .. } => {
+                    writeln!(output, "Hand {}, staking {:.2}: {} {:+.2}",
+                             describe(&hand_of(cards)), stake,
+                             match outcome {
+                                 HandOutcome::Win => "win",
+                                 HandOutcome::Push
+                                 | HandOutcome::DealerPush => "push",
+                                 HandOutcome::Lose => "lose",
+                             }, result + 0.0)?;
+                },
+                Event::RoundEnd { dealer, reshuffled } => {
+                    writeln!(output, "Dealer {}", describe(&hand_of(dealer)))?;
+                    if *reshuffled {
+                        writeln!(output, "The shoe is reshuffled.")?;
+                    }
+                },
+                _ => {},
+            }
+        }
+
+        stats.rounds += 1;
+        stats.result += result.player_results[0];
+        // Adding zero turns the results of zero bets from -0.0 to 0.0
+        writeln!(output, "Round {:+.2}, session {:+.2}",
+                 result.player_results[0] + 0.0, stats.result + 0.0)?;
+
+        if trainer.ended.get() {
+            break;
+        }
+    }
+
+    let stats = trainer.stats.into_inner();
+    let output = trainer.output.into_inner();
+
+    writeln!(output, "\n{} round(s) played, result {:+.2}", stats.rounds,
+             stats.result)?;
+    if stats.decisions > 0 {
+        writeln!(output, "{} of {} decision(s) correct ({:.1} %)",
+                 stats.correct, stats.decisions,
+                 stats.correct as f64 * 100.0 / stats.decisions as f64)?;
+    }
+    if stats.deviations > 0 {
+        writeln!(output, "{} of {} deviation(s) correct",
+                 stats.correct_deviations, stats.deviations)?;
+    }
+
+    Ok(stats)
+}
+
+fn hand_of(cards: &[Card]) -> Hand {
+    let mut hand = Hand::new();
+    for &card in cards {
+        hand.add(card);
+    }
+    hand
+}
+
+/// Describes a hand for the player, e.g. "A 6 (soft 17)".
+fn describe(hand: &Hand) -> String {
+    let cards: Vec<String> = hand.iter().map(Card::to_string).collect();
+    let total = if hand.is_busted() {
+        String::from("bust")
+    } else if hand.is_bj() {
+        String::from("blackjack")
+    } else if hand.is_soft() {
+        format!("soft {}", hand.value())
+    } else {
+        hand.value().to_string()
+    };
+
+    format!("{} ({})", cards.join(" "), total)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::play::{play, PlayStats};
+    use freebj::basic_strategy::BasicStrategy;
+    use freebj::betting::FixedBet;
+    use freebj::game_rules::GameRules;
+    use freebj::shoe::queued_shoe::QueuedShoe;
+    use freebj::deviation::Deviation;
+    use std::str::FromStr;
+
+    fn run_play(strategy: &BasicStrategy,
+                cards: &[u8],
+                input: &str,
+                counting: bool) -> (PlayStats, String) {
+        let rules = GameRules::default();
+        let mut shoe = QueuedShoe::from_ints(cards);
+        let mut input = input.as_bytes();
+        let mut output = Vec::new();
+
+        let stats = play(&rules, strategy, &FixedBet(1.0), &mut shoe,
+                         counting, false, &mut input, &mut output).unwrap();
+
+        (stats, String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn it_checks_the_decisions() {
+        let strategy = BasicStrategy::new(false);
+        // 16 against a 10: hit, the dealer draws to 17
+        let (stats, output) = run_play(&strategy, &[10, 10, 6, 7, 10],
+                                       "\ns\nq\n", false);
+
+        assert_eq!(stats, PlayStats {
+            rounds: 1,
+            decisions: 1,
+            correct: 0,
+            deviations: 0,
+            correct_deviations: 0,
+            result: -1.0,
+        });
+        assert!(output.contains("10 6 (16) against 10: [h]it, [s]tand, \
+                                 [d]ouble? Wrong, the strategy would hit."));
+        assert!(output.contains("Dealer 10 7 (17)"));
+        assert!(output.contains("1 round(s) played, result -1.00"));
+        assert!(output.contains("0 of 1 decision(s) correct (0.0 %)"));
+    }
+
+    #[test]
+    fn it_asks_again_on_unavailable_choices() {
+        let strategy = BasicStrategy::new(false);
+        // 11 against a 6: no split, double down and win
+        let (stats, output) = run_play(&strategy, &[5, 6, 6, 10, 10, 10],
+                                       "\np\nx\nd\n", false);
+
+        assert_eq!(stats.decisions, 1);
+        assert_eq!(stats.correct, 1);
+        assert_eq!(stats.result, 2.0);
+        assert_eq!(output.matches("against 6:").count(), 3);
+        assert!(output.contains("Correct."));
+    }
+
+    #[test]
+    fn it_tells_deviations() {
+        let mut strategy = BasicStrategy::new(true);
+        strategy.add_deviation(Deviation::from_str("16vs10:>+0=").unwrap());
+        let (stats, output) = run_play(&strategy, &[10, 10, 6, 7],
+                                       "\ns\n", true);
+
+        assert_eq!(stats.deviations, 1);
+        assert_eq!(stats.correct_deviations, 1);
+        assert!(output.contains("Correct: a deviation from basic strategy, \
+                                 which would hit (true count +0.0)."));
+    }
+
+    #[test]
+    fn it_plays_on_when_the_input_ends() {
+        let strategy = BasicStrategy::new(false);
+        let (stats, _) = run_play(&strategy, &[10, 10, 6, 7, 10], "\n",
+                                  false);
+
+        assert_eq!(stats.rounds, 1);
+        assert_eq!(stats.decisions, 0);
+        assert_eq!(stats.result, -1.0);
+    }
+}
@@ -1,44 +1,86 @@
 use std::collections::{VecDeque, vec_deque};
 use std::fmt::{Debug, Formatter};
-use std::process::exit;
 use arrayvec::ArrayVec;
+use rand::{Rng, RngCore};
 
 use crate::game_rules::GameRules;
 use crate::hand::Hand;
-use crate::strategy::{Strategy, GameContext, Decision};
-use crate::card::Card;
+use crate::strategy::{Strategy, GameContext, Decision, HoleTell};
+use crate::card::{Card, SuitedCard};
 use crate::shoe::CardShoe;
-use crate::game_rules::GameType::{Ahc, Enhc};
-use crate::game_rules::SurrenderPolicy::{EarlySurrender, LateSurrender};
+use crate::game_rules::GameType::Ahc;
+use crate::game_rules::SurrenderPolicy::LateSurrender;
 use crate::game_rules::Soft17::H17;
 use crate::hand_stats::HandStats;
-use crate::hand_logic::{hand_result, may_double};
+use crate::hand_logic::{hand_result, insurance_result, may_double,
+                        may_surrender_in_play, is_charlie};
 use crate::betting::BettingStrategy;
+use crate::side_bet::{SideBetWager, SideBetCards};
+use crate::script::{Script, ScriptError};
+use crate::event::{Event, Action, cards_of};
+
+/// A player seated at the table, with their own playing and betting
+/// strategies.
+#[derive(Clone, Copy)]
+pub struct Player<'a> {
+    pub strategy: &'a (dyn Strategy + Sync),
+    pub betting_strategy: &'a (dyn BettingStrategy + Sync),
+}
 
 pub struct Round<'a>
 {
     rules:      &'a GameRules,
     context:    GameContext<'a>,
-    strategy:   &'a dyn Strategy,
-    betting_strategy: &'a dyn BettingStrategy,
+    /// The playing and betting strategies of each seat
+    players:    ArrayVec<(&'a dyn Strategy, &'a dyn BettingStrategy), 7>,
     shoe:       &'a mut dyn CardShoe,
     dealer:     Hand,
+    /// The hands, the first ones by spot, then the split-off hands
     hands:      ArrayVec<Hand, 32>,
-    hands_per_player: [u8; 7],
+    hands_per_spot: [u8; 7],
     start_cards: &'a VecDeque<Card>,
     dealer_cards: vec_deque::Iter<'a, Card>,
     holecarding: bool,
-    override_action: Option<Decision>,
-    surrender_override: Option<bool>,
+    /// The accuracy of the players' tell on whether the holecard is a ten,
+    /// and the source of its misreadings
+    tell:       Option<(f32, &'a mut dyn RngCore)>,
+    /// Whether the players know the next card off the shoe
+    next_card:  bool,
+    /// The script played by a player instead of their strategy
+    script:     Option<(u8, &'a Script)>,
+    /// The number of hands started on each spot, which follow the script
+    script_hands: [u8; 7],
+    /// The script's hand followed by each hand, and the next decision's step
+    script_steps: [Option<(u8, u8)>; 32],
+    side_bets:  &'a [SideBetWager],
+    /// The player watching the round without playing it, if any
+    watcher:    Option<u8>,
+    /// The first two cards of each spot, for side bets
+    first_cards: ArrayVec<[SuitedCard; 2], 7>,
+    dealer_upcard: Option<SuitedCard>,
+    /// The events of the round, when keeping an event log
+    events:     Option<Vec<Event>>,
 }
 
 #[derive(Debug)]
 pub struct RoundResult {
+    /// The result of each player, summed over the spots they played
     pub player_results: [f64; 7],
-    pub hand_stats: HandStats,
+    /// The part of `player_results` coming from insurance and even money
+    pub insurance_results: [f64; 7],
+    /// The result of each side bet for each player, placed on their first
+    /// spot, for the amount wagered, or `None` when not placed
+    pub side_bet_results: Vec<[Option<f64>; 7]>,
+    /// The statistics of the hands of each player
+    pub hand_stats: [HandStats; 7],
+    /// Whether the shoe was reshuffled at the end of the round
+    pub reshuffled: bool,
+    /// The events of the round, empty unless keeping an event log
+    pub events: Vec<Event>,
 }
 
 impl<'a> Round<'a> {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         rules: &'a GameRules,
         strategy: &'a dyn Strategy,
@@ -46,20 +88,16 @@ impl<'a> Round<'a> {
         shoe: &'a mut dyn CardShoe,
         num_players: u8,
         holecarding: bool,
-        override_action: Option<Decision>,
-        surrender_override: Option<bool>,
         start_cards: &'a VecDeque<Card>,
         dealer_cards: &'a VecDeque<Card>,
     ) -> Self {
         assert!(num_players > 0 && num_players < 8);
 
-        let mut hands = ArrayVec::new();
-        for _ in 0..num_players {
-            hands.push(Hand::new());
-        }
-
-        assert!(!(surrender_override == Some(true)
-                  && override_action.is_some()));
+        let players = (0..num_players)
+            .map(|_| -> (&'a dyn Strategy, &'a dyn BettingStrategy) {
+                (strategy, betting_strategy)
+            })
+            .collect();
 
         Self {
             rules,
@@ -69,43 +107,128 @@ impl<'a> Round<'a> {
                 may_double: false,
                 true_count: 0.0,
                 holecard: None,
+                hole_tell: None,
+                next_card: None,
             },
-            strategy,
-            betting_strategy,
+            players,
             shoe,
             dealer: Hand::new(),
-            hands,
-            hands_per_player: [1; 7],
+            hands: ArrayVec::new(),
+            hands_per_spot: [1; 7],
             start_cards,
             dealer_cards: dealer_cards.iter(),
             holecarding,
-            override_action,
-            surrender_override,
+            tell: None,
+            next_card: false,
+            script: None,
+            script_hands: [0; 7],
+            script_steps: [None; 32],
+            side_bets: &[],
+            watcher: None,
+            first_cards: ArrayVec::new(),
+            dealer_upcard: None,
+            events: None,
         }
     }
 
-    pub fn run(mut self) -> (Self, RoundResult) {
+    /// Sets the playing and betting strategies of each seat, instead of the
+    /// same ones for every seat.
+    pub fn with_players(mut self, players: &[Player<'a>]) -> Self {
+        assert_eq!(players.len(), self.players.len());
+        self.players = players.iter()
+            .map(|player| -> (&'a dyn Strategy, &'a dyn BettingStrategy) {
+                (player.strategy, player.betting_strategy)
+            })
+            .collect();
+        self
+    }
+
+    /// Sets the side bets offered at the table, which are settled on the
+    /// cards' suits: the shoe must know them.
+    pub fn with_side_bets(mut self, side_bets: &'a [SideBetWager]) -> Self {
+        assert!(side_bets.is_empty() || self.shoe.is_suited(),
+                "Side bets require a shoe of suited cards");
+        self.side_bets = side_bets;
+        self
+    }
+
+    /// Makes a player watch the round without playing it: they play no spot,
+    /// and their results are zero.
+    pub fn with_watcher(mut self, id: u8) -> Self {
+        assert!((id as usize) < self.players.len());
+        self.watcher = Some(id);
+        self
+    }
+
+    /// Makes a player play a script instead of their strategy, on each of
+    /// their spots.
+    pub fn with_script(mut self, id: u8, script: &'a Script) -> Self {
+        assert!((id as usize) < self.players.len());
+        self.script = Some((id, script));
+        self
+    }
+
+    /// Gives the players a tell on whether the dealer's holecard is a ten,
+    /// right with probability `accuracy` (1.0 for a warp), drawn from `rng`.
+    pub fn with_tell(mut self, accuracy: f32, rng: &'a mut dyn RngCore)
+        -> Self {
+        assert!(accuracy > 0.5 && accuracy <= 1.0);
+        self.tell = Some((accuracy, rng));
+        self
+    }
+
+    /// Lets the players know the next card off the shoe before each of their
+    /// decisions (first-basing).
+    pub fn with_next_card(mut self) -> Self {
+        self.next_card = true;
+        self
+    }
+
+    /// Records the events of the round, returned in its result.
+    pub fn with_event_log(mut self) -> Self {
+        self.events = Some(Vec::new());
+        self
+    }
+
+    /// Plays the round; fails when the script takes a decision forbidden on
+    /// the hand.
+    pub fn run(mut self) -> Result<(Self, RoundResult), ScriptError> {
         self.context.true_count = self.shoe.true_count();
+        let start_true_count = self.context.true_count;
 
-        for hand in self.hands.iter_mut() {
-            hand.bet = self.betting_strategy.place_bet(self.context.true_count);
+        self.log(|round| Event::RoundStart {
+            running_count: round.shoe.running_count(),
+            true_count: start_true_count,
+            cards_left: round.shoe.cards_left(),
+        });
 
-            if self.start_cards.is_empty() {
-                hand.add(self.shoe.pick());
+        self.place_bets();
+
+        for hand in self.hands.iter_mut() {
+            let card = if self.start_cards.is_empty() {
+                self.shoe.pick_suited()
             } else {
-                hand.add(self.shoe.pick_first(self.start_cards[0]));
-            }
+                self.shoe.pick_first_suited(self.start_cards[0])
+            };
+            hand.add(card.card());
+            self.first_cards.push([card, card]);
         }
 
-        self.dealer_pick();
+        self.dealer_upcard = Some(self.dealer_pick());
 
-        for hand in self.hands.iter_mut() {
+        for (i, hand) in self.hands.iter_mut().enumerate() {
             if self.start_cards.len() > 1 {
-                for &card in self.start_cards.iter().skip(1) {
-                    hand.add(self.shoe.pick_first(card));
+                for (j, &card) in self.start_cards.iter().skip(1).enumerate() {
+                    let card = self.shoe.pick_first_suited(card);
+                    hand.add(card.card());
+                    if j == 0 {
+                        self.first_cards[i][1] = card;
+                    }
                 }
             } else {
-                hand.add(self.shoe.pick());
+                let card = self.shoe.pick_suited();
+                hand.add(card.card());
+                self.first_cards[i][1] = card;
             }
         }
 
@@ -114,33 +237,70 @@ impl<'a> Round<'a> {
             if self.holecarding {
                 self.context.holecard = Some(self.dealer[1]);
             }
+            if let Some((accuracy, rng)) = &mut self.tell {
+                let ten = self.dealer[1] == Card(10);
+                let right = *accuracy >= 1.0 || rng.gen::<f32>() < *accuracy;
+                self.context.hole_tell = Some(HoleTell {
+                    ten: ten == right,
+                    accuracy: *accuracy,
+                });
+            }
+        }
+
+        for i in 0..self.hands.len() {
+            self.log(|round| {
+                let hand = &round.hands[i];
+                Event::Deal {
+                    player: hand.id,
+                    spot: hand.spot,
+                    bet: hand.bet,
+                    cards: [hand[0], hand[1]],
+                }
+            });
         }
+        self.log(|round| Event::DealerDeal {
+            upcard: round.dealer[0],
+            holecard: if round.dealer.count() > 1 {
+                Some(round.dealer[1])
+            } else {
+                None
+            },
+        });
 
-        if self.rules.surrender == EarlySurrender {
-            self.check_surrender();
+        if self.rules.surrenders_early(self.dealer[0]) {
+            self.check_surrender(true);
         }
 
         if self.dealer[0] == Card(1) {
-            for hand in self.hands.iter_mut() {
+            for i in 0..self.hands.len() {
+                let hand = &mut self.hands[i];
                 if hand.is_surrendered() {
                     continue;
                 }
 
-                if self.strategy.take_insurance(&self.context, hand) {
-                    hand.insure();
+                let (strategy, _) = self.players[hand.id as usize];
+                if hand.is_bj() && self.rules.even_money {
+                    if strategy.take_even_money(&self.context, hand) {
+                        hand.take_even_money();
+                        self.log_decision(i, Action::EvenMoney, false);
+                    }
+                } else if self.rules.insurance
+                          && strategy.take_insurance(&self.context, hand) {
+                    let amount = strategy.insurance_amount(&self.context, hand);
+                    hand.insure_for(amount);
+                    self.log_decision(i, Action::Insure, false);
                 }
             }
         }
 
-        if self.rules.game_type == Enhc
-           || (self.rules.game_type == Ahc && !self.dealer.is_bj()) {
+        if !(self.rules.dealer_peeks(self.dealer[0]) && self.dealer.is_bj()) {
             if self.rules.surrender == LateSurrender {
-                self.check_surrender();
+                self.check_surrender(false);
             }
 
             for i in 0..self.hands.len() {
                 if !self.hands[i].is_surrendered() {
-                    self.do_player_turn(i);
+                    self.do_player_turn(i)?;
                 }
             }
         }
@@ -149,108 +309,312 @@ impl<'a> Round<'a> {
               || (self.rules.soft17 == H17
                   && self.dealer.is_soft()
                   && self.dealer.value() == 17) {
-            self.dealer_pick();
+            let card = self.dealer_pick();
+            self.log(|round| Event::DealerDraw {
+                card: card.card(),
+                value: round.dealer.value(),
+                soft: round.dealer.is_soft(),
+            });
         }
 
-        if self.shoe.needs_reshuffle() {
+        let reshuffled = self.shoe.needs_reshuffle();
+        if reshuffled {
             self.shoe.reshuffle();
         }
 
         let mut player_results = [0.0; 7];
-        let mut hand_stats = HandStats::default();
+        let mut insurance_results = [0.0; 7];
+        let mut hand_stats: [HandStats; 7] = Default::default();
 
-        for hand in self.hands.iter() {
-            let (outcome, hand_result) = hand_result(hand, &self.dealer);
+        for i in 0..self.hands.len() {
+            let hand = &self.hands[i];
+            let (outcome, hand_result) = hand_result(self.rules, hand, &self.dealer);
+            let insurance = insurance_result(self.rules, hand, &self.dealer);
             player_results[hand.id as usize] += hand_result * hand.bet;
-            hand_stats.update(hand, outcome);
+            insurance_results[hand.id as usize] += insurance * hand.bet;
+            hand_stats[hand.id as usize].update(hand, outcome);
+
+            self.log(|round| {
+                let hand = &round.hands[i];
+                Event::Settle {
+                    player: hand.id,
+                    spot: hand.spot,
+                    hand: i,
+                    cards: cards_of(hand),
+                    value: hand.value(),
+                    stake: hand.stake() * hand.bet,
+                    outcome,
+                    result: hand_result * hand.bet,
+                    insurance: insurance * hand.bet,
+                }
+            });
         }
 
-        (
+        let side_bet_results = self.settle_side_bets(start_true_count);
+        self.log(|round| Event::RoundEnd {
+            dealer: cards_of(&round.dealer),
+            reshuffled,
+        });
+        let events = self.events.take().unwrap_or_default();
+
+        Ok((
             self,
             RoundResult {
                 player_results,
+                insurance_results,
+                side_bet_results,
                 hand_stats,
+                reshuffled,
+                events,
             },
-        )
+        ))
+    }
+
+    /// Records an event when keeping an event log; the event is only made
+    /// then.
+    fn log<F>(&mut self, make: F)
+        where F: FnOnce(&Self) -> Event {
+        if let Some(mut events) = self.events.take() {
+            events.push(make(self));
+            self.events = Some(events);
+        }
+    }
+
+    /// Records an action taken on the `i`-th hand, and its context.
+    fn log_decision(&mut self, i: usize, action: Action, scripted: bool) {
+        self.log(|round| {
+            let hand = &round.hands[i];
+            Event::Decision {
+                player: hand.id,
+                spot: hand.spot,
+                hand: i,
+                cards: cards_of(hand),
+                value: hand.value(),
+                soft: hand.is_soft(),
+                dealer: round.dealer[0],
+                action,
+                true_count: round.context.true_count,
+                may_double: round.context.may_double,
+                may_split: round.context.may_split,
+                scripted,
+            }
+        });
+    }
+
+    /// Returns the script played on the hand, if any.
+    fn script_of(&self, hand: &Hand) -> Option<&'a Script> {
+        match self.script {
+            Some((id, script)) if id == hand.id => Some(script),
+            _ => None,
+        }
+    }
+
+    /// Places the bets of each player on the spots they play, from first
+    /// base; a player gets fewer spots than wanted when the table is full, but
+    /// always one.
+    fn place_bets(&mut self) {
+        let num_players = self.players.len();
+
+        for (id, &(_, betting_strategy)) in self.players.iter().enumerate() {
+            if self.watcher == Some(id as u8) {
+                continue;
+            }
+
+            let true_count = self.context.true_count;
+            let free_spots = 7 - self.hands.len() - (num_players - id - 1);
+            let spots = (betting_strategy.spots(true_count) as usize)
+                .clamp(1, free_spots);
+
+            for _ in 0..spots {
+                let mut hand = Hand::new();
+                hand.id = id as u8;
+                hand.spot = self.hands.len() as u8;
+                hand.bet = betting_strategy.place_bet(true_count);
+                self.hands.push(hand);
+            }
+        }
     }
 
-    fn dealer_pick(&mut self) {
+    fn dealer_pick(&mut self) -> SuitedCard {
         let card = match self.dealer_cards.next() {
-            Some(&card) => self.shoe.pick_first(card),
-            None => self.shoe.pick(),
+            Some(&card) => self.shoe.pick_first_suited(card),
+            None => self.shoe.pick_suited(),
         };
-        self.dealer.add(card);
+        self.dealer.add(card.card());
+        card
     }
 
-    fn check_surrender(&mut self) {
-        if self.surrender_override == Some(false) {
-            return;
-        }
+    fn settle_side_bets(&self, true_count: f32) -> Vec<[Option<f64>; 7]> {
+        self.side_bets.iter().map(|wager| {
+            let mut results = [None; 7];
 
-        if self.surrender_override == Some(true) {
-            for hand in self.hands.iter_mut() {
-                hand.surrender();
+            if !wager.is_placed(true_count) {
+                return results;
             }
-        } else {
-            for hand in self.hands.iter_mut() {
-                if !hand.is_surrendered() && self.strategy.surrender(
-                    &self.context,
-                    self.dealer[0],
-                    hand,
-                    self.rules.surrender == EarlySurrender,
-                ) {
-                    hand.surrender();
+
+            for (i, &player) in self.first_cards.iter().enumerate() {
+                let hand = &self.hands[i];
+                if hand.bet == 0.0 || results[hand.id as usize].is_some() {
+                    continue;
                 }
+
+                let result = wager.side_bet.settle(&SideBetCards {
+                    player,
+                    dealer_upcard: self.dealer_upcard.unwrap(),
+                    dealer: &self.dealer,
+                });
+                results[hand.id as usize] = Some(result * wager.bet);
+            }
+
+            results
+        }).collect()
+    }
+
+    fn check_surrender(&mut self, is_early: bool) {
+        for i in 0..self.hands.len() {
+            let hand = &self.hands[i];
+            let surrender = match self.script_of(hand) {
+                Some(Script::Surrender) => true,
+                Some(Script::Play(_)) => false,
+                None => {
+                    let (strategy, _) = self.players[hand.id as usize];
+                    !hand.is_surrendered() && strategy.surrender(
+                        &self.context,
+                        self.dealer[0],
+                        hand,
+                        is_early,
+                    )
+                },
+            };
+
+            if surrender {
+                self.hands[i].surrender();
+                let scripted = self.script_of(&self.hands[i]).is_some();
+                self.log_decision(i, Action::Surrender, scripted);
             }
         }
     }
 
-    fn do_player_turn(&mut self, i: usize) {
+    /// Returns the next scripted decision of the hand, if any.
+    fn next_scripted(&mut self, i: usize) -> Option<Decision> {
+        let script = self.script_of(&self.hands[i])?;
+        let (hand, step) = self.script_steps[i]?;
+        let decision = script.decision(hand as usize, step as usize)?;
+        self.script_steps[i] = Some((hand, step + 1));
+        Some(decision)
+    }
+
+    fn do_player_turn(&mut self, i: usize) -> Result<(), ScriptError> {
+        let (strategy, _) = self.players[self.hands[i].id as usize];
+
+        if let Some(Script::Surrender) = self.script_of(&self.hands[i]) {
+            // The hand was not surrendered, the upcard excludes it
+            return Err(ScriptError::SurrenderUnavailable {
+                hand: self.hands[i].to_string(),
+                dealer: self.dealer[0],
+            });
+        }
+
+        if self.script_of(&self.hands[i]).is_some()
+           && self.script_steps[i].is_none() {
+            // The hands of each spot follow the script in the order they
+            // are played
+            let spot = self.hands[i].spot as usize;
+            self.script_steps[i] = Some((self.script_hands[spot], 0));
+            self.script_hands[spot] += 1;
+        }
+
         loop {
             let hand = &self.hands[i];
             assert!(!hand.is_busted());
-            let hands_count = self.hands_per_player[hand.id as usize] as u32;
+            if is_charlie(self.rules, hand) {
+                return Ok(());
+            }
+            if self.next_card {
+                self.context.next_card = self.shoe.peek();
+            }
+            // A doubled hand is closed once it may not double again, which
+            // the script's decisions account for
+            let closed = hand.is_doubled() && !may_double(self.rules, hand);
+            let scripted = self.next_scripted(i);
+            let hand = &self.hands[i];
+            if scripted.is_none()
+               && may_surrender_in_play(self.rules, hand)
+               && strategy.surrender(&self.context, self.dealer[0], hand,
+                                     false) {
+                self.hands[i].surrender();
+                self.log_decision(i, Action::Surrender, false);
+                return Ok(());
+            }
+            if closed {
+                return Ok(());
+            }
+            let hands_count = self.hands_per_spot[hand.spot as usize] as u32;
             self.context.may_split = hands_count < self.rules.max_splits
                                      && hand.count() == 2
                                      && hand[0] == hand[1];
-            self.context.may_double = may_double(
-                self.rules.double_down,
-                self.rules.das,
-                hand
-            );
+            self.context.may_double = may_double(self.rules, hand);
             self.context.true_count = self.shoe.true_count();
 
-            let decision = if let Some(action) = self.override_action {
-                if action == Decision::Split && !self.context.may_split {
-                    eprintln!("Unable to split");
-                    exit(2);
-                }
-                // FIXME: does not work with multiple players
-                self.override_action = None;
+            let decision = match scripted {
+                Some(decision) => {
+                    let allowed = match decision {
+                        Decision::Hit => !hand.is_doubled(),
+                        Decision::Stand => true,
+                        Decision::Double => self.context.may_double,
+                        Decision::Split => self.context.may_split,
+                    };
+                    if !allowed {
+                        return Err(ScriptError::Forbidden {
+                            decision,
+                            hand: hand.to_string(),
+                            dealer: self.dealer[0],
+                        });
+                    }
 
-                action
+                    decision
+                },
+                None => strategy.player_turn(&self.context, self.dealer[0],
+                                             hand),
+            };
+
+            self.log_decision(i, decision.into(), scripted.is_some());
+
+            let hand = &self.hands[i];
+            let double_ratio = if decision == Decision::Double
+                                  && scripted.is_none() {
+                strategy.double_amount(&self.context, self.dealer[0], hand)
             } else {
-                self.strategy.player_turn(&self.context, self.dealer[0], hand)
+                1.0
             };
 
             let hand = &mut self.hands[i];
 
             match decision {
                 Decision::Stand => {
-                    return;
+                    return Ok(());
                 },
                 Decision::Hit => {
+                    assert!(!hand.is_doubled(),
+                            "Hitting a doubled-down hand is forbidden");
                     hand.add(self.shoe.pick());
                     if hand.is_busted() {
-                        return;
+                        return Ok(());
                     }
                 },
                 Decision::Double => {
                     assert!(self.context.may_double,
                             "Doubling down is forbidden");
+                    assert!(double_ratio == 1.0 || self.rules.double_for_less,
+                            "Doubling down for less is forbidden");
                     hand.add(self.shoe.pick());
-                    hand.double_down();
-                    return;
+                    hand.double_down_by(double_ratio);
+
+                    // The hand is closed unless it may be re-doubled or
+                    // rescued, which is checked on the next iteration
+                    if hand.is_busted() {
+                        return Ok(());
+                    }
                 },
                 Decision::Split => {
                     assert!(self.context.may_split,
@@ -258,17 +622,17 @@ impl<'a> Round<'a> {
 
                     let common = hand[0];
                     let mut new_hand = hand.split();
-                    self.hands_per_player[hand.id as usize] += 1;
+                    self.hands_per_spot[hand.spot as usize] += 1;
                     hand.add(self.shoe.pick());
                     new_hand.add(self.shoe.pick());
                     self.hands.push(new_hand);
 
                     if self.rules.play_ace_pairs || common != Card(1) {
                         let next = self.hands.len() - 1;
-                        self.do_player_turn(i);
-                        self.do_player_turn(next);
+                        self.do_player_turn(i)?;
+                        self.do_player_turn(next)?;
                     }
-                    return;
+                    return Ok(());
                 },
             }
         }
@@ -278,7 +642,7 @@ impl<'a> Round<'a> {
 impl Debug for Round<'_> {
     fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
         for hand in self.hands.iter() {
-            write!(f, "({}) {}\n", hand.id, hand)?;
+            writeln!(f, "({}) {}", hand.id, hand)?;
         }
 
         write!(f, "Vs. {}", self.dealer)
@@ -287,15 +651,30 @@ impl Debug for Round<'_> {
 
 #[cfg(test)]
 mod tests {
-    use crate::round::Round;
-    use crate::test_utils::{QueuedStrategy, make_rules};
+    use crate::round::{Round, RoundResult, Player};
+    use crate::basic_strategy::BasicStrategy;
+    use crate::tourist_strategy::TouristStrategy;
+    use crate::test_utils::{QueuedStrategy, make_rules, assert_f64_eq};
     use crate::strategy::Decision::*;
     use crate::strategy::Decision;
     use crate::hand_stats::HandStats;
-    use crate::betting::FixedBet;
+    use crate::betting::{FixedBet, HiloBetting};
     use crate::test_utils::options::*;
     use std::collections::VecDeque;
     use crate::shoe::queued_shoe::QueuedShoe;
+    use crate::game_rules::{GameRules, DoublePolicy, SurrenderPolicy,
+                            PeekPolicy};
+    use crate::card::{Suit, SuitedCard};
+    use crate::side_bet::{SideBetWager, PerfectPairs, BusterBlackjack};
+    use crate::script::{Script, ScriptError};
+    use crate::event::{Event, Action};
+    use crate::hand_logic::HandOutcome;
+    use crate::hand::Hand;
+    use crate::card::Card;
+    use crate::dealer_probabilities::DealerProbabilities;
+    use std::str::FromStr;
+    use rand::SeedableRng;
+    use rand::rngs::SmallRng;
 
     #[test]
     fn it_wins_a_hand() {
@@ -333,6 +712,226 @@ mod tests {
                     (1, 0, 1, 0, 0, 0, 1, 0, 0, 0));
     }
 
+    #[test]
+    fn it_stops_playing_a_charlie() {
+        //           Tt Wo Lo Pu Bu BJ Db Sp In Su
+        test_result(&[2, 10, 3, 9, 2, 2, 2], AHC|S17|CHARLIE5,
+                    &[Hit, Hit, Hit], 10.0,
+                    (1, 1, 0, 0, 0, 0, 0, 0, 0, 0));
+        test_result(&[2, 10, 3, 9, 2, 2, 2, 3], AHC|S17,
+                    &[Hit, Hit, Hit, Hit, Stand], -10.0,
+                    (1, 0, 1, 0, 0, 0, 0, 0, 0, 0));
+    }
+
+    #[test]
+    fn it_doubles_down_for_less() {
+        let mut rules = make_rules(AHC|S17);
+        rules.double_for_less = true;
+        let strategy = QueuedStrategy::new(&[Double], false, false)
+            .with_double_amount(0.5);
+        let (result, _) = run_round(&rules, &strategy, &[6, 7, 5, 10, 9]);
+        assert_eq!(result, 15.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Doubling down for less is forbidden")]
+    fn it_panics_when_doubling_for_less_is_forbidden() {
+        let rules = make_rules(AHC|S17);
+        let strategy = QueuedStrategy::new(&[Double], false, false)
+            .with_double_amount(0.5);
+        run_round(&rules, &strategy, &[6, 7, 5, 10, 9]);
+    }
+
+    #[test]
+    fn it_redoubles() {
+        let mut rules = make_rules(AHC|S17);
+        rules.max_redoubles = 1;
+
+        let strategy = QueuedStrategy::new(&[Double, Double], false, false);
+        let (result, stats) = run_round(&rules, &strategy,
+                                        &[3, 7, 2, 10, 4, 9]);
+        assert_eq!(result, 40.0);
+        assert_eq!(stats, HandStats::from((1, 1, 0, 0, 0, 0, 1, 0, 0, 0)));
+
+        let strategy = QueuedStrategy::new(&[Double, Stand], false, false);
+        let (result, _) = run_round(&rules, &strategy, &[3, 7, 2, 10, 9]);
+        assert_eq!(result, -20.0);
+    }
+
+    #[test]
+    fn it_doubles_after_hit() {
+        let mut rules = make_rules(AHC|S17);
+        rules.double_after_hit = DoublePolicy::AnyHand;
+
+        let strategy = QueuedStrategy::new(&[Hit, Double], false, false);
+        let (result, _) = run_round(&rules, &strategy,
+                                    &[3, 7, 2, 10, 5, 9]);
+        assert_eq!(result, 20.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Hitting a doubled-down hand is forbidden")]
+    fn it_panics_when_hitting_a_redoubled_hand() {
+        let mut rules = make_rules(AHC|S17);
+        rules.max_redoubles = 1;
+        let strategy = QueuedStrategy::new(&[Double, Hit], false, false);
+        run_round(&rules, &strategy, &[3, 7, 2, 10, 4, 9]);
+    }
+
+    #[test]
+    fn it_rescues_a_doubled_hand() {
+        let mut rules = make_rules(AHC|S17);
+        rules.double_rescue = true;
+
+        let strategy = QueuedStrategy::new(&[Double], false, true);
+        let (result, stats) = run_round(&rules, &strategy, &[5, 10, 6, 7, 5]);
+        assert_eq!(result, -10.0);
+        assert_eq!(stats, HandStats::from((1, 0, 1, 0, 0, 0, 1, 0, 0, 1)));
+
+        let strategy = QueuedStrategy::new(&[Double], false, false);
+        let (result, _) = run_round(&rules, &strategy, &[5, 10, 6, 7, 5]);
+        assert_eq!(result, -20.0);
+    }
+
+    #[test]
+    fn it_surrenders_after_hitting() {
+        let mut rules = make_rules(AHC|S17);
+        rules.surrender = SurrenderPolicy::EarlySurrenderTens;
+        rules.surrender_any_cards = true;
+
+        let strategy = QueuedStrategy::new(&[Hit], false, true);
+        let (result, stats) = run_round(&rules, &strategy, &[4, 9, 6, 8, 4]);
+        assert_eq!(result, -5.0);
+        assert_eq!(stats, HandStats::from((1, 0, 1, 0, 0, 0, 0, 0, 0, 1)));
+    }
+
+    #[test]
+    fn it_surrenders_after_split() {
+        let mut rules = make_rules(AHC|S17);
+        rules.surrender = SurrenderPolicy::EarlySurrenderTens;
+        rules.surrender_after_split = true;
+
+        let strategy = QueuedStrategy::new(&[Split], false, true);
+        let (result, stats) = run_round(&rules, &strategy,
+                                        &[8, 9, 8, 8, 10, 10]);
+        assert_eq!(result, -10.0);
+        assert_eq!(stats, HandStats::from((2, 0, 2, 0, 0, 0, 0, 2, 0, 2)));
+    }
+
+    #[test]
+    fn it_surrenders_early_against_tens_only() {
+        let mut rules = make_rules(ENHC|S17);
+        rules.surrender = SurrenderPolicy::EarlySurrenderTens;
+
+        let strategy = QueuedStrategy::new(&[], false, true);
+        let (result, _) = run_round(&rules, &strategy, &[10, 10, 6, 7]);
+        assert_eq!(result, -5.0);
+
+        let strategy = QueuedStrategy::new(&[Stand], false, true);
+        let (result, _) = run_round(&rules, &strategy, &[10, 1, 6, 7]);
+        assert_eq!(result, -10.0);
+    }
+
+    #[test]
+    fn it_plays_against_unpeeked_naturals() {
+        let mut rules = make_rules(AHC|S17);
+        rules.peek = PeekPolicy::PeekAce;
+
+        // The dealer does not peek under a ten: the player plays and loses
+        // their double to the natural
+        let strategy = QueuedStrategy::new(&[Double], false, false);
+        let (result, _) = run_round(&rules, &strategy, &[5, 10, 6, 1, 9]);
+        assert_eq!(result, -20.0);
+
+        // The dealer peeks under an ace
+        let strategy = QueuedStrategy::new(&[], false, false);
+        let (result, _) = run_round(&rules, &strategy, &[5, 1, 6, 10]);
+        assert_eq!(result, -10.0);
+
+        rules.peek = PeekPolicy::NoPeek;
+        let strategy = QueuedStrategy::new(&[Hit, Stand], false, false);
+        let (result, _) = run_round(&rules, &strategy, &[5, 1, 6, 10, 2]);
+        assert_eq!(result, -10.0);
+    }
+
+    #[test]
+    fn it_only_loses_original_bets() {
+        let mut rules = make_rules(ENHC|S17|DAS);
+        rules.original_bets_only = true;
+
+        let strategy = QueuedStrategy::new(&[Double], false, false);
+        let (result, stats) = run_round(&rules, &strategy, &[5, 10, 6, 9, 1]);
+        assert_eq!(result, -10.0);
+        assert_eq!(stats, HandStats::from((1, 0, 1, 0, 0, 0, 1, 0, 0, 0)));
+
+        let strategy = QueuedStrategy::new(&[Split, Double, Stand], false,
+                                           false);
+        let (result, stats) = run_round(&rules, &strategy,
+                                        &[8, 10, 8, 3, 10, 9, 1]);
+        assert_eq!(result, -10.0);
+        assert_eq!(stats, HandStats::from((2, 0, 1, 1, 0, 0, 1, 2, 0, 0)));
+
+        rules.original_bets_only = false;
+        let strategy = QueuedStrategy::new(&[Split, Double, Stand], false,
+                                           false);
+        let (result, _) = run_round(&rules, &strategy,
+                                    &[8, 10, 8, 3, 10, 9, 1]);
+        assert_eq!(result, -30.0);
+    }
+
+    #[test]
+    fn it_settles_side_bets() {
+        let rules = make_rules(AHC|S17);
+        let strategy = QueuedStrategy::new(&[Stand], false, false);
+        let side_bets = [
+            SideBetWager {
+                side_bet: Box::new(PerfectPairs::default()),
+                bet: 5.0,
+                min_true_count: None,
+            },
+            SideBetWager {
+                side_bet: Box::new(BusterBlackjack::default()),
+                bet: 5.0,
+                min_true_count: Some(2.0),
+            },
+        ];
+        let start_cards = VecDeque::new();
+        let mut shoe = QueuedShoe::from_suited(&[
+            SuitedCard::new(8, Suit::Hearts),
+            SuitedCard::new(6, Suit::Clubs),
+            SuitedCard::new(8, Suit::Diamonds),
+            SuitedCard::new(13, Suit::Spades),
+            SuitedCard::new(12, Suit::Spades),
+        ]);
+        let (_, result) = Round::new(&rules, &strategy, &FixedBet(10.0),
+                                     &mut shoe, 1, false,
+                                     &start_cards, &start_cards)
+            .with_side_bets(&side_bets)
+            .run().unwrap();
+
+        assert!(shoe.is_empty());
+        assert_eq!(result.player_results[0], 10.0);
+        assert_eq!(result.side_bet_results[0][0], Some(60.0));
+        assert_eq!(result.side_bet_results[1][0], None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Side bets require a shoe of suited cards")]
+    fn it_settles_side_bets_on_suited_cards_only() {
+        let rules = make_rules(AHC|S17);
+        let strategy = QueuedStrategy::new(&[Stand], false, false);
+        let side_bets = [SideBetWager {
+            side_bet: Box::new(PerfectPairs::default()),
+            bet: 1.0,
+            min_true_count: None,
+        }];
+        let start_cards = VecDeque::new();
+        let mut shoe = QueuedShoe::from_ints(&[8, 6, 8, 10]);
+        let _ = Round::new(&rules, &strategy, &FixedBet(10.0), &mut shoe, 1,
+                           false, &start_cards, &start_cards)
+            .with_side_bets(&side_bets);
+    }
+
     #[test]
     fn it_insures() {
         //           Tt Wo Lo Pu Bu BJ Db Sp In Su
@@ -340,10 +939,6 @@ mod tests {
                     (1, 0, 1, 0, 0, 0, 0, 0, 0, 0));
         test_result(&[8, 1, 10, 10], AHC|S17|INSURE, &[], 0.0,
                     (1, 0, 1, 0, 0, 0, 0, 0, 1, 0));
-        test_result(&[1, 1, 10, 10], AHC|S17|INSURE, &[], 10.0,
-                    (1, 0, 0, 1, 0, 1, 0, 0, 1, 0));
-        test_result(&[1, 1, 10, 9], AHC|S17|INSURE, &[Stand], 10.0,
-                    (1, 1, 0, 0, 0, 1, 0, 0, 1, 0));
         test_result(&[10, 1, 10, 9], AHC|S17|INSURE, &[Stand], -5.0,
                     (1, 0, 0, 1, 0, 0, 0, 0, 1, 0));
         test_result(&[10, 1, 9, 9], AHC|S17|INSURE, &[Stand], -15.0,
@@ -351,6 +946,171 @@ mod tests {
         //           Tt Wo Lo Pu Bu BJ Db Sp In Su
     }
 
+    #[test]
+    fn it_insures_for_less() {
+        let rules = make_rules(AHC|S17);
+        let strategy = QueuedStrategy::new(&[], true, false)
+            .with_insurance_amount(0.25);
+        let result = run_round_result(&rules, &strategy, &[8, 1, 10, 10]);
+        assert_eq!(result.player_results[0], -5.0);
+        assert_eq!(result.insurance_results[0], 5.0);
+
+        let strategy = QueuedStrategy::new(&[Stand], true, false)
+            .with_insurance_amount(0.25);
+        let result = run_round_result(&rules, &strategy, &[10, 1, 9, 9]);
+        assert_eq!(result.player_results[0], -12.5);
+        assert_eq!(result.insurance_results[0], -2.5);
+    }
+
+    #[test]
+    fn it_pays_even_money() {
+        let mut rules = make_rules(AHC|S17);
+
+        let strategy = QueuedStrategy::new(&[], true, false);
+        let result = run_round_result(&rules, &strategy, &[1, 1, 10, 10]);
+        assert_eq!(result.player_results[0], 10.0);
+        assert_eq!(result.insurance_results[0], 10.0);
+        assert_eq!(result.hand_stats[0], HandStats {
+            even_money: 1,
+            ..HandStats::from((1, 0, 0, 1, 0, 1, 0, 0, 1, 0))
+        });
+
+        let strategy = QueuedStrategy::new(&[Stand], true, false);
+        let result = run_round_result(&rules, &strategy, &[1, 1, 10, 9]);
+        assert_eq!(result.player_results[0], 10.0);
+        assert_eq!(result.insurance_results[0], -5.0);
+
+        // Without even money, a natural may still be insured
+        rules.even_money = false;
+        let strategy = QueuedStrategy::new(&[Stand], true, false);
+        let result = run_round_result(&rules, &strategy, &[1, 1, 10, 9]);
+        assert_eq!(result.player_results[0], 10.0);
+        assert_eq!(result.hand_stats[0].even_money, 0);
+        assert_eq!(result.hand_stats[0].insured, 1);
+    }
+
+    #[test]
+    fn it_does_not_insure_without_the_rule() {
+        let mut rules = make_rules(AHC|S17);
+        rules.insurance = false;
+        rules.even_money = false;
+
+        let strategy = QueuedStrategy::new(&[], true, false);
+        let result = run_round_result(&rules, &strategy, &[8, 1, 10, 10]);
+        assert_eq!(result.player_results[0], -10.0);
+        assert_eq!(result.insurance_results[0], 0.0);
+
+        let result = run_round_result(&rules, &strategy, &[1, 1, 10, 10]);
+        assert_eq!(result.player_results[0], 0.0);
+        assert_eq!(result.hand_stats[0].insured, 0);
+    }
+
+    #[test]
+    fn it_plays_several_seats() {
+        let rules = make_rules(AHC|S17);
+        let start_cards = VecDeque::new();
+        let strategy = QueuedStrategy::new(&[Stand, Split, Stand, Stand, Hit],
+                                           false, false);
+        // Seats 1, 2, 3, dealer upcard, seats 1, 2, 3, dealer holecard
+        let mut shoe = QueuedShoe::from_ints(&[10, 8, 10, 10, 10, 8, 6, 7,
+                                               10, 2, 10]);
+        let (round, result) = Round::new(&rules, &strategy, &FixedBet(10.0),
+                                         &mut shoe, 3, false,
+                                         &start_cards, &start_cards)
+            .run().unwrap();
+
+        assert_eq!(round.hands.iter().map(|h| h.id).collect::<Vec<_>>(),
+                   vec![0, 1, 2, 1]);
+        drop(round);
+        assert!(shoe.is_empty());
+        assert!(strategy.is_empty(), "Not all decisions were taken");
+        assert_eq!(result.player_results,
+                   [10.0, 0.0, -10.0, 0.0, 0.0, 0.0, 0.0]);
+        assert_eq!(result.hand_stats[0],
+                   HandStats::from((1, 1, 0, 0, 0, 0, 0, 0, 0, 0)));
+        assert_eq!(result.hand_stats[1],
+                   HandStats::from((2, 1, 1, 0, 0, 0, 0, 2, 0, 0)));
+        assert_eq!(result.hand_stats[2],
+                   HandStats::from((1, 0, 1, 0, 1, 0, 0, 0, 0, 0)));
+        assert_eq!(result.hand_stats[3], HandStats::default());
+    }
+
+    #[test]
+    fn it_plays_each_seat_with_its_strategy() {
+        let rules = make_rules(AHC|S17);
+        let start_cards = VecDeque::new();
+        let basic = BasicStrategy::new(false);
+        let tourist = TouristStrategy::new();
+        let players = [
+            Player { strategy: &basic, betting_strategy: &FixedBet(10.0) },
+            Player { strategy: &tourist, betting_strategy: &FixedBet(5.0) },
+        ];
+        let mut shoe = QueuedShoe::from_ints(&[10, 10, 2, 2, 2, 10, 5, 10]);
+        let (round, result) = Round::new(&rules, &basic, &FixedBet(1.0),
+                                         &mut shoe, 2, false,
+                                         &start_cards, &start_cards)
+            .with_players(&players)
+            .run().unwrap();
+
+        // Basic strategy hits 12 against a 2, the tourist never does
+        assert_eq!(round.hands[0].count(), 3);
+        assert_eq!(round.hands[1].count(), 2);
+        drop(round);
+        assert!(shoe.is_empty());
+        assert_eq!(result.player_results,
+                   [10.0, 5.0, 0.0, 0.0, 0.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn it_lets_a_player_watch() {
+        let rules = make_rules(AHC|S17);
+        let start_cards = VecDeque::new();
+        let strategy = BasicStrategy::new(false);
+        let mut shoe = QueuedShoe::from_ints(&[10, 6, 8, 10, 10]);
+        let (round, result) = Round::new(&rules, &strategy, &FixedBet(10.0),
+                                         &mut shoe, 2, false,
+                                         &start_cards, &start_cards)
+            .with_watcher(0)
+            .run().unwrap();
+
+        assert_eq!(round.hands.iter().map(|h| h.id).collect::<Vec<_>>(), [1]);
+        drop(round);
+        assert!(shoe.is_empty());
+        assert_eq!(result.player_results,
+                   [0.0, 10.0, 0.0, 0.0, 0.0, 0.0, 0.0]);
+        assert_eq!(result.hand_stats[0].total, 0);
+    }
+
+    #[test]
+    fn it_plays_several_spots() {
+        let rules = make_rules(AHC|S17);
+        let start_cards = VecDeque::new();
+        let basic = BasicStrategy::new(false);
+        let two_spots = HiloBetting::new(10.0, 0.0, None, None, None)
+            .with_spots(vec![(0.0, 2)]);
+        let players = [
+            Player { strategy: &basic, betting_strategy: &two_spots },
+            Player { strategy: &basic, betting_strategy: &FixedBet(5.0) },
+        ];
+        let mut shoe = QueuedShoe::from_ints(&[10, 10, 10, 6, 8, 9, 7, 10, 10]);
+        let (round, result) = Round::new(&rules, &basic, &FixedBet(1.0),
+                                         &mut shoe, 2, false,
+                                         &start_cards, &start_cards)
+            .with_players(&players)
+            .run().unwrap();
+
+        assert_eq!(round.hands.iter().map(|h| h.id).collect::<Vec<_>>(),
+                   [0, 0, 1]);
+        assert_eq!(round.hands.iter().map(|h| h.spot).collect::<Vec<_>>(),
+                   [0, 1, 2]);
+        drop(round);
+        assert!(shoe.is_empty());
+        assert_eq!(result.player_results,
+                   [20.0, 5.0, 0.0, 0.0, 0.0, 0.0, 0.0]);
+        assert_eq!(result.hand_stats[0].total, 2);
+        assert_eq!(result.hand_stats[1].total, 1);
+    }
+
     #[test]
     fn it_split_pairs() {
         //                 Tt Wo Lo Pu Bu BJ Db Sp In Su
@@ -382,8 +1142,8 @@ mod tests {
         let strategy = QueuedStrategy::new(&[Split, Double, Double], false, false);
         let mut shoe = QueuedShoe::from_ints(&[8, 6, 8, 10, 3, 1]);
         Round::new(&rules, &strategy, &FixedBet(1.0), &mut shoe,
-                   1, false, None, None, &start_cards, &start_cards)
-            .run();
+                   1, false, &start_cards, &start_cards)
+            .run().unwrap();
     }
 
     #[test]
@@ -394,8 +1154,8 @@ mod tests {
         let strategy = QueuedStrategy::new(&[Split], false, false);
         let mut shoe = QueuedShoe::from_ints(&[8, 6, 7, 10]);
         Round::new(&rules, &strategy, &FixedBet(1.0), &mut shoe,
-                   1, false, None, None, &start_cards, &start_cards)
-            .run();
+                   1, false, &start_cards, &start_cards)
+            .run().unwrap();
     }
 
     #[test]
@@ -407,8 +1167,8 @@ mod tests {
         let strategy = QueuedStrategy::new(&[Split, Split], false, false);
         let mut shoe = QueuedShoe::from_ints(&[8, 6, 8, 10, 8, 7]);
         Round::new(&rules, &strategy, &FixedBet(1.0), &mut shoe,
-                   1, false, None, None, &start_cards, &start_cards)
-            .run();
+                   1, false, &start_cards, &start_cards)
+            .run().unwrap();
     }
 
     #[test]
@@ -419,8 +1179,33 @@ mod tests {
         let strategy = QueuedStrategy::new(&[Hit, Split], false, false);
         let mut shoe = QueuedShoe::from_ints(&[4, 6, 4, 10, 4]);
         Round::new(&rules, &strategy, &FixedBet(1.0), &mut shoe,
-                   1, false, None, None, &start_cards, &start_cards)
-            .run();
+                   1, false, &start_cards, &start_cards)
+            .run().unwrap();
+    }
+
+    fn run_round(rules: &GameRules,
+                 strategy: &QueuedStrategy,
+                 cards: &[u8]) -> (f64, HandStats) {
+        let RoundResult { player_results, hand_stats: [stats, ..], .. }
+            = run_round_result(rules, strategy, cards);
+
+        (player_results[0], stats)
+    }
+
+    fn run_round_result(rules: &GameRules,
+                        strategy: &QueuedStrategy,
+                        cards: &[u8]) -> RoundResult {
+        let start_cards = VecDeque::new();
+        let mut shoe = QueuedShoe::from_ints(cards);
+        let (_, result) = Round::new(rules, strategy, &FixedBet(10.0),
+                                     &mut shoe, 1, false,
+                                     &start_cards, &start_cards)
+            .run().unwrap();
+
+        assert!(shoe.is_empty());
+        assert!(strategy.is_empty(), "Not all decisions were taken");
+
+        result
     }
 
     const INSURE: u32    = 1 << 3;
@@ -441,18 +1226,234 @@ mod tests {
         let betting = FixedBet(10.0);
         let mut shoe = QueuedShoe::from_ints(cards);
         let round = Round::new(&rules, &strategy, &betting, &mut shoe,
-                               1, false, None, None,
+                               1, false,
                                &start_cards, &start_cards);
 
-        let (_, result) = round.run();
+        let (_, result) = round.run().unwrap();
 
         assert_eq!(result.player_results,
                    [expected_result, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0]);
-        assert_eq!(result.hand_stats, HandStats::from(expected_stats),
+        assert_eq!(result.hand_stats[0], HandStats::from(expected_stats),
                    "{:?}", cards);
         assert!(shoe.is_empty());
         assert!(strategy.is_empty(), "Not all decisions were taken");
     }
 
-    // TODO: test surrender override
+    #[test]
+    fn it_logs_events() {
+        let rules = make_rules(AHC|S17);
+        let start_cards = VecDeque::new();
+        let strategy = QueuedStrategy::new(&[Hit, Stand], false, false);
+        let mut shoe = QueuedShoe::from_ints(&[10, 6, 2, 10, 8, 2]);
+        let (_, result) = Round::new(&rules, &strategy, &FixedBet(10.0),
+                                     &mut shoe, 1, false,
+                                     &start_cards, &start_cards)
+            .with_event_log()
+            .run().unwrap();
+
+        assert_eq!(result.events.len(), 8);
+        assert!(matches!(result.events[0], Event::RoundStart { .. }));
+        assert_eq!(result.events[1], Event::Deal {
+            player: 0, spot: 0, bet: 10.0, cards: [Card(10), Card(2)],
+        });
+        assert_eq!(result.events[2], Event::DealerDeal {
+            upcard: Card(6), holecard: Some(Card(10)),
+        });
+        assert!(matches!(result.events[3], Event::Decision {
+            action: Action::Hit, value: 12, may_double: true, may_split: false,
+            scripted: false, ..
+        }));
+        assert!(matches!(result.events[4], Event::Decision {
+            action: Action::Stand, value: 20, may_double: false, ..
+        }));
+        assert_eq!(result.events[5], Event::DealerDraw {
+            card: Card(2), value: 18, soft: false,
+        });
+        assert!(matches!(result.events[6], Event::Settle {
+            outcome: HandOutcome::Win, result, stake, ..
+        } if result == 10.0 && stake == 10.0));
+        assert_eq!(result.events[7], Event::RoundEnd {
+            dealer: vec![Card(6), Card(10), Card(2)], reshuffled: false,
+        });
+    }
+
+    #[test]
+    fn it_draws_the_dealer_hands_by_their_exact_probabilities() {
+        // Every order of the cards left is dealt after the player's 10-10 and
+        // the dealer's 6, each ending with the dealer standing on a total
+        let left = [1, 1, 2, 4, 5, 9, 10];
+        let orders = permutations(&left);
+        let mut cards = [0; 10];
+        for &card in left.iter() {
+            cards[card as usize - 1] += 1;
+        }
+
+        for opts in [AHC|S17, AHC|H17] {
+            let rules = make_rules(opts);
+            let start_cards = VecDeque::new();
+            let mut finals = [0.0; 10];
+            for order in orders.iter() {
+                let strategy = QueuedStrategy::new(&[Stand], false, false);
+                let dealt = [&[10, 6, 10], &order[..]].concat();
+                let mut shoe = QueuedShoe::from_ints(&dealt);
+                let (_, result) = Round::new(&rules, &strategy,
+                                             &FixedBet(10.0), &mut shoe, 1,
+                                             false, &start_cards, &start_cards)
+                    .with_event_log()
+                    .run().unwrap();
+                let dealer = match result.events.last() {
+                    Some(Event::RoundEnd { dealer, .. }) => {
+                        Hand::from(&dealer[..])
+                    },
+                    _ => panic!("No end of round"),
+                };
+                let total = dealer.value() as usize - 17;
+                finals[total] += 1.0 / orders.len() as f64;
+            }
+
+            let expected = DealerProbabilities::new(&rules.soft17)
+                .finals(&cards, Card(6));
+            for (&actual, expected) in finals.iter().zip(expected) {
+                assert_f64_eq(actual, expected, 1e-12);
+            }
+        }
+    }
+
+    fn permutations(cards: &[u8]) -> Vec<Vec<u8>> {
+        if cards.is_empty() {
+            return vec![Vec::new()];
+        }
+        (0..cards.len()).flat_map(|i| {
+            let rest = [&cards[..i], &cards[i + 1..]].concat();
+            permutations(&rest).into_iter().map(move |mut order| {
+                order.push(cards[i]);
+                order
+            })
+        }).collect()
+    }
+
+    #[test]
+    fn it_plays_against_a_warp() {
+        let rules = make_rules(AHC|S17);
+        let strategy = BasicStrategy::new(false);
+        let start_cards = VecDeque::new();
+        let mut rng = SmallRng::seed_from_u64(0);
+
+        // Hit a 17 against a ten reading as a ten
+        let mut shoe = QueuedShoe::from_ints(&[10, 10, 7, 10, 4]);
+        let (_, result) = Round::new(&rules, &strategy, &FixedBet(10.0),
+                                     &mut shoe, 1, false,
+                                     &start_cards, &start_cards)
+            .with_tell(1.0, &mut rng)
+            .run().unwrap();
+        assert_eq!(result.player_results[0], 10.0);
+        assert!(shoe.is_empty());
+    }
+
+    #[test]
+    fn it_plays_the_next_card() {
+        let rules = make_rules(AHC|S17);
+        let strategy = BasicStrategy::new(false);
+        let start_cards = VecDeque::new();
+
+        // Double a 16 against a ten drawing a five
+        let mut shoe = QueuedShoe::from_ints(&[10, 10, 6, 7, 5]);
+        let (_, result) = Round::new(&rules, &strategy, &FixedBet(10.0),
+                                     &mut shoe, 1, false,
+                                     &start_cards, &start_cards)
+            .with_next_card()
+            .run().unwrap();
+        assert_eq!(result.player_results[0], 20.0);
+        assert!(shoe.is_empty());
+    }
+
+    #[test]
+    fn it_plays_a_script() {
+        let rules = make_rules(AHC|S17|DAS);
+        let start_cards = VecDeque::new();
+        let strategy = QueuedStrategy::new(&[], false, false);
+        let script = Script::from_str("VD,=").unwrap();
+        let mut shoe = QueuedShoe::from_ints(&[8, 10, 8, 7, 3, 10, 10]);
+        let (_, result) = Round::new(&rules, &strategy, &FixedBet(10.0),
+                                     &mut shoe, 1, false,
+                                     &start_cards, &start_cards)
+            .with_script(0, &script)
+            .run().unwrap();
+
+        assert!(shoe.is_empty());
+        assert_eq!(result.player_results[0], 30.0);
+        assert_eq!(result.hand_stats[0],
+                   HandStats::from((2, 2, 0, 0, 0, 0, 1, 2, 0, 0)));
+    }
+
+    #[test]
+    fn it_fails_on_forbidden_scripted_decisions() {
+        let rules = make_rules(AHC|S17);
+        let start_cards = VecDeque::new();
+        let strategy = QueuedStrategy::new(&[], false, false);
+        let script = Script::from_str("V").unwrap();
+        let mut shoe = QueuedShoe::from_ints(&[10, 8, 9, 7]);
+        let result = Round::new(&rules, &strategy, &FixedBet(10.0),
+                                &mut shoe, 1, false,
+                                &start_cards, &start_cards)
+            .with_script(0, &script)
+            .run();
+
+        assert_eq!(result.err(), Some(ScriptError::Forbidden {
+            decision: Split,
+            hand: Hand::from(&[10, 9][..]).to_string(),
+            dealer: Card(8),
+        }));
+
+        // Surrendering early against a ten only
+        let mut rules = make_rules(ENHC|S17);
+        rules.surrender = SurrenderPolicy::EarlySurrenderTens;
+        let mut shoe = QueuedShoe::from_ints(&[10, 8, 6]);
+        let result = Round::new(&rules, &strategy, &FixedBet(10.0),
+                                &mut shoe, 1, false,
+                                &start_cards, &start_cards)
+            .with_script(0, &Script::Surrender)
+            .run();
+
+        assert_eq!(result.err(), Some(ScriptError::SurrenderUnavailable {
+            hand: Hand::from(&[10, 6][..]).to_string(),
+            dealer: Card(8),
+        }));
+    }
+
+    #[test]
+    fn it_ignores_the_decisions_left_for_a_busted_hand() {
+        let rules = make_rules(AHC|S17);
+        let start_cards = VecDeque::new();
+        let strategy = QueuedStrategy::new(&[], false, false);
+        let script = Script::from_str("++").unwrap();
+        let mut shoe = QueuedShoe::from_ints(&[10, 8, 6, 7, 9, 3]);
+        let (_, result) = Round::new(&rules, &strategy, &FixedBet(10.0),
+                                     &mut shoe, 1, false,
+                                     &start_cards, &start_cards)
+            .with_script(0, &script)
+            .run().unwrap();
+
+        assert!(shoe.is_empty());
+        assert_eq!(result.player_results[0], -10.0);
+    }
+
+    #[test]
+    fn it_scripts_the_player_only() {
+        let mut rules = make_rules(AHC|S17);
+        rules.surrender = SurrenderPolicy::LateSurrender;
+        let start_cards = VecDeque::new();
+        let strategy = QueuedStrategy::new(&[Stand], false, false);
+        let mut shoe = QueuedShoe::from_ints(&[10, 10, 9, 8, 6, 8]);
+        let (round, result) = Round::new(&rules, &strategy, &FixedBet(10.0),
+                                         &mut shoe, 2, false,
+                                         &start_cards, &start_cards)
+            .with_script(1, &Script::Surrender)
+            .run().unwrap();
+
+        assert!(!round.hands[0].is_surrendered());
+        assert!(round.hands[1].is_surrendered());
+        assert_eq!(result.player_results[..2], [10.0, -5.0]);
+        assert!(strategy.is_empty());
+    }
 }
@@ -1,11 +1,55 @@
 use std::collections::VecDeque;
+use rand::RngCore;
 
 use crate::game_rules::GameRules;
-use crate::strategy::{Strategy, Decision};
+use crate::strategy::Strategy;
 use crate::shoe::CardShoe;
-use crate::round::Round;
-use crate::betting::BettingStrategy;
+use crate::round::{Round, Player};
+use crate::betting::{BettingStrategy, Wonging};
 use crate::card::Card;
+use crate::side_bet::SideBetWager;
+use crate::script::Script;
+
+/// What the players know of the dealer's hand and of the shoe beyond the
+/// upcard, each an information source.
+#[derive(Debug, Default, PartialEq, Copy, Clone)]
+pub struct Information {
+    /// Whether the players see the dealer's holecard (holecarding)
+    pub holecard:   bool,
+    /// The accuracy of a tell on whether the dealer's holecard is a ten, 1.0
+    /// for a warp
+    pub tell:       Option<f32>,
+    /// Whether the players know the next card off the shoe (first-basing)
+    pub next_card:  bool,
+}
+
+impl Information {
+    /// Returns each information source alone, along with its name.
+    pub fn sources(&self) -> Vec<(&'static str, Information)> {
+        let mut sources = Vec::new();
+
+        if self.holecard {
+            sources.push(("holecard", Information {
+                holecard: true,
+                ..Default::default()
+            }));
+        }
+        if let Some(accuracy) = self.tell {
+            sources.push(("tell", Information {
+                tell: Some(accuracy),
+                ..Default::default()
+            }));
+        }
+        if self.next_card {
+            sources.push(("next_card", Information {
+                next_card: true,
+                ..Default::default()
+            }));
+        }
+
+        sources
+    }
+}
 
 pub struct RoundFactory<'a>
 {
@@ -13,22 +57,24 @@ pub struct RoundFactory<'a>
     strategy: &'a (dyn Strategy + Sync),
     betting_strategy: &'a (dyn BettingStrategy + Sync),
     num_players: u8,
-    holecarding: bool,
-    override_action: Option<Decision>,
-    surrender_override: Option<bool>,
+    information: Information,
     start_cards: VecDeque<Card>,
     dealer_cards: VecDeque<Card>,
+    side_bets: &'a [SideBetWager],
+    seat: u8,
+    players: Vec<Player<'a>>,
+    wonging: Option<Wonging>,
+    script: Option<Script>,
 }
 
 impl<'a> RoundFactory<'a>
 {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(rules: &'a GameRules,
                strategy: &'a (dyn Strategy + Sync),
                betting_strategy: &'a (dyn BettingStrategy + Sync),
                num_players: u8,
                holecarding: bool,
-               override_action: Option<Decision>,
-               surrender_override: Option<bool>,
                start_cards: VecDeque<Card>,
                dealer_cards: VecDeque<Card>) -> RoundFactory<'a> {
         RoundFactory {
@@ -36,26 +82,122 @@ impl<'a> RoundFactory<'a>
             strategy,
             betting_strategy,
             num_players,
-            holecarding,
-            override_action,
-            surrender_override,
+            information: Information {
+                holecard: holecarding,
+                ..Default::default()
+            },
             start_cards,
             dealer_cards,
+            side_bets: &[],
+            seat: 0,
+            players: Vec::new(),
+            wonging: None,
+            script: None,
         }
     }
 
-    pub fn make(&self, shoe: &'a mut dyn CardShoe) -> Round {
-        Round::new(
+    /// Sets the side bets offered at the table.
+    pub fn with_side_bets(mut self, side_bets: &'a [SideBetWager]) -> Self {
+        self.side_bets = side_bets;
+        self
+    }
+
+    pub fn side_bets(&self) -> &[SideBetWager] {
+        self.side_bets
+    }
+
+    /// Sets the playing and betting strategies of each seat, one per player;
+    /// by default every seat plays the factory's strategies.
+    pub fn with_players(mut self, players: Vec<Player<'a>>) -> Self {
+        assert_eq!(players.len(), self.num_players as usize);
+        self.players = players;
+        self
+    }
+
+    /// Sets which seat is the player's, from 0 (first base) to
+    /// `num_players - 1`; the other seats are played by other players.
+    pub fn with_seat(mut self, seat: u8) -> Self {
+        assert!(seat < self.num_players);
+        self.seat = seat;
+        self
+    }
+
+    /// Makes the player back-count, watching rounds without playing them.
+    pub fn with_wonging(mut self, wonging: Wonging) -> Self {
+        self.wonging = Some(wonging);
+        self
+    }
+
+    pub fn wonging(&self) -> Option<Wonging> {
+        self.wonging
+    }
+
+    /// Makes the player play a script instead of their strategy.
+    pub fn with_script(mut self, script: Script) -> Self {
+        self.script = Some(script);
+        self
+    }
+
+    /// Gives the players a tell on whether the dealer's holecard is a ten,
+    /// right with probability `accuracy` (1.0 for a warp).
+    pub fn with_tell(mut self, accuracy: f32) -> Self {
+        assert!(accuracy > 0.5 && accuracy <= 1.0);
+        self.information.tell = Some(accuracy);
+        self
+    }
+
+    /// Lets the players know the next card off the shoe before each of their
+    /// decisions.
+    pub fn with_next_card(mut self) -> Self {
+        self.information.next_card = true;
+        self
+    }
+
+    pub fn information(&self) -> Information {
+        self.information
+    }
+
+    /// Sets what the players know beyond the upcard, replacing the
+    /// holecarding, tell, and next card settings.
+    pub fn set_information(&mut self, information: Information) {
+        self.information = information;
+    }
+
+    pub fn seat(&self) -> u8 {
+        self.seat
+    }
+
+    pub fn num_players(&self) -> u8 {
+        self.num_players
+    }
+
+    /// Makes a round dealt from `shoe`; the tells are misread with `rng`.
+    pub fn make(&self, shoe: &'a mut dyn CardShoe, rng: &'a mut dyn RngCore)
+        -> Round<'_> {
+        let mut round = Round::new(
             self.rules,
             self.strategy,
             self.betting_strategy,
             shoe,
             self.num_players,
-            self.holecarding,
-            self.override_action,
-            self.surrender_override,
+            self.information.holecard,
             &self.start_cards,
             &self.dealer_cards,
-        )
+        ).with_side_bets(self.side_bets);
+
+        if !self.players.is_empty() {
+            round = round.with_players(&self.players);
+        }
+        if let Some(script) = &self.script {
+            round = round.with_script(self.seat, script);
+        }
+        if let Some(accuracy) = self.information.tell {
+            round = round.with_tell(accuracy, rng);
+        }
+        if self.information.next_card {
+            round = round.with_next_card();
+        }
+
+        round
     }
 }
@@ -118,6 +118,7 @@ impl AddAssign for RunningStats {
 }
 
 #[cfg(test)]
+#[allow(clippy::excessive_precision)]
 mod tests {
     use crate::running_stats::RunningStats;
     use crate::test_utils::assert_f64_eq;
@@ -0,0 +1,288 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+use serde::de::Error;
+
+use crate::card::Card;
+use crate::game_rules::GameRules;
+use crate::game_rules::SurrenderPolicy::NoSurrender;
+use crate::strategy::Decision;
+
+/// A scenario script, fixing the decisions of the player's hands instead of
+/// their strategy, to compare complete lines of play.
+///
+/// A script is either "#", surrendering the hand, or a sequence of decisions
+/// for each hand, separated by commas in the order the hands are played:
+/// "+" hit, "=" stand, "D" double down, and "V" split; e.g. "VD,=" splits,
+/// doubles down the first hand, and stands the second one. Once the decisions
+/// of a hand are taken, its strategy plays it on, and plays the hands missing
+/// from the script; the decisions left for a hand which was closed early (e.g.
+/// busted) are ignored. A hand has no decision after standing, nor after its
+/// last double down, and only doubles down again after doubling down.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Script {
+    Surrender,
+    Play(Vec<Vec<Decision>>),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum ScriptError {
+    /// A character which is not an action
+    UnknownAction(char),
+    /// A hand without any decision
+    EmptyHand,
+    /// Surrendering can only be the whole script
+    MisplacedSurrender,
+    /// More hands than one plus the number of splits
+    HandCount { hands: usize, splits: usize },
+    /// A decision after standing or doubling down, which is never played
+    ClosedHand,
+    /// The script surrenders but the rules do not offer surrender
+    SurrenderForbidden,
+    /// The rules do not offer surrender on the hand when its turn came
+    SurrenderUnavailable { hand: String, dealer: Card },
+    /// A decision forbidden on the hand when its turn came
+    Forbidden { decision: Decision, hand: String, dealer: Card },
+}
+
+impl Display for ScriptError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScriptError::UnknownAction(c) =>
+                write!(f, "unknown action '{}'", c),
+            ScriptError::EmptyHand =>
+                write!(f, "a hand has no decision"),
+            ScriptError::MisplacedSurrender =>
+                write!(f, "surrender must be the only action"),
+            ScriptError::HandCount { hands, splits } =>
+                write!(f, "{} hands for {} split(s), at most {}", hands,
+                       splits, splits + 1),
+            ScriptError::ClosedHand =>
+                write!(f, "a hand has a decision after it is closed"),
+            ScriptError::SurrenderForbidden =>
+                write!(f, "the rules do not offer surrender"),
+            ScriptError::SurrenderUnavailable { hand, dealer } =>
+                write!(f, "unable to surrender {} vs {}", hand, dealer),
+            ScriptError::Forbidden { decision, hand, dealer } =>
+                write!(f, "unable to {} {} vs {}", match decision {
+                    Decision::Hit => "hit",
+                    Decision::Stand => "stand",
+                    Decision::Double => "double down",
+                    Decision::Split => "split",
+                }, hand, dealer),
+        }
+    }
+}
+
+impl std::error::Error for ScriptError {}
+
+impl Script {
+    /// Checks the script against the game rules, before playing it.
+    pub fn check(&self, rules: &GameRules) -> Result<(), ScriptError> {
+        if *self == Script::Surrender && rules.surrender == NoSurrender {
+            return Err(ScriptError::SurrenderForbidden);
+        }
+
+        if let Script::Play(hands) = self {
+            // Each double down closes the hand once the redoubles are spent
+            let max_doubles = rules.max_redoubles as usize + 1;
+            for hand in hands {
+                let doubles = hand.iter()
+                    .filter(|&&decision| decision == Decision::Double)
+                    .count();
+                if doubles > max_doubles
+                   || (doubles == max_doubles
+                       && hand.last() != Some(&Decision::Double)) {
+                    return Err(ScriptError::ClosedHand);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the decision at `step` of the `hand`-th hand played, if any.
+    pub fn decision(&self, hand: usize, step: usize) -> Option<Decision> {
+        match self {
+            Script::Surrender => None,
+            Script::Play(hands) => hands.get(hand)?.get(step).copied(),
+        }
+    }
+}
+
+impl Display for Script {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let hands = match self {
+            Script::Surrender => return write!(f, "#"),
+            Script::Play(hands) => hands,
+        };
+
+        for (i, hand) in hands.iter().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
+            for decision in hand.iter() {
+                write!(f, "{}", match decision {
+                    Decision::Hit => '+',
+                    Decision::Stand => '=',
+                    Decision::Double => 'D',
+                    Decision::Split => 'V',
+                })?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Scripts are serialized with the same syntax as `-a`.
+impl Serialize for Script {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Script {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where D: Deserializer<'de> {
+        let s = String::deserialize(deserializer)?;
+        Script::from_str(&s)
+            .map_err(|e| D::Error::custom(format!("{}: {}", s, e)))
+    }
+}
+
+impl FromStr for Script {
+    type Err = ScriptError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "#" {
+            return Ok(Script::Surrender);
+        }
+
+        let hands = s.split(',')
+            .map(|hand| {
+                if hand.is_empty() {
+                    return Err(ScriptError::EmptyHand);
+                }
+
+                hand.chars()
+                    .map(|c| match c {
+                        '+' => Ok(Decision::Hit),
+                        '=' => Ok(Decision::Stand),
+                        'D' => Ok(Decision::Double),
+                        'V' => Ok(Decision::Split),
+                        '#' => Err(ScriptError::MisplacedSurrender),
+                        _ => Err(ScriptError::UnknownAction(c)),
+                    })
+                    .collect()
+            })
+            .collect::<Result<Vec<Vec<_>>, _>>()?;
+
+        // Standing closes the hand, and so does doubling down unless it is
+        // doubled down again
+        for hand in hands.iter() {
+            let mut doubled = false;
+            for (i, &decision) in hand.iter().enumerate() {
+                match decision {
+                    Decision::Stand if i + 1 < hand.len() =>
+                        return Err(ScriptError::ClosedHand),
+                    Decision::Hit | Decision::Split if doubled =>
+                        return Err(ScriptError::ClosedHand),
+                    Decision::Double => doubled = true,
+                    _ => {},
+                }
+            }
+        }
+
+        let splits = hands.iter()
+            .flatten()
+            .filter(|&&decision| decision == Decision::Split)
+            .count();
+        if hands.len() > splits + 1 {
+            return Err(ScriptError::HandCount { hands: hands.len(), splits });
+        }
+
+        Ok(Script::Play(hands))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::script::{Script, ScriptError};
+    use crate::strategy::Decision::*;
+    use crate::game_rules::GameRules;
+    use crate::game_rules::SurrenderPolicy::LateSurrender;
+    use std::str::FromStr;
+
+    #[test]
+    fn it_parses_scripts() {
+        assert_eq!(Script::from_str("#"), Ok(Script::Surrender));
+        assert_eq!(Script::from_str("D"), Ok(Script::Play(vec![vec![Double]])));
+        assert_eq!(Script::from_str("VD,="),
+                   Ok(Script::Play(vec![vec![Split, Double], vec![Stand]])));
+        assert_eq!(Script::from_str("V"), Ok(Script::Play(vec![vec![Split]])));
+        assert_eq!(Script::from_str("VV+=,D,="),
+                   Ok(Script::Play(vec![vec![Split, Split, Hit, Stand],
+                                        vec![Double], vec![Stand]])));
+    }
+
+    #[test]
+    fn it_rejects_invalid_scripts() {
+        assert_eq!(Script::from_str("VX,="),
+                   Err(ScriptError::UnknownAction('X')));
+        assert_eq!(Script::from_str("V,"), Err(ScriptError::EmptyHand));
+        assert_eq!(Script::from_str("+#"),
+                   Err(ScriptError::MisplacedSurrender));
+        assert_eq!(Script::from_str("=,="),
+                   Err(ScriptError::HandCount { hands: 2, splits: 0 }));
+        assert_eq!(Script::from_str("V=,=,D"),
+                   Err(ScriptError::HandCount { hands: 3, splits: 1 }));
+        assert_eq!(Script::from_str("=+"), Err(ScriptError::ClosedHand));
+        assert_eq!(Script::from_str("D+"), Err(ScriptError::ClosedHand));
+        assert_eq!(Script::from_str("V,D+"), Err(ScriptError::ClosedHand));
+        assert_eq!(Script::from_str("+DV"), Err(ScriptError::ClosedHand));
+        assert_eq!(Script::from_str("DD="),
+                   Ok(Script::Play(vec![vec![Double, Double, Stand]])));
+    }
+
+    #[test]
+    fn it_checks_surrender_is_offered() {
+        let mut rules = GameRules::default();
+        assert_eq!(Script::Surrender.check(&rules),
+                   Err(ScriptError::SurrenderForbidden));
+        assert_eq!(Script::from_str("D").unwrap().check(&rules), Ok(()));
+
+        rules.surrender = LateSurrender;
+        assert_eq!(Script::Surrender.check(&rules), Ok(()));
+    }
+
+    #[test]
+    fn it_checks_the_redoubles() {
+        let mut rules = GameRules::default();
+        assert_eq!(Script::from_str("D").unwrap().check(&rules), Ok(()));
+        assert_eq!(Script::from_str("V,D").unwrap().check(&rules), Ok(()));
+        assert_eq!(Script::from_str("D=").unwrap().check(&rules),
+                   Err(ScriptError::ClosedHand));
+        assert_eq!(Script::from_str("DD").unwrap().check(&rules),
+                   Err(ScriptError::ClosedHand));
+
+        rules.max_redoubles = 1;
+        assert_eq!(Script::from_str("D=").unwrap().check(&rules), Ok(()));
+        assert_eq!(Script::from_str("DD").unwrap().check(&rules), Ok(()));
+        assert_eq!(Script::from_str("DD=").unwrap().check(&rules),
+                   Err(ScriptError::ClosedHand));
+    }
+
+    #[test]
+    fn it_gives_the_decisions_of_each_hand() {
+        let script = Script::from_str("VD,=").unwrap();
+
+        assert_eq!(script.decision(0, 0), Some(Split));
+        assert_eq!(script.decision(0, 1), Some(Double));
+        assert_eq!(script.decision(0, 2), None);
+        assert_eq!(script.decision(1, 0), Some(Stand));
+        assert_eq!(script.decision(2, 0), None);
+        assert_eq!(Script::Surrender.decision(0, 0), None);
+    }
+}
@@ -75,6 +75,10 @@ impl CardShoe for FileShoe {
     fn adjust_running_count(&mut self, _rel_rc: i32) {
         unimplemented!("Cannot adjust running count on a FileShoe")
     }
+
+    fn peek(&self) -> Option<Card> {
+        if self.ended { None } else { Some(self.cards[self.curr_pos]) }
+    }
 }
 
 impl fmt::Display for FileShoe {
@@ -1,5 +1,5 @@
 use std::fmt::Display;
-use crate::card::Card;
+use crate::card::{Card, SuitedCard};
 
 pub mod standard_shoe;
 pub mod file_shoe;
@@ -25,6 +25,35 @@ pub trait CardShoe: Display {
         })
     }
 
+    /// Picks a card along with its rank and suit; shoes which do not know
+    /// them (see `is_suited()`) convert the card from its value.
+    fn try_pick_suited(&mut self) -> Option<SuitedCard> {
+        self.try_pick().map(SuitedCard::from)
+    }
+
+    fn try_pick_first_suited(&mut self, card: Card) -> Option<SuitedCard> {
+        self.try_pick_first(card).map(SuitedCard::from)
+    }
+
+    fn pick_suited(&mut self) -> SuitedCard {
+        self.try_pick_suited().unwrap_or_else(|| {
+            self.reshuffle();
+            self.try_pick_suited()
+                .expect("Couldn't pick any card after reshuffling")
+        })
+    }
+
+    fn pick_first_suited(&mut self, card: Card) -> SuitedCard {
+        self.try_pick_first_suited(card).unwrap_or_else(|| {
+            self.reshuffle();
+            self.try_pick_first_suited(card)
+                .expect("Couldn't find the card after reshuffling")
+        })
+    }
+
+    /// Whether the shoe knows the ranks and suits of its cards.
+    fn is_suited(&self) -> bool { false }
+
     fn reshuffle(&mut self);
 
     fn force_true_count(&mut self, true_count: f32);
@@ -36,4 +65,10 @@ pub trait CardShoe: Display {
     fn running_count(&self) -> i32 { 0 }
 
     fn true_count(&self) -> f32 { 0.0 }
+
+    /// The number of cards left in the shoe, if known.
+    fn cards_left(&self) -> Option<usize> { None }
+
+    /// The next card off the shoe, if known, without picking it.
+    fn peek(&self) -> Option<Card> { None }
 }
@@ -1,26 +1,37 @@
 use std::fmt;
 use std::collections::VecDeque;
 
-use crate::card::Card;
+use crate::card::{Card, SuitedCard};
 use crate::shoe::CardShoe;
 
 pub struct QueuedShoe {
-    cards: VecDeque<Card>,
+    cards: VecDeque<SuitedCard>,
+    /// Whether the cards were given with their suits
+    suited: bool,
 }
 
 impl QueuedShoe {
     pub fn new(cards: &[Card]) -> QueuedShoe {
         QueuedShoe {
-            cards: cards.into_iter().map(|c| *c).collect(),
+            cards: cards.iter().map(|&c| SuitedCard::from(c)).collect(),
+            suited: false,
         }
     }
 
     pub fn from_ints(cards: &[u8]) -> QueuedShoe {
         QueuedShoe {
-            cards: cards.into_iter().map(|&c| {
+            cards: cards.iter().map(|&c| {
                 assert!(c > 0 && c < 11);
-                Card(c)
-            }).collect()
+                SuitedCard::from(Card(c))
+            }).collect(),
+            suited: false,
+        }
+    }
+
+    pub fn from_suited(cards: &[SuitedCard]) -> QueuedShoe {
+        QueuedShoe {
+            cards: cards.iter().copied().collect(),
+            suited: true,
         }
     }
 
@@ -46,12 +57,11 @@ impl fmt::Display for QueuedShoe {
 
 impl CardShoe for QueuedShoe {
     fn try_pick(&mut self) -> Option<Card> {
-        self.cards.pop_front()
+        self.try_pick_suited().map(SuitedCard::card)
     }
 
     fn try_pick_first(&mut self, card: Card) -> Option<Card> {
-        self.cards.iter().position(|c| *c == card)
-            .map(|i| self.cards.remove(i).unwrap())
+        self.try_pick_first_suited(card).map(SuitedCard::card)
     }
 
     fn pick(&mut self) -> Card {
@@ -62,6 +72,25 @@ impl CardShoe for QueuedShoe {
         self.try_pick_first(card).expect("Couldn't find card")
     }
 
+    fn try_pick_suited(&mut self) -> Option<SuitedCard> {
+        self.cards.pop_front()
+    }
+
+    fn try_pick_first_suited(&mut self, card: Card) -> Option<SuitedCard> {
+        self.cards.iter().position(|c| c.card() == card)
+            .map(|i| self.cards.remove(i).unwrap())
+    }
+
+    fn is_suited(&self) -> bool { self.suited }
+
+    fn pick_suited(&mut self) -> SuitedCard {
+        self.try_pick_suited().expect("No cards left")
+    }
+
+    fn pick_first_suited(&mut self, card: Card) -> SuitedCard {
+        self.try_pick_first_suited(card).expect("Couldn't find card")
+    }
+
     fn reshuffle(&mut self) {
         unimplemented!("Cannot reshuffle a QueuedShoe");
     }
@@ -73,6 +102,10 @@ impl CardShoe for QueuedShoe {
     fn adjust_running_count(&mut self, _rel_rc: i32) {
         unimplemented!("Cannot adjust running count on a QueuedShoe")
     }
+
+    fn peek(&self) -> Option<Card> {
+        self.cards.front().map(|card| card.card())
+    }
 }
 
 #[cfg(test)]
@@ -85,11 +118,13 @@ mod tests {
     fn it_picks_a_card_in_queued_shoe() {
         let mut shoe = QueuedShoe::from_ints(&[5, 6, 8][..]);
 
+        assert_eq!(shoe.peek(), Some(Card(5)));
         assert_eq!(shoe.pick(), Card(5));
         assert_eq!(shoe.pick(), Card(6));
         assert!(!shoe.is_empty());
         assert_eq!(shoe.pick(), Card(8));
         assert!(shoe.is_empty());
+        assert_eq!(shoe.peek(), None);
     }
 
     #[test]
@@ -3,12 +3,12 @@ use rand::rngs::SmallRng;
 use rand::{SeedableRng, Rng};
 use rand::seq::SliceRandom;
 
-use crate::card::Card;
+use crate::card::{Card, Suit, SuitedCard};
 use crate::shoe::CardShoe;
 
 #[derive(Debug)]
 pub struct StandardShoe {
-    cards: Vec<Card>,
+    cards: Vec<SuitedCard>,
     decks: u32,
     min_cards: usize,
     needs_reshuffle: bool,
@@ -42,21 +42,28 @@ impl StandardShoe {
         shoe
     }
 
+    /// Creates a shuffled shoe whose shuffles are all determined by a seed,
+    /// for simulations to be reproducible.
+    pub fn seeded(decks: u32, pen_cards: u32, seed: u64) -> StandardShoe {
+        let mut shoe = Self::non_shuffled(decks, pen_cards);
+        shoe.rng = SmallRng::seed_from_u64(seed);
+        shoe.reshuffle();
+
+        shoe
+    }
+
     fn fill_cards(&mut self) {
         for _ in 0..self.decks {
-            for _ in 0..4 {
-                for c in 1..=9 {
-                    self.cards.push(Card(c));
-                }
-                for _ in 0..4 {
-                    self.cards.push(Card(10));
+            for &suit in Suit::ALL.iter() {
+                for rank in 1..=13 {
+                    self.cards.push(SuitedCard::new(rank, suit));
                 }
             }
         }
     }
 
-    fn card_removed(&mut self, card: Card) {
-        match card.0 {
+    fn card_removed(&mut self, card: SuitedCard) {
+        match card.card().0 {
             2..=6 => self.running_count += 1,
             1 | 10 => self.running_count -= 1,
             _ => (),
@@ -67,22 +74,22 @@ impl StandardShoe {
         }
     }
 
-    fn remove_high_card(&mut self) -> Card {
+    fn remove_high_card(&mut self) -> SuitedCard {
         let (card, card_alt) = if self.rng.gen_range(0..4) == 0 {
             (Card(1), Card(10))
         } else {
             (Card(10), Card(1))
         };
-        self.try_pick_first(card).or_else(|| {
-            self.try_pick_first(card_alt)
+        self.try_pick_first_suited(card).or_else(|| {
+            self.try_pick_first_suited(card_alt)
         }).expect("Not enough high cards to reach desired true count")
     }
 
-    fn remove_low_card(&mut self) -> Card {
+    fn remove_low_card(&mut self) -> SuitedCard {
         let card_orig = self.rng.gen_range(2..7);
         let mut card = card_orig;
         loop {
-            if let Some(c) = self.try_pick_first(Card(card)) {
+            if let Some(c) = self.try_pick_first_suited(Card(card)) {
                 break c;
             }
 
@@ -101,6 +108,15 @@ impl StandardShoe {
 impl CardShoe for StandardShoe {
     #[inline]
     fn try_pick(&mut self) -> Option<Card> {
+        self.try_pick_suited().map(SuitedCard::card)
+    }
+
+    fn try_pick_first(&mut self, card: Card) -> Option<Card> {
+        self.try_pick_first_suited(card).map(SuitedCard::card)
+    }
+
+    #[inline]
+    fn try_pick_suited(&mut self) -> Option<SuitedCard> {
         if let Some(card) = self.cards.pop() {
             self.card_removed(card);
             Some(card)
@@ -109,12 +125,12 @@ impl CardShoe for StandardShoe {
         }
     }
 
-    fn try_pick_first(&mut self, card: Card) -> Option<Card> {
+    fn try_pick_first_suited(&mut self, card: Card) -> Option<SuitedCard> {
         // TODO: Why does using reverse iterator yield incorrect results?
-        let pos = self.cards.iter().position(|c| *c == card);
+        let pos = self.cards.iter().position(|c| c.card() == card);
 
         if let Some(pos) = pos {
-            self.cards.remove(pos);
+            let card = self.cards.remove(pos);
             self.card_removed(card);
             Some(card)
         } else {
@@ -122,6 +138,8 @@ impl CardShoe for StandardShoe {
         }
     }
 
+    fn is_suited(&self) -> bool { true }
+
     fn reshuffle(&mut self)
     {
         self.cards.clear();
@@ -192,6 +210,14 @@ impl CardShoe for StandardShoe {
     fn true_count(&self) -> f32 {
         self.running_count as f32 / (self.cards.len() as f32 / 52.0)
     }
+
+    fn cards_left(&self) -> Option<usize> {
+        Some(self.cards.len())
+    }
+
+    fn peek(&self) -> Option<Card> {
+        self.cards.last().map(|card| card.card())
+    }
 }
 
 impl fmt::Display for StandardShoe {
@@ -223,11 +249,27 @@ mod tests {
         assert_eq!(shoe.cards.len(), 52);
 
         for card in 1..=9 {
-            assert_eq!(shoe.cards.iter().filter(|&&c| c == Card(card))
+            assert_eq!(shoe.cards.iter().filter(|&&c| c.card() == Card(card))
                            .count(), 4);
         }
 
-        assert_eq!(shoe.cards.iter().filter(|&&c| c == Card(10)).count(), 16);
+        assert_eq!(shoe.cards.iter().filter(|&&c| c.card() == Card(10)).count(), 16);
+    }
+
+    #[test]
+    fn it_shuffles_the_same_from_the_same_seed() {
+        let mut a = StandardShoe::seeded(2, 80, 42);
+        let mut b = StandardShoe::seeded(2, 80, 42);
+        let mut c = StandardShoe::seeded(2, 80, 43);
+
+        assert_eq!(a.cards, b.cards);
+        assert_ne!(a.cards, c.cards);
+
+        a.reshuffle();
+        b.reshuffle();
+        c.reshuffle();
+        assert_eq!(a.cards, b.cards);
+        assert_ne!(a.cards, c.cards);
     }
 
     #[test]
@@ -242,11 +284,11 @@ mod tests {
 
         for card in 1..=9 {
             let expected_count = if card == 5 { 0 } else { 4 };
-            assert_eq!(shoe.cards.iter().filter(|&&c| c == Card(card))
+            assert_eq!(shoe.cards.iter().filter(|&&c| c.card() == Card(card))
                            .count(), expected_count);
         }
 
-        assert_eq!(shoe.cards.iter().filter(|&&c| c == Card(10)).count(), 16);
+        assert_eq!(shoe.cards.iter().filter(|&&c| c.card() == Card(10)).count(), 16);
     }
 
     #[test]
@@ -342,7 +384,7 @@ mod tests {
         assert_eq!(shoe.running_count(), -20);
         assert_eq!(shoe.true_count(), -32.5);
         assert_eq!(shoe.cards.iter()
-                       .filter(|&&c| c == Card(10) || c == Card(1))
+                       .filter(|&&c| c.card() == Card(10) || c.card() == Card(1))
                        .count(), 0);
     }
 
@@ -362,7 +404,7 @@ mod tests {
         assert_eq!(shoe.running_count(), 20);
         assert_eq!(shoe.true_count(), 32.5);
         assert_eq!(shoe.cards.iter()
-                       .filter(|&&c| c.0 >= 2 && c.0 <= 6)
+                       .filter(|&&c| c.rank >= 2 && c.rank <= 6)
                        .count(), 0);
     }
 
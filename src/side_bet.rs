@@ -0,0 +1,423 @@
+use std::fmt::Debug;
+use std::convert::TryFrom;
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+use serde::de::Error;
+
+use crate::card::{SuitedCard, Suit};
+use crate::hand::Hand;
+use crate::running_stats::RunningStats;
+
+/// The cards a side bet is settled against, once the round is over.
+pub struct SideBetCards<'a> {
+    /// The player's first two cards
+    pub player: [SuitedCard; 2],
+
+    /// The dealer's upcard
+    pub dealer_upcard: SuitedCard,
+
+    /// The dealer's final hand
+    pub dealer: &'a Hand,
+}
+
+/// A side bet, paid from a paytable whose entries are the payouts for a bet
+/// of 1.0 (e.g. 9.0 for 9:1), in the order documented by each side bet.
+pub trait SideBet: Debug + Send + Sync {
+    /// The name of the side bet, as shown in the results
+    fn name(&self) -> &'static str;
+
+    fn paytable(&self) -> &[f64];
+
+    /// Settles a bet of 1.0, returns what it pays when won or -1.0 when lost.
+    fn settle(&self, cards: &SideBetCards) -> f64;
+}
+
+/// A side bet offered at the table along with how the player wagers on it.
+#[derive(Debug)]
+pub struct SideBetWager {
+    pub side_bet: Box<dyn SideBet>,
+
+    /// The amount wagered on the side bet each round it is placed
+    pub bet: f64,
+
+    /// When set, the side bet is only placed from this true count on, which
+    /// is how counting side bets are played
+    pub min_true_count: Option<f32>,
+}
+
+impl SideBetWager {
+    /// Parses a side bet wager of `bet` from the syntax of `--side-bet`:
+    /// "NAME[:PAYS][@TC]".
+    pub fn parse(spec: &str, bet: f64) -> Result<SideBetWager, String> {
+        let (spec, min_true_count) = match spec.split_once('@') {
+            Some((spec, tc)) => (spec, Some(tc.parse()
+                .map_err(|_| format!("invalid true count \"{}\"", tc))?)),
+            None => (spec, None),
+        };
+
+        let (name, pays) = match spec.split_once(':') {
+            Some((name, pays)) => {
+                let pays = pays.split(',')
+                    .map(|p| match p.parse::<f64>() {
+                        Ok(pay) if pay >= 0.0 => Ok(pay),
+                        _ => Err(format!("invalid payout \"{}\"", p)),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                (name, Some(pays))
+            },
+            None => (spec, None),
+        };
+
+        let side_bet: Box<dyn SideBet> = match name {
+            "21+3" => Box::new(match pays {
+                Some(pays) => TwentyOnePlusThree::new(parse_paytable(pays)?),
+                None => TwentyOnePlusThree::default(),
+            }),
+            "perfect-pairs" => Box::new(match pays {
+                Some(pays) => PerfectPairs::new(parse_paytable(pays)?),
+                None => PerfectPairs::default(),
+            }),
+            "lucky-ladies" => Box::new(match pays {
+                Some(pays) => LuckyLadies::new(parse_paytable(pays)?),
+                None => LuckyLadies::default(),
+            }),
+            "buster" => Box::new(match pays {
+                Some(pays) => BusterBlackjack::new(parse_paytable(pays)?),
+                None => BusterBlackjack::default(),
+            }),
+            _ => return Err(format!("unknown side bet \"{}\"", name)),
+        };
+
+        Ok(SideBetWager {
+            side_bet,
+            bet,
+            min_true_count,
+        })
+    }
+
+    /// Returns the side bet in the syntax of `--side-bet`, its paytable
+    /// included.
+    pub fn spec(&self) -> String {
+        let pays: Vec<String> = self.side_bet.paytable().iter()
+            .map(|pays| pays.to_string())
+            .collect();
+        let mut spec = format!("{}:{}", self.side_bet.name(), pays.join(","));
+        if let Some(tc) = self.min_true_count {
+            spec += &format!("@{}", tc);
+        }
+        spec
+    }
+
+    /// Returns whether the side bet is placed at a given true count.
+    pub fn is_placed(&self, true_count: f32) -> bool {
+        self.min_true_count.map_or(true, |tc| true_count >= tc)
+    }
+}
+
+/// Side bet wagers are serialized as their `--side-bet` syntax and the amount
+/// wagered.
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WagerConfig {
+    side_bet:   String,
+    bet:        f64,
+}
+
+impl Serialize for SideBetWager {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer {
+        WagerConfig { side_bet: self.spec(), bet: self.bet }
+            .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for SideBetWager {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where D: Deserializer<'de> {
+        let config = WagerConfig::deserialize(deserializer)?;
+        if config.bet <= 0.0 {
+            return Err(D::Error::custom(format!("{}: invalid bet",
+                                                config.side_bet)));
+        }
+        SideBetWager::parse(&config.side_bet, config.bet)
+            .map_err(|e| D::Error::custom(format!("{}: {}", config.side_bet,
+                                                  e)))
+    }
+}
+
+fn parse_paytable<const N: usize>(pays: Vec<f64>) -> Result<[f64; N], String> {
+    <[f64; N]>::try_from(pays)
+        .map_err(|_| format!("the paytable must have {} payouts", N))
+}
+
+/// The results of a side bet over a simulation, for the amount wagered.
+#[derive(Debug)]
+pub struct SideBetStats {
+    pub name: &'static str,
+    pub results: RunningStats,
+}
+
+/// 21+3: a three-card poker hand made of the player's first two cards and the
+/// dealer's upcard. Paytable: flush, straight, three of a kind, straight
+/// flush, suited three of a kind.
+#[derive(Debug)]
+pub struct TwentyOnePlusThree {
+    pays: [f64; 5],
+}
+
+impl TwentyOnePlusThree {
+    pub fn new(pays: [f64; 5]) -> TwentyOnePlusThree {
+        TwentyOnePlusThree { pays }
+    }
+}
+
+impl Default for TwentyOnePlusThree {
+    fn default() -> Self {
+        Self::new([5.0, 10.0, 30.0, 40.0, 100.0])
+    }
+}
+
+impl SideBet for TwentyOnePlusThree {
+    fn name(&self) -> &'static str { "21+3" }
+
+    fn paytable(&self) -> &[f64] { &self.pays }
+
+    fn settle(&self, cards: &SideBetCards) -> f64 {
+        let hand = [cards.player[0], cards.player[1], cards.dealer_upcard];
+        let flush = hand.iter().all(|c| c.suit == hand[0].suit);
+        let trips = hand.iter().all(|c| c.rank == hand[0].rank);
+
+        let mut ranks = [hand[0].rank, hand[1].rank, hand[2].rank];
+        ranks.sort_unstable();
+        let straight = (ranks[1] == ranks[0] + 1 && ranks[2] == ranks[1] + 1)
+            || ranks == [1, 12, 13];
+
+        match (flush, straight, trips) {
+            (true, _, true) => self.pays[4],
+            (true, true, _) => self.pays[3],
+            (_, _, true) => self.pays[2],
+            (_, true, _) => self.pays[1],
+            (true, _, _) => self.pays[0],
+            _ => -1.0,
+        }
+    }
+}
+
+/// Perfect Pairs: the player's first two cards are a pair. Paytable: mixed
+/// pair (different colors), colored pair (same color), perfect pair (same
+/// suit).
+#[derive(Debug)]
+pub struct PerfectPairs {
+    pays: [f64; 3],
+}
+
+impl PerfectPairs {
+    pub fn new(pays: [f64; 3]) -> PerfectPairs {
+        PerfectPairs { pays }
+    }
+}
+
+impl Default for PerfectPairs {
+    fn default() -> Self {
+        Self::new([6.0, 12.0, 25.0])
+    }
+}
+
+impl SideBet for PerfectPairs {
+    fn name(&self) -> &'static str { "perfect-pairs" }
+
+    fn paytable(&self) -> &[f64] { &self.pays }
+
+    fn settle(&self, cards: &SideBetCards) -> f64 {
+        let [a, b] = cards.player;
+
+        if a.rank != b.rank {
+            -1.0
+        } else if a.suit == b.suit {
+            self.pays[2]
+        } else if a.suit.is_red() == b.suit.is_red() {
+            self.pays[1]
+        } else {
+            self.pays[0]
+        }
+    }
+}
+
+/// Lucky Ladies: the player's first two cards total 20. Paytable: any 20,
+/// suited 20, matched 20 (same rank and suit), pair of queens of hearts, pair
+/// of queens of hearts with a dealer natural.
+#[derive(Debug)]
+pub struct LuckyLadies {
+    pays: [f64; 5],
+}
+
+impl LuckyLadies {
+    pub fn new(pays: [f64; 5]) -> LuckyLadies {
+        LuckyLadies { pays }
+    }
+}
+
+impl Default for LuckyLadies {
+    fn default() -> Self {
+        Self::new([4.0, 10.0, 25.0, 200.0, 1000.0])
+    }
+}
+
+impl SideBet for LuckyLadies {
+    fn name(&self) -> &'static str { "lucky-ladies" }
+
+    fn paytable(&self) -> &[f64] { &self.pays }
+
+    fn settle(&self, cards: &SideBetCards) -> f64 {
+        let [a, b] = cards.player;
+        let queen_hearts = SuitedCard::new(12, Suit::Hearts);
+
+        if Hand::from(&[a.card(), b.card()][..]).value() != 20 {
+            -1.0
+        } else if a == queen_hearts && b == queen_hearts {
+            if cards.dealer.is_bj() { self.pays[4] } else { self.pays[3] }
+        } else if a == b {
+            self.pays[2]
+        } else if a.suit == b.suit {
+            self.pays[1]
+        } else {
+            self.pays[0]
+        }
+    }
+}
+
+/// Buster Blackjack: the dealer busts, paid by the number of cards in the
+/// dealer's busted hand. Paytable: 3 cards, 4, 5, 6, 7, 8 or more.
+#[derive(Debug)]
+pub struct BusterBlackjack {
+    pays: [f64; 6],
+}
+
+impl BusterBlackjack {
+    pub fn new(pays: [f64; 6]) -> BusterBlackjack {
+        BusterBlackjack { pays }
+    }
+}
+
+impl Default for BusterBlackjack {
+    fn default() -> Self {
+        Self::new([1.0, 2.0, 9.0, 50.0, 100.0, 250.0])
+    }
+}
+
+impl SideBet for BusterBlackjack {
+    fn name(&self) -> &'static str { "buster" }
+
+    fn paytable(&self) -> &[f64] { &self.pays }
+
+    fn settle(&self, cards: &SideBetCards) -> f64 {
+        if cards.dealer.is_busted() {
+            self.pays[cards.dealer.count().min(8) - 3]
+        } else {
+            -1.0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::side_bet::{SideBet, SideBetCards, SideBetWager,
+                          TwentyOnePlusThree, PerfectPairs, LuckyLadies,
+                          BusterBlackjack};
+    use crate::card::{SuitedCard, Suit};
+    use crate::card::Suit::*;
+    use crate::hand::Hand;
+
+    #[test]
+    fn it_parses_side_bets() {
+        let wager = SideBetWager::parse("perfect-pairs:5,10,30@2", 5.0)
+            .unwrap();
+        assert_eq!(wager.side_bet.name(), "perfect-pairs");
+        assert_eq!(wager.side_bet.paytable(), &[5.0, 10.0, 30.0]);
+        assert_eq!(wager.bet, 5.0);
+        assert_eq!(wager.min_true_count, Some(2.0));
+        assert_eq!(wager.spec(), "perfect-pairs:5,10,30@2");
+
+        assert!(SideBetWager::parse("perfect-pairs:5,10", 1.0).is_err());
+        assert!(SideBetWager::parse("perfect-pairs:5,-10,30", 1.0).is_err());
+        assert!(SideBetWager::parse("insurance", 1.0).is_err());
+    }
+
+    #[test]
+    fn it_settles_21_plus_3() {
+        let bet = TwentyOnePlusThree::default();
+
+        test_settle(&bet, -1.0,  [(2, Hearts), (9, Hearts)],  (5, Clubs),  &[5]);
+        test_settle(&bet, 5.0,   [(2, Hearts), (9, Hearts)],  (5, Hearts), &[5]);
+        test_settle(&bet, 10.0,  [(12, Hearts), (1, Clubs)],  (13, Hearts),
+                    &[10]);
+        test_settle(&bet, 10.0,  [(2, Hearts), (1, Clubs)],   (3, Hearts), &[3]);
+        test_settle(&bet, -1.0,  [(13, Hearts), (1, Clubs)],  (2, Hearts), &[2]);
+        test_settle(&bet, 30.0,  [(7, Hearts), (7, Clubs)],   (7, Hearts), &[7]);
+        test_settle(&bet, 40.0,  [(9, Spades), (11, Spades)], (10, Spades),
+                    &[10]);
+        test_settle(&bet, 100.0, [(7, Hearts), (7, Hearts)],  (7, Hearts), &[7]);
+    }
+
+    #[test]
+    fn it_settles_perfect_pairs() {
+        let bet = PerfectPairs::default();
+
+        test_settle(&bet, -1.0, [(10, Hearts), (13, Hearts)], (5, Clubs), &[5]);
+        test_settle(&bet, 6.0,  [(8, Hearts), (8, Clubs)],    (5, Clubs), &[5]);
+        test_settle(&bet, 12.0, [(8, Hearts), (8, Diamonds)], (5, Clubs), &[5]);
+        test_settle(&bet, 25.0, [(8, Spades), (8, Spades)],   (5, Clubs), &[5]);
+    }
+
+    #[test]
+    fn it_settles_lucky_ladies() {
+        let bet = LuckyLadies::default();
+
+        test_settle(&bet, -1.0, [(10, Hearts), (9, Hearts)], (5, Clubs), &[5]);
+        test_settle(&bet, 4.0, [(10, Hearts), (13, Clubs)], (5, Clubs), &[5]);
+        test_settle(&bet, 4.0, [(1, Hearts), (9, Clubs)], (5, Clubs), &[5]);
+        test_settle(&bet, 10.0, [(10, Hearts), (13, Hearts)], (5, Clubs), &[5]);
+        test_settle(&bet, 25.0, [(11, Clubs), (11, Clubs)], (5, Clubs), &[5]);
+        test_settle(&bet, 200.0, [(12, Hearts), (12, Hearts)], (1, Clubs),
+                    &[9]);
+        test_settle(&bet, 1000.0, [(12, Hearts), (12, Hearts)], (1, Clubs),
+                    &[10]);
+    }
+
+    #[test]
+    fn it_settles_buster_blackjack() {
+        let bet = BusterBlackjack::default();
+
+        test_settle(&bet, -1.0, [(10, Hearts), (9, Hearts)], (10, Clubs),
+                    &[7]);
+        test_settle(&bet, 1.0, [(10, Hearts), (9, Hearts)], (10, Clubs),
+                    &[6, 6]);
+        test_settle(&bet, 9.0, [(10, Hearts), (9, Hearts)], (2, Clubs),
+                    &[3, 7, 5, 6]);
+        test_settle(&bet, 250.0, [(10, Hearts), (9, Hearts)], (2, Clubs),
+                    &[1, 1, 2, 2, 2, 1, 1, 10]);
+    }
+
+    fn test_settle(bet: &dyn SideBet,
+                   expected: f64,
+                   player: [(u8, Suit); 2],
+                   upcard: (u8, Suit),
+                   dealer_rest: &[u8]) {
+        let player = [SuitedCard::new(player[0].0, player[0].1),
+                      SuitedCard::new(player[1].0, player[1].1)];
+        let dealer_upcard = SuitedCard::new(upcard.0, upcard.1);
+        let mut dealer = Hand::new();
+        dealer.add(dealer_upcard.card());
+        for &card in dealer_rest {
+            dealer.add(crate::card::Card(card));
+        }
+
+        let result = bet.settle(&SideBetCards {
+            player,
+            dealer_upcard,
+            dealer: &dealer,
+        });
+
+        assert_eq!(result, expected, "{} {:?} vs {}", bet.name(), player,
+                   dealer);
+    }
+}
@@ -1,10 +1,16 @@
 use std::ops::AddAssign;
 use std::collections::BTreeMap;
+use rand::SeedableRng;
+use rand::rngs::SmallRng;
+use serde::Serialize;
 
 use crate::hand_stats::HandStats;
 use crate::shoe::CardShoe;
 use crate::round_factory::RoundFactory;
 use crate::running_stats::RunningStats;
+use crate::side_bet::SideBetStats;
+use crate::script::ScriptError;
+use crate::event::EventLog;
 
 pub struct Simulator<'a>
 {
@@ -15,23 +21,86 @@ pub struct Simulator<'a>
     adjust_rc: Option<i32>,
     verbose: bool,
     print_progress: bool,
+    /// The event log the rounds are written to, with the job's number
+    event_log: Option<(&'a EventLog, u32)>,
+    /// The source of the players' tell misreadings
+    rng: SmallRng,
 }
 
+/// The results of the player's seat; the other seats' are in `seats`.
 #[derive(Debug, Default)]
 pub struct SimulationResult {
     pub winnings: RunningStats,
+    /// The part of the winnings coming from insurance and even money
+    pub insurance: RunningStats,
     pub hand_stats: HandStats,
     pub winning_distrib: BTreeMap<i32, u64>,
+    /// The results of each side bet, not included in the winnings
+    pub side_bets: Vec<SideBetStats>,
+    /// The results of every seat at the table, the player's included
+    pub seats: Vec<SeatStats>,
+    /// The number of shoes dealt, i.e. of reshuffles
+    pub shoes: u64,
+    /// The number of rounds the player watched without playing, when
+    /// wonging; the winnings are those of the rounds played
+    pub watched: u64,
+    /// The EV gained from each information source, when measured
+    pub information: Option<InformationGains>,
+}
+
+#[derive(Debug, Default)]
+pub struct SeatStats {
+    pub winnings: RunningStats,
+    pub hand_stats: HandStats,
+}
+
+/// The EV of the player knowing each information source alone, against the
+/// EV of the player knowing none.
+#[derive(Debug, Default, Serialize)]
+pub struct InformationGains {
+    /// The EV knowing nothing beyond the upcard
+    pub baseline_ev: f64,
+    pub sources: Vec<SourceGain>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SourceGain {
+    pub source: &'static str,
+    /// The EV knowing the source alone
+    pub ev: f64,
+    /// The EV gained over the baseline
+    pub ev_gain: f64,
 }
 
 impl AddAssign for SimulationResult {
     fn add_assign(&mut self, rhs: Self) {
         self.winnings += rhs.winnings;
+        self.insurance += rhs.insurance;
         self.hand_stats += rhs.hand_stats;
 
         for (&k, &v) in rhs.winning_distrib.iter() {
             *self.winning_distrib.entry(k).or_insert(0) += v;
         }
+
+        if self.side_bets.is_empty() {
+            self.side_bets = rhs.side_bets;
+        } else {
+            for (lhs, rhs) in self.side_bets.iter_mut().zip(rhs.side_bets) {
+                lhs.results += rhs.results;
+            }
+        }
+
+        if self.seats.is_empty() {
+            self.seats = rhs.seats;
+        } else {
+            for (lhs, rhs) in self.seats.iter_mut().zip(rhs.seats) {
+                lhs.winnings += rhs.winnings;
+                lhs.hand_stats += rhs.hand_stats;
+            }
+        }
+
+        self.shoes += rhs.shoes;
+        self.watched += rhs.watched;
     }
 }
 
@@ -52,13 +121,47 @@ impl<'a> Simulator<'a>
             adjust_rc,
             verbose,
             print_progress,
+            event_log: None,
+            rng: SmallRng::seed_from_u64(0),
         }
     }
 
-    pub fn run(mut self) -> SimulationResult {
+    /// Seeds the misreadings of the players' tells, for the simulation to be
+    /// reproducible.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = SmallRng::seed_from_u64(seed);
+        self
+    }
+
+    /// Writes the events of each round to an event log, as the `job`-th job.
+    pub fn with_event_log(mut self, event_log: &'a EventLog, job: u32)
+        -> Self {
+        self.event_log = Some((event_log, job));
+        self
+    }
+
+    /// Runs the simulation; fails when the player's script cannot be played.
+    pub fn run(mut self) -> Result<SimulationResult, ScriptError> {
         let mut winnings = RunningStats::default();
+        let mut insurance = RunningStats::default();
         let mut hand_stats = HandStats::default();
         let mut winning_distrib = BTreeMap::new();
+        let mut side_bets: Vec<SideBetStats> = self.round_factory.side_bets()
+            .iter()
+            .map(|wager| SideBetStats {
+                name: wager.side_bet.name(),
+                results: RunningStats::default(),
+            })
+            .collect();
+        let mut seats: Vec<SeatStats> = (0..self.round_factory.num_players())
+            .map(|_| SeatStats::default())
+            .collect();
+        let mut shoes = 0;
+        let mut watched = 0;
+        let seat = self.round_factory.seat() as usize;
+        let wonging = self.round_factory.wonging();
+        let mut playing = wonging.is_none();
+        let mut shoe_start = true;
 
         for round_i in 0..self.round_count {
             if let Some(force_tc) = self.force_tc {
@@ -67,34 +170,92 @@ impl<'a> Simulator<'a>
             let rc = self.shoe.running_count();
             let tc = self.shoe.true_count();
 
-            let (_, result) = self.round_factory.make(&mut *self.shoe).run();
+            if let Some(wonging) = wonging {
+                playing = wonging.plays(playing, tc, shoe_start);
+            }
+
+            let mut round = self.round_factory.make(&mut *self.shoe,
+                                                    &mut self.rng);
+            if !playing {
+                round = round.with_watcher(seat as u8);
+            }
+            if self.event_log.is_some() {
+                round = round.with_event_log();
+            }
+            let (_, result) = round.run()?;
+
+            if let Some((event_log, job)) = self.event_log {
+                event_log.write_round(job, round_i, &result.events)
+                    .expect("Couldn't write the event log");
+            }
+            shoe_start = result.reshuffled;
 
             if let Some(rel_rc) = self.adjust_rc {
                 self.shoe.adjust_running_count(rel_rc);
             }
 
-            let num_result = result.player_results[0];
-            winnings.push(num_result);
-            hand_stats += result.hand_stats;
+            if result.reshuffled {
+                shoes += 1;
+            }
 
-            let hash_key = (num_result * 2.0).round() as i32;
-            *winning_distrib.entry(hash_key).or_insert(0) += 1;
+            let num_result = result.player_results[seat];
+            if self.verbose {
+                eprintln!("rc = {:+}, tc = {:+.1}", rc, tc);
+                //eprintln!("{:?}", round);
+                if playing {
+                    eprintln!("res = {:+.1}\n", num_result);
+                } else {
+                    eprintln!("watched\n");
+                }
+            }
+
+            for (i, (stats, seat_hand_stats)) in seats.iter_mut()
+                .zip(result.hand_stats)
+                .enumerate() {
+                if i == seat && !playing {
+                    continue;
+                }
+                stats.winnings.push(result.player_results[i]);
+                if i == seat {
+                    hand_stats += seat_hand_stats.clone();
+                }
+                stats.hand_stats += seat_hand_stats;
+            }
 
             if self.print_progress {
                 Self::update_progress(round_i + 1, self.round_count);
             }
-            if self.verbose {
-                eprintln!("rc = {:+}, tc = {:+.1}", rc, tc);
-                //eprintln!("{:?}", round);
-                eprintln!("res = {:+.1}\n", num_result);
+
+            if !playing {
+                watched += 1;
+                continue;
+            }
+
+            winnings.push(num_result);
+            insurance.push(result.insurance_results[seat]);
+
+            for (stats, results) in side_bets.iter_mut()
+                                             .zip(result.side_bet_results) {
+                if let Some(res) = results[seat] {
+                    stats.results.push(res);
+                }
             }
+
+            let hash_key = (num_result * 2.0).round() as i32;
+            *winning_distrib.entry(hash_key).or_insert(0) += 1;
         }
 
-        SimulationResult {
+        Ok(SimulationResult {
             winnings,
+            insurance,
             hand_stats,
             winning_distrib,
-        }
+            side_bets,
+            seats,
+            shoes,
+            watched,
+            information: None,
+        })
     }
 
     fn update_progress(done: u64, total: u64) {
@@ -1,25 +1,31 @@
-use crate::simulator::{Simulator, SimulationResult};
+use crate::simulator::{Simulator, SimulationResult, InformationGains,
+                       SourceGain};
 use crate::shoe::CardShoe;
-use crate::round_factory::RoundFactory;
+use crate::round_factory::{RoundFactory, Information};
+use crate::script::ScriptError;
+use crate::event::EventLog;
 
 pub struct SmpSimulator<'a> {
     round_count: u64,
     round_factory: RoundFactory<'a>,
-    shoe_factory: Box<dyn Fn() -> Box<dyn CardShoe + Send>>,
+    shoe_factory: Box<dyn Fn(u32) -> Box<dyn CardShoe + Send>>,
     force_tc: Option<f32>,
     adjust_rc: Option<i32>,
     num_threads: u32,
     verbose: bool,
+    event_log: Option<EventLog>,
+    seed: u64,
+    information_gains: bool,
 }
 
 impl<'a> SmpSimulator<'a> {
     pub fn new(round_count: u64,
                round_factory: RoundFactory<'a>,
-               shoe_factory: Box<dyn Fn() -> Box<dyn CardShoe + Send>>,
+               shoe_factory: Box<dyn Fn(u32) -> Box<dyn CardShoe + Send>>,
                force_tc: Option<f32>,
                adjust_rc: Option<i32>,
                num_threads: u32,
-               verbose: bool) -> SmpSimulator {
+               verbose: bool) -> SmpSimulator<'a> {
         SmpSimulator {
             round_count,
             round_factory,
@@ -28,10 +34,77 @@ impl<'a> SmpSimulator<'a> {
             adjust_rc,
             num_threads,
             verbose,
+            event_log: None,
+            seed: 0,
+            information_gains: false,
         }
     }
 
-    pub fn run(self) -> SimulationResult {
+    /// Seeds the misreadings of the players' tells, each job from the next
+    /// seed.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Also measures the EV gained from each information source the players
+    /// have, by simulating the rounds again knowing each source alone, and
+    /// knowing none. Each run deals the same shoes, the shoe factory being
+    /// called with the index of the job.
+    pub fn with_information_gains(mut self) -> Self {
+        self.information_gains = true;
+        self
+    }
+
+    /// Writes the events of each round to an event log.
+    pub fn with_event_log(mut self, event_log: EventLog) -> Self {
+        self.event_log = Some(event_log);
+        self
+    }
+
+    /// Runs the simulation on all threads; fails when the player's script
+    /// cannot be played.
+    pub fn run(mut self) -> Result<SimulationResult, ScriptError> {
+        let mut result = self.run_jobs(true)?;
+
+        if let Some(event_log) = &self.event_log {
+            event_log.flush().expect("Couldn't write the event log");
+        }
+
+        let information = self.round_factory.information();
+        let sources = information.sources();
+        if self.information_gains && !sources.is_empty() {
+            self.round_factory.set_information(Information::default());
+            let baseline_ev = self.run_jobs(false)?.winnings.mean();
+
+            let mut gains = Vec::new();
+            for &(source, alone) in sources.iter() {
+                let ev = if sources.len() == 1 {
+                    result.winnings.mean()
+                } else {
+                    self.round_factory.set_information(alone);
+                    self.run_jobs(false)?.winnings.mean()
+                };
+                gains.push(SourceGain {
+                    source,
+                    ev,
+                    ev_gain: ev - baseline_ev,
+                });
+            }
+
+            self.round_factory.set_information(information);
+            result.information = Some(InformationGains {
+                baseline_ev,
+                sources: gains,
+            });
+        }
+
+        Ok(result)
+    }
+
+    /// Runs the rounds once on all threads; only the `main` run is logged
+    /// and shows its progress.
+    fn run_jobs(&self, main: bool) -> Result<SimulationResult, ScriptError> {
         let per_thread = self.round_count / self.num_threads as u64;
         let rest = self.round_count % self.num_threads as u64;
 
@@ -41,31 +114,38 @@ impl<'a> SmpSimulator<'a> {
             let mut threads = Vec::new();
 
             for i in 0..self.num_threads {
-                let shoe = (self.shoe_factory)();
+                let shoe = (self.shoe_factory)(i);
                 let round_factory = &self.round_factory;
                 let force_tc = self.force_tc;
                 let adjust_rc = self.adjust_rc;
                 let verbose = self.verbose;
+                let event_log = self.event_log.as_ref().filter(|_| main);
+                let seed = self.seed.wrapping_add(i as u64);
 
                 threads.push(scope.spawn(move |_| {
-                    let simulator = Simulator::new(
+                    let mut simulator = Simulator::new(
                         per_thread + (if i == 0 { rest } else { 0 }),
                         shoe,
                         round_factory,
                         force_tc,
                         adjust_rc,
                         verbose,
-                        i == 0,
-                    );
+                        main && i == 0,
+                    ).with_seed(seed);
+                    if let Some(event_log) = event_log {
+                        simulator = simulator.with_event_log(event_log, i);
+                    }
                     simulator.run()
                 }));
             }
 
             for thread in threads {
-                result += thread.join().unwrap();
+                result += thread.join().unwrap()?;
             }
-        }).unwrap();
 
-        result
+            Ok(())
+        }).unwrap()?;
+
+        Ok(result)
     }
 }
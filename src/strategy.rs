@@ -32,6 +32,25 @@ pub struct GameContext<'a> {
 
     /// The dealer's holecard if it is known (see holecarding option)
     pub holecard:   Option<Card>,
+
+    /// What a tell or a warp reads of the dealer's holecard, if any (see tell
+    /// option)
+    pub hole_tell:  Option<HoleTell>,
+
+    /// The next card off the shoe if it is known (see next card option)
+    pub next_card:  Option<Card>,
+}
+
+/// A reading of whether the dealer's holecard is a ten, from a tell or a
+/// warp, right with a given probability.
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub struct HoleTell {
+    /// Whether the holecard reads as a ten
+    pub ten:        bool,
+
+    /// The probability for the reading to be right, in `(0.5, 1.0]`; 1.0 for
+    /// a warp
+    pub accuracy:   f32,
 }
 
 pub trait Strategy {
@@ -42,6 +61,18 @@ pub trait Strategy {
         me: &Hand,
     ) -> Decision;
 
+    /// Returns how much to double down for when the strategy decided to
+    /// double, as a ratio of the hand's current stake in `(0.0, 1.0]`; values
+    /// under 1.0 require the double-for-less rule.
+    fn double_amount(
+        &self,
+        _game: &GameContext,
+        _dealer: Card,
+        _me: &Hand,
+    ) -> f64 {
+        1.0
+    }
+
     fn surrender(
         &self,
         _game: &GameContext,
@@ -55,4 +86,18 @@ pub trait Strategy {
     fn take_insurance(&self, _game: &GameContext, _me: &Hand) -> bool {
         false
     }
+
+    /// Returns how much to insure for when the strategy decided to take
+    /// insurance, as a ratio of the hand's bet in `(0.0, 0.5]`; values under
+    /// 0.5 are partial insurance.
+    fn insurance_amount(&self, _game: &GameContext, _me: &Hand) -> f64 {
+        0.5
+    }
+
+    /// Whether to take even money on a natural against a dealer ace, when the
+    /// rules offer it; defaults to the insurance decision, which is equivalent
+    /// for a 3:2 natural.
+    fn take_even_money(&self, game: &GameContext, me: &Hand) -> bool {
+        self.take_insurance(game, me)
+    }
 }
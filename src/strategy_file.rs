@@ -0,0 +1,220 @@
+use std::collections::HashMap;
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+use serde::ser::SerializeMap;
+use serde::de::Error;
+
+use crate::basic_strategy::BasicStrategy;
+use crate::deviation::Deviation;
+use crate::composition::CompositionRule;
+
+/// The actions of the hard and soft totals' tables.
+static TOTAL_ACTIONS: &[u8] = b"+=DdSsEeUu?&h";
+
+/// The actions of the pairs' table, '-' for playing the pair's total.
+static PAIR_ACTIONS: &[u8] = b"-V*?@";
+
+/// A basic strategy, as written in a strategy file: its tables, its
+/// deviations, and its composition-dependent rules.
+///
+/// Each table row is a hand's decisions against a dealer ace, then 2 to 10,
+/// by the tables' codes; the tables missing from a file are the built-in
+/// ones.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct StrategyFile {
+    /// From hard 20 down to hard 4
+    #[serde(serialize_with = "serialize_hard")]
+    #[serde(deserialize_with = "deserialize_hard")]
+    pub hard:           [[u8; 11]; 17],
+    /// From A10 down to AA
+    #[serde(serialize_with = "serialize_soft")]
+    #[serde(deserialize_with = "deserialize_soft")]
+    pub soft:           [[u8; 11]; 10],
+    /// From T/T down to A/A, ' ' ('-' in files) for playing the pair's total
+    #[serde(serialize_with = "serialize_pairs")]
+    #[serde(deserialize_with = "deserialize_pairs")]
+    pub pairs:          [[u8; 11]; 10],
+    pub deviations:     Vec<Deviation>,
+    pub composition:    Vec<CompositionRule>,
+}
+
+impl Default for StrategyFile {
+    fn default() -> Self {
+        BasicStrategy::new(false).export()
+    }
+}
+
+impl StrategyFile {
+    /// Parses a JSON strategy file.
+    pub fn from_json(json: &str) -> Result<StrategyFile, String> {
+        serde_json::from_str(json).map_err(|e| e.to_string())
+    }
+}
+
+fn hard_label(row: usize) -> String {
+    (20 - row).to_string()
+}
+
+fn soft_label(row: usize) -> String {
+    if row == 9 {
+        String::from("AA")
+    } else {
+        format!("A{}", 10 - row)
+    }
+}
+
+fn pair_label(row: usize) -> String {
+    match row {
+        0 => String::from("T/T"),
+        9 => String::from("A/A"),
+        _ => format!("{0}/{0}", 10 - row),
+    }
+}
+
+/// A table serialized as a map of its rows by hand, in the table's order.
+struct Rows<'a> {
+    rows: &'a [[u8; 11]],
+    label: fn(usize) -> String,
+}
+
+impl Serialize for Rows<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer {
+        let mut map = serializer.serialize_map(Some(self.rows.len()))?;
+        for (row, actions) in self.rows.iter().enumerate() {
+            let actions: String = actions[1..].iter()
+                .map(|&ch| if ch == b' ' { '-' } else { ch as char })
+                .collect();
+            map.serialize_entry(&(self.label)(row), &actions)?;
+        }
+        map.end()
+    }
+}
+
+fn serialize_hard<S>(rows: &[[u8; 11]; 17], serializer: S)
+    -> Result<S::Ok, S::Error> where S: Serializer {
+    Rows { rows, label: hard_label }.serialize(serializer)
+}
+
+fn serialize_soft<S>(rows: &[[u8; 11]; 10], serializer: S)
+    -> Result<S::Ok, S::Error> where S: Serializer {
+    Rows { rows, label: soft_label }.serialize(serializer)
+}
+
+fn serialize_pairs<S>(rows: &[[u8; 11]; 10], serializer: S)
+    -> Result<S::Ok, S::Error> where S: Serializer {
+    Rows { rows, label: pair_label }.serialize(serializer)
+}
+
+/// Reads a table from a map of all its rows by hand, checking their actions.
+fn deserialize_rows<'de, D, const N: usize>(deserializer: D,
+                                            label: fn(usize) -> String,
+                                            actions: &[u8])
+    -> Result<[[u8; 11]; N], D::Error> where D: Deserializer<'de> {
+    let mut map = HashMap::<String, String>::deserialize(deserializer)?;
+    let mut rows = [[b' '; 11]; N];
+
+    for (row, slots) in rows.iter_mut().enumerate() {
+        let hand = label(row);
+        let text = map.remove(&hand)
+            .ok_or_else(|| D::Error::custom(format!("missing hand {}", hand)))?;
+        if text.len() != 10 {
+            return Err(D::Error::custom(format!(
+                "{}: expected 10 actions, against A to 10", hand
+            )));
+        }
+        for (slot, &ch) in slots[1..].iter_mut().zip(text.as_bytes()) {
+            if !actions.contains(&ch) {
+                return Err(D::Error::custom(format!(
+                    "{}: invalid action '{}'", hand, ch as char
+                )));
+            }
+            *slot = if ch == b'-' { b' ' } else { ch };
+        }
+    }
+
+    if let Some(hand) = map.keys().next() {
+        return Err(D::Error::custom(format!("unknown hand {}", hand)));
+    }
+
+    Ok(rows)
+}
+
+fn deserialize_hard<'de, D>(deserializer: D)
+    -> Result<[[u8; 11]; 17], D::Error> where D: Deserializer<'de> {
+    deserialize_rows(deserializer, hard_label, TOTAL_ACTIONS)
+}
+
+fn deserialize_soft<'de, D>(deserializer: D)
+    -> Result<[[u8; 11]; 10], D::Error> where D: Deserializer<'de> {
+    deserialize_rows(deserializer, soft_label, TOTAL_ACTIONS)
+}
+
+fn deserialize_pairs<'de, D>(deserializer: D)
+    -> Result<[[u8; 11]; 10], D::Error> where D: Deserializer<'de> {
+    deserialize_rows(deserializer, pair_label, PAIR_ACTIONS)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::strategy_file::StrategyFile;
+
+    #[test]
+    fn it_round_trips_the_built_in_strategy() {
+        let file = StrategyFile::default();
+        let json = serde_json::to_string_pretty(&file).unwrap();
+
+        assert!(json.contains(r#""16": "S=====++SS""#));
+        assert!(json.contains(r#""A7": "+hdddd==++""#));
+        assert!(json.contains(r#""8/8": "@VVVVVVVV?""#));
+        assert!(json.contains(r#""T/T": "----------""#));
+        // The rows are in the tables' order
+        assert!(json.find(r#""20""#) < json.find(r#""4""#));
+
+        assert_eq!(StrategyFile::from_json(&json), Ok(file));
+    }
+
+    #[test]
+    fn it_reads_partial_files() {
+        let mut json: serde_json::Value = serde_json::to_value(
+            StrategyFile::default()
+        ).unwrap();
+        json["hard"]["12"] = "E+====++++".into();
+        let file = StrategyFile::from_json(&serde_json::json!({
+            "hard": json["hard"],
+            "deviations": ["16vs10:>+0="],
+        }).to_string()).unwrap();
+
+        assert_eq!(&file.hard[8], b" E+====++++");
+        assert_eq!(file.soft, StrategyFile::default().soft);
+        assert_eq!(file.deviations.len(), 1);
+    }
+
+    #[test]
+    fn it_rejects_invalid_tables() {
+        let json = serde_json::to_value(StrategyFile::default()).unwrap();
+        let check = |table: &str, hand: &str, row: serde_json::Value| {
+            let mut rows = json[table].clone();
+            if row.is_null() {
+                rows.as_object_mut().unwrap().remove(hand);
+            } else {
+                rows[hand] = row;
+            }
+            StrategyFile::from_json(
+                &serde_json::json!({ table: rows }).to_string()
+            ).unwrap_err()
+        };
+
+        assert!(check("hard", "16", "S=====++S".into())
+            .starts_with("16: expected 10 actions"));
+        assert!(check("hard", "16", "S=====++SV".into())
+            .starts_with("16: invalid action 'V'"));
+        assert!(check("pairs", "8/8", "+VVVVVVVVV".into())
+            .starts_with("8/8: invalid action '+'"));
+        assert!(check("soft", "A7", serde_json::Value::Null)
+            .starts_with("missing hand A7"));
+        assert!(check("soft", "A11", "==========".into())
+            .starts_with("unknown hand A11"));
+        assert!(StrategyFile::from_json(r#"{"double": {}}"#).is_err());
+    }
+}
@@ -4,27 +4,41 @@ use crate::card::Card;
 use std::collections::VecDeque;
 use bitflags::_core::cell::RefCell;
 use std::iter::FromIterator;
-use crate::game_rules::{GameRules, GameType, Soft17};
+use crate::game_rules::{GameRules, GameType, Soft17, CharlieRule};
 
 pub struct QueuedStrategy {
     decisions: RefCell<VecDeque<Decision>>,
     take_insurance: bool,
     surrender: bool,
+    double_amount: f64,
+    insurance_amount: f64,
 }
 
 impl QueuedStrategy {
     pub fn new(decisions: &[Decision],
                take_insurance: bool,
                surrender: bool) -> QueuedStrategy {
-        let vec = VecDeque::from_iter(decisions.iter().map(|&c| c));
+        let vec = VecDeque::from_iter(decisions.iter().copied());
 
         QueuedStrategy {
             decisions: RefCell::new(vec),
             take_insurance,
             surrender,
+            double_amount: 1.0,
+            insurance_amount: 0.5,
         }
     }
 
+    pub fn with_double_amount(mut self, double_amount: f64) -> Self {
+        self.double_amount = double_amount;
+        self
+    }
+
+    pub fn with_insurance_amount(mut self, insurance_amount: f64) -> Self {
+        self.insurance_amount = insurance_amount;
+        self
+    }
+
     pub fn is_empty(&self) -> bool {
         self.decisions.borrow().is_empty()
     }
@@ -39,6 +53,13 @@ impl Strategy for QueuedStrategy {
             .expect("No more decisions in the queue")
     }
 
+    fn double_amount(&self,
+                     _game: &GameContext,
+                     _dealer: Card,
+                     _me: &Hand) -> f64 {
+        self.double_amount
+    }
+
     fn surrender(&self,
                  _game: &GameContext,
                  _dealer: Card,
@@ -50,6 +71,10 @@ impl Strategy for QueuedStrategy {
     fn take_insurance(&self, _game: &GameContext, _me: &Hand) -> bool {
         self.take_insurance
     }
+
+    fn insurance_amount(&self, _game: &GameContext, _me: &Hand) -> f64 {
+        self.insurance_amount
+    }
 }
 
 pub mod options {
@@ -59,6 +84,7 @@ pub mod options {
     pub const H17: u32      = 1 << 1;
     pub const DAS: u32      = 1 << 2;
     pub const HAA: u32      = 1 << 3;
+    pub const CHARLIE5: u32 = 1 << 8;
 }
 
 pub fn make_rules(opts: u32) -> GameRules {
@@ -69,6 +95,11 @@ pub fn make_rules(opts: u32) -> GameRules {
         soft17: if opts & H17 > 0 { Soft17::H17 } else { Soft17::S17 },
         das: opts & DAS > 0,
         play_ace_pairs: opts & HAA > 0,
+        charlie: if opts & CHARLIE5 > 0 {
+            Some(CharlieRule { cards: 5, pays: 1.0, split_hands: true })
+        } else {
+            None
+        },
         ..GameRules::default()
     }
 }
@@ -0,0 +1,112 @@
+use crate::card::Card;
+use crate::hand::Hand;
+use crate::strategy::{Strategy, GameContext, Decision};
+
+/// The strategy of a typical tourist, making the common mistakes of players who
+/// never learned basic strategy: they never risk busting a hard 12 or more,
+/// stand on soft 18 whatever the dealer shows, only double down a hard 10 or
+/// 11 against a small card, only split aces and eights, insure their good
+/// hands, and never surrender.
+#[derive(Debug, Default)]
+pub struct TouristStrategy;
+
+impl TouristStrategy {
+    pub fn new() -> TouristStrategy {
+        TouristStrategy
+    }
+}
+
+impl Strategy for TouristStrategy {
+    fn player_turn(&self,
+                   game: &GameContext,
+                   dealer: Card,
+                   me: &Hand) -> Decision {
+        if me.is_doubled() {
+            return Decision::Stand;
+        }
+
+        if game.may_split && (me[0] == Card(1) || me[0] == Card(8)) {
+            return Decision::Split;
+        }
+
+        if me.is_soft() {
+            if me.value() >= 18 { Decision::Stand } else { Decision::Hit }
+        } else if me.value() >= 12 {
+            Decision::Stand
+        } else if game.may_double
+                  && (me.value() == 10 || me.value() == 11)
+                  && (2..=9).contains(&dealer.0) {
+            Decision::Double
+        } else {
+            Decision::Hit
+        }
+    }
+
+    fn take_insurance(&self, _game: &GameContext, me: &Hand) -> bool {
+        me.value() >= 19
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tourist_strategy::TouristStrategy;
+    use crate::strategy::{Strategy, GameContext, Decision};
+    use crate::strategy::Decision::*;
+    use crate::card::Card;
+    use crate::hand::Hand;
+    use crate::game_rules::GameRules;
+
+    #[test]
+    fn it_plays_like_a_tourist() {
+        test_decision(Stand,  &[10, 2],    2);
+        test_decision(Stand,  &[10, 6],    10);
+        test_decision(Stand,  &[5, 6, 1],  10);
+        test_decision(Hit,    &[1, 6],     3);
+        test_decision(Stand,  &[1, 7],     10);
+        test_decision(Double, &[5, 6],     9);
+        test_decision(Hit,    &[5, 6],     10);
+        test_decision(Hit,    &[5, 4],     3);
+        test_decision(Split,  &[8, 8],     10);
+        test_decision(Split,  &[1, 1],     1);
+        test_decision(Stand,  &[9, 9],     9);
+        test_decision(Double, &[5, 5],     6);
+    }
+
+    #[test]
+    fn it_insures_good_hands() {
+        let rules = GameRules::default();
+        let game = make_context(&rules);
+        let strategy = TouristStrategy::new();
+
+        assert!(strategy.take_insurance(&game, &Hand::from(&[10, 10][..])));
+        assert!(strategy.take_even_money(&game, &Hand::from(&[1, 10][..])));
+        assert!(!strategy.take_insurance(&game, &Hand::from(&[10, 6][..])));
+        assert!(!strategy.surrender(&game, Card(10),
+                                    &Hand::from(&[10, 6][..]), false));
+    }
+
+    fn make_context(rules: &GameRules) -> GameContext<'_> {
+        GameContext {
+            rules,
+            may_split: true,
+            may_double: true,
+            true_count: 0.0,
+            holecard: None,
+            hole_tell: None,
+            next_card: None,
+        }
+    }
+
+    fn test_decision(expected: Decision, hand: &[u8], dealer: u8) {
+        let rules = GameRules::default();
+        let mut game = make_context(&rules);
+        let hand = Hand::from(hand);
+        game.may_split = hand.count() == 2 && hand[0] == hand[1];
+        game.may_double = hand.count() == 2;
+
+        let decision = TouristStrategy::new().player_turn(&game, Card(dealer),
+                                                          &hand);
+
+        assert_eq!(decision, expected, "{} vs {}", hand, dealer);
+    }
+}
@@ -1,6 +1,6 @@
 mod utils;
 
-use utils::bin_runner::run_freebj;
+use utils::bin_runner::{run_freebj, run_freebj_status};
 use crate::utils::assert_f64_eq;
 
 #[test]
@@ -18,10 +18,10 @@ fn ahc_s17_das_d6() {
     let rules = json["rules"].as_object().unwrap();
     assert_eq!(rules["game_type"].as_str().unwrap(), "ahc");
     assert_eq!(rules["soft17"].as_str().unwrap(), "s17");
-    assert_eq!(rules["das"].as_bool().unwrap(), true);
+    assert!(rules["das"].as_bool().unwrap());
     assert_eq!(rules["double_down"].as_str().unwrap(), "any_two");
     assert_eq!(rules["surrender"].as_str().unwrap(), "no_surrender");
-    assert_eq!(rules["play_ace_pairs"].as_bool().unwrap(), false);
+    assert!(!rules["play_ace_pairs"].as_bool().unwrap());
     assert_eq!(rules["max_splits"].as_u64().unwrap(), 4);
     assert_eq!(rules["decks"].as_u64().unwrap(), 6);
 
@@ -71,10 +71,10 @@ fn ahc_s17_das_d6_esurr() {
     let rules = json["rules"].as_object().unwrap();
     assert_eq!(rules["game_type"].as_str().unwrap(), "ahc");
     assert_eq!(rules["soft17"].as_str().unwrap(), "s17");
-    assert_eq!(rules["das"].as_bool().unwrap(), true);
+    assert!(rules["das"].as_bool().unwrap());
     assert_eq!(rules["double_down"].as_str().unwrap(), "any_two");
     assert_eq!(rules["surrender"].as_str().unwrap(), "early_surrender");
-    assert_eq!(rules["play_ace_pairs"].as_bool().unwrap(), false);
+    assert!(!rules["play_ace_pairs"].as_bool().unwrap());
     assert_eq!(rules["max_splits"].as_u64().unwrap(), 4);
     assert_eq!(rules["decks"].as_u64().unwrap(), 6);
 
@@ -125,10 +125,10 @@ fn ahc_h17_das_d6() {
     let rules = json["rules"].as_object().unwrap();
     assert_eq!(rules["game_type"].as_str().unwrap(), "ahc");
     assert_eq!(rules["soft17"].as_str().unwrap(), "h17");
-    assert_eq!(rules["das"].as_bool().unwrap(), true);
+    assert!(rules["das"].as_bool().unwrap());
     assert_eq!(rules["double_down"].as_str().unwrap(), "any_two");
     assert_eq!(rules["surrender"].as_str().unwrap(), "no_surrender");
-    assert_eq!(rules["play_ace_pairs"].as_bool().unwrap(), false);
+    assert!(!rules["play_ace_pairs"].as_bool().unwrap());
     assert_eq!(rules["max_splits"].as_u64().unwrap(), 4);
     assert_eq!(rules["decks"].as_u64().unwrap(), 6);
 
@@ -178,10 +178,10 @@ fn ahc_s17_nodas_d6() {
     let rules = json["rules"].as_object().unwrap();
     assert_eq!(rules["game_type"].as_str().unwrap(), "ahc");
     assert_eq!(rules["soft17"].as_str().unwrap(), "s17");
-    assert_eq!(rules["das"].as_bool().unwrap(), false);
+    assert!(!rules["das"].as_bool().unwrap());
     assert_eq!(rules["double_down"].as_str().unwrap(), "any_two");
     assert_eq!(rules["surrender"].as_str().unwrap(), "no_surrender");
-    assert_eq!(rules["play_ace_pairs"].as_bool().unwrap(), false);
+    assert!(!rules["play_ace_pairs"].as_bool().unwrap());
     assert_eq!(rules["max_splits"].as_u64().unwrap(), 4);
     assert_eq!(rules["decks"].as_u64().unwrap(), 6);
 
@@ -226,10 +226,10 @@ fn enhc_s17_das_d6() {
     let rules = json["rules"].as_object().unwrap();
     assert_eq!(rules["game_type"].as_str().unwrap(), "enhc");
     assert_eq!(rules["soft17"].as_str().unwrap(), "s17");
-    assert_eq!(rules["das"].as_bool().unwrap(), true);
+    assert!(rules["das"].as_bool().unwrap());
     assert_eq!(rules["double_down"].as_str().unwrap(), "any_two");
     assert_eq!(rules["surrender"].as_str().unwrap(), "no_surrender");
-    assert_eq!(rules["play_ace_pairs"].as_bool().unwrap(), false);
+    assert!(!rules["play_ace_pairs"].as_bool().unwrap());
     assert_eq!(rules["max_splits"].as_u64().unwrap(), 4);
     assert_eq!(rules["decks"].as_u64().unwrap(), 6);
 
@@ -263,3 +263,356 @@ fn enhc_s17_das_d6() {
     assert_eq!(distrib["+5.0"].as_u64().unwrap(), 192);
     assert_eq!(distrib["+6.0"].as_u64().unwrap(), 56);
 }
+
+#[test]
+fn preset_with_overrides() {
+    let json = run_freebj(&[
+        "-n", "10k", "-j1",
+        "--preset", "single-deck-6to5", "--s17", "--bj-pays", "1.5",
+        "--shoe-file", "rc/shoe_1M",
+    ]);
+
+    let rules = json["rules"].as_object().unwrap();
+    assert_eq!(rules["preset"].as_str().unwrap(), "single-deck-6to5");
+    assert_eq!(rules["game_type"].as_str().unwrap(), "ahc");
+    assert_eq!(rules["soft17"].as_str().unwrap(), "s17");
+    assert!(!rules["das"].as_bool().unwrap());
+    assert_f64_eq(rules["bj_pays"].as_f64().unwrap(), 1.5, 10e-8);
+    assert_eq!(rules["decks"].as_u64().unwrap(), 1);
+    assert_eq!(rules["penetration_cards"].as_u64().unwrap(), 26);
+}
+
+#[test]
+fn rerun_from_config() {
+    let json = run_freebj(&[
+        "-n", "100k", "-j4",
+        "--enhc", "--h17", "--hilo", "--deviations",
+        "--bet-per-tc", "2", "-D", "12vs3:<+1+",
+        "--shoe-file", "rc/shoe_1M",
+    ]);
+
+    let mut path = std::env::temp_dir();
+    path.push(format!("freebj-config-{}.json", std::process::id()));
+    std::fs::write(&path, json["config"].to_string()).unwrap();
+    let rerun = run_freebj(&["--config", path.to_str().unwrap()]);
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(rerun["config"], json["config"]);
+    assert_eq!(rerun["rules"], json["rules"]);
+    assert_eq!(rerun["ev"], json["ev"]);
+    assert_eq!(rerun["hands"], json["hands"]);
+
+    let config = json["config"].as_object().unwrap();
+    assert_eq!(config["counting"].as_str().unwrap(), "hilo");
+    assert_eq!(config["shoe"]["path"].as_str().unwrap(), "rc/shoe_1M");
+    assert_eq!(config["run"]["rounds"].as_u64().unwrap(), 100_000);
+    assert_eq!(config["strategy"]["add_deviations"][0].as_str().unwrap(),
+               "12vs3:<+1+");
+}
+
+#[test]
+fn invalid_configs() {
+    let mut path = std::env::temp_dir();
+    path.push(format!("freebj-invalid-config-{}.json", std::process::id()));
+    let path_str = path.to_str().unwrap();
+    for config in [
+        r#"{"rules": {"decks": 1}}"#,
+        r#"{"rules": {"decks": 0}}"#,
+        r#"{"rules": {"bj_pays": -1.0}}"#,
+        r#"{"rules": {"preset": "monaco"}}"#,
+        r#"{"run": {"jobs": 0}}"#,
+        r#"{"betting": {"bet": -5.0}}"#,
+        r#"{"counting": "hilo",
+            "betting": {"spots": [{"true_count": -20.0, "spots": 0}]}}"#,
+    ] {
+        std::fs::write(&path, config).unwrap();
+        let status = run_freebj_status(&["--config", path_str, "--dry-run"]);
+        assert!(!status.success(), "{}", config);
+    }
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn multiple_seats() {
+    let json = run_freebj(&[
+        "-n", "100k", "-j4",
+        "--players", "3", "--seat", "2",
+        "--shoe-file", "rc/shoe_1M",
+    ]);
+
+    let seats = json["seats"].as_array().unwrap();
+    assert_eq!(seats.len(), 3);
+    assert_eq!(seats[1]["seat"].as_u64().unwrap(), 2);
+    assert_eq!(seats[1]["ev"], json["ev"]);
+    assert_eq!(seats[1]["stddev"], json["stddev"]);
+    assert_eq!(seats[1]["hands"], json["hands"]);
+    assert_ne!(seats[0]["ev"], seats[1]["ev"]);
+
+    for seat in seats {
+        assert!(seat["hands"]["total"].as_u64().unwrap() >= 100_000);
+    }
+
+    let table = json["config"]["table"].as_object().unwrap();
+    assert_eq!(table["players"].as_u64().unwrap(), 3);
+    assert_eq!(table["seat"].as_u64().unwrap(), 2);
+}
+
+#[test]
+fn heterogeneous_seats() {
+    let json = run_freebj(&[
+        "-n", "100k", "-j4",
+        "--seats", "tourist,basic,player", "--hilo", "--bet-per-tc", "2",
+        "--shoe-file", "rc/shoe_1M",
+    ]);
+
+    let seats = json["seats"].as_array().unwrap();
+    assert_eq!(seats.len(), 3);
+    assert_eq!(seats[0]["strategy"].as_str().unwrap(), "tourist");
+    assert_eq!(seats[1]["strategy"].as_str().unwrap(), "basic");
+    assert_eq!(seats[2]["strategy"].as_str().unwrap(), "player");
+    assert_eq!(seats[2]["ev"], json["ev"]);
+    assert_eq!(json["config"]["table"]["seat"].as_u64().unwrap(), 3);
+
+    // The tourist's mistakes cost them
+    assert!(seats[0]["ev"].as_f64().unwrap()
+            < seats[1]["ev"].as_f64().unwrap());
+    assert!(seats[0]["hands"]["doubled"].as_u64().unwrap()
+            < seats[1]["hands"]["doubled"].as_u64().unwrap());
+}
+
+#[test]
+fn multiple_spots() {
+    let one_spot = run_freebj(&[
+        "-n", "100k", "-j4", "--hilo", "--bet-per-tc", "0",
+        "--shoe-file", "rc/shoe_1M",
+    ]);
+    let two_spots = run_freebj(&[
+        "-n", "100k", "-j4", "--hilo", "--bet-per-tc", "0",
+        "--spots=-20:2",
+        "--shoe-file", "rc/shoe_1M",
+    ]);
+
+    let hands = two_spots["hands"]["total"].as_u64().unwrap();
+    assert!(hands >= 200_000);
+    assert!(hands >= 2 * one_spot["hands"]["total"].as_u64().unwrap() - 100);
+
+    // The spots play against the same dealer hand, so their results are
+    // correlated: the variance is more than twice a single spot's
+    let stddev = one_spot["stddev"].as_f64().unwrap();
+    assert!(two_spots["stddev"].as_f64().unwrap() > 1.5 * stddev);
+
+    let spots = &two_spots["config"]["betting"]["spots"];
+    assert_eq!(spots[0]["true_count"].as_f64().unwrap(), -20.0);
+    assert_eq!(spots[0]["spots"].as_u64().unwrap(), 2);
+}
+
+#[test]
+fn wonging() {
+    let json = run_freebj(&[
+        "-n", "100k", "-j4", "--hilo", "--bet-per-tc", "2",
+        "--wong-in", "2", "--wong-out", "0",
+        // Shoe files have no count, deal seeded shoes instead
+        "--seed", "42",
+    ]);
+
+    let played = json["rounds_played"].as_u64().unwrap();
+    let watched = json["rounds_watched"].as_u64().unwrap();
+    assert_eq!(played + watched, 100_000);
+    assert!(played > 0 && watched > played);
+    assert!(json["hands"]["total"].as_u64().unwrap() >= played);
+
+    let ev = json["ev"].as_f64().unwrap();
+    assert_f64_eq(json["ev_per_round_observed"].as_f64().unwrap(),
+                  ev * played as f64 / 100_000.0, 1e-9);
+
+    let wonging = &json["config"]["betting"]["wonging"];
+    assert_eq!(wonging["enter_tc"].as_f64().unwrap(), 2.0);
+    assert_eq!(wonging["exit_tc"].as_f64().unwrap(), 0.0);
+    assert!(!wonging["no_mid_shoe_entry"].as_bool().unwrap());
+}
+
+#[test]
+fn scripted_lines() {
+    let split_only = run_freebj(&[
+        "-n", "100k", "-j4", "--das", "-c", "8,8", "-a", "V",
+        "--shoe-file", "rc/shoe_1M",
+    ]);
+    let split_double = run_freebj(&[
+        "-n", "100k", "-j4", "--das", "-c", "8,8", "-a", "VD,D",
+        "--shoe-file", "rc/shoe_1M",
+    ]);
+
+    // Both hands are doubled unless the dealer has a natural; the strategy
+    // may split again the hands it plays, not the script
+    let hands = &split_double["hands"];
+    let doubled = hands["doubled"].as_u64().unwrap();
+    assert!(doubled > 180_000);
+    assert_eq!(hands["split"].as_u64().unwrap(), doubled);
+    assert_eq!(hands["total"].as_u64().unwrap(), 100_000 + doubled / 2);
+    assert!(split_only["hands"]["split"].as_u64().unwrap()
+            > hands["split"].as_u64().unwrap());
+    assert!(split_only["ev"].as_f64().unwrap()
+            > split_double["ev"].as_f64().unwrap());
+}
+
+#[test]
+fn event_log() {
+    let mut path = std::env::temp_dir();
+    path.push(format!("freebj-events-{}.jsonl", std::process::id()));
+    let json = run_freebj(&[
+        "-n", "10k", "-j2", "--event-log", path.to_str().unwrap(),
+        "--shoe-file", "rc/shoe_1M",
+    ]);
+    let log = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let events: Vec<serde_json::Value> = log.lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    let of_kind = |kind: &'static str| events.iter()
+        .filter(move |event| event["event"] == kind);
+
+    assert_eq!(of_kind("round_start").count(), 10_000);
+    assert_eq!(of_kind("round_end").count(), 10_000);
+    assert_eq!(of_kind("settle").count() as u64,
+               json["hands"]["total"].as_u64().unwrap());
+    assert_eq!(of_kind("decision")
+                   .filter(|event| event["action"] == "double")
+                   .count() as u64,
+               json["hands"]["doubled"].as_u64().unwrap());
+    assert!(of_kind("round_start").all(|event| event["job"].as_u64() < Some(2)));
+
+    let total: f64 = of_kind("settle")
+        .map(|event| event["result"].as_f64().unwrap())
+        .sum();
+    assert_f64_eq(total / 10_000.0, json["ev"].as_f64().unwrap(), 1e-9);
+}
+
+#[test]
+fn holecarding() {
+    let args = ["-n", "200k", "-j4", "--das", "--shoe-file", "rc/shoe_1M"];
+    let basic = run_freebj(&args);
+    let json = run_freebj(&[&args[..], &["--holecarding"]].concat());
+
+    // A flashing dealer is worth more than 10 % of the bet
+    assert!(basic["ev"].as_f64().unwrap() < 0.0);
+    assert!(json["ev"].as_f64().unwrap() > 0.1);
+    assert!(json["config"]["strategy"]["holecarding"].as_bool().unwrap());
+
+    // Doubling down into the dealer's stiffs, splitting even tens
+    let hands = &json["hands"];
+    assert!(hands["doubled"].as_u64() > basic["hands"]["doubled"].as_u64());
+    assert!(hands["split"].as_u64() > basic["hands"]["split"].as_u64());
+}
+
+#[test]
+fn partial_information() {
+    let args = ["-n", "100k", "-j4", "--seed", "1", "--shoe-file",
+                "rc/shoe_1M"];
+    let basic = run_freebj(&args);
+    let json = run_freebj(&[&args[..], &["--tell", "0.9", "--next-card",
+                                         "--info-gains"]].concat());
+    let strategy = &json["config"]["strategy"];
+    assert_f64_eq(strategy["tell"].as_f64().unwrap(), 0.9, 1e-6);
+    assert!(strategy["next_card"].as_bool().unwrap());
+
+    // The baseline is dealt the same cards as basic strategy
+    let information = &json["information"];
+    assert_f64_eq(information["baseline_ev"].as_f64().unwrap(),
+                  basic["ev"].as_f64().unwrap(), 1e-12);
+
+    let sources = information["sources"].as_array().unwrap();
+    assert_eq!(sources.len(), 2);
+    assert_eq!(sources[0]["source"], "tell");
+    assert_eq!(sources[1]["source"], "next_card");
+    for source in sources {
+        assert_f64_eq(source["ev_gain"].as_f64().unwrap(),
+                      source["ev"].as_f64().unwrap()
+                      - information["baseline_ev"].as_f64().unwrap(),
+                      1e-12);
+    }
+    assert!(sources[0]["ev_gain"].as_f64().unwrap() > 0.01);
+    assert!(sources[1]["ev_gain"].as_f64().unwrap() > 0.1);
+    assert!(json["ev"].as_f64().unwrap()
+            > sources[1]["ev"].as_f64().unwrap());
+}
+
+#[test]
+fn information_gains_on_the_same_shuffles() {
+    let args = ["-n", "50k", "-j2", "--seed", "1", "--ahc"];
+    let basic = run_freebj(&args);
+    let json = run_freebj(&[&args[..], &["--holecarding"]].concat());
+    assert!(json["information"].is_null());
+
+    // Each run is dealt the shoes of the simulation
+    let gains = run_freebj(&[&args[..], &["--holecarding", "--info-gains"]]
+                           .concat());
+    assert!(gains["config"]["run"]["information_gains"].as_bool().unwrap());
+    let information = &gains["information"];
+    assert_f64_eq(information["baseline_ev"].as_f64().unwrap(),
+                  basic["ev"].as_f64().unwrap(), 1e-12);
+    assert_f64_eq(information["sources"][0]["ev"].as_f64().unwrap(),
+                  json["ev"].as_f64().unwrap(), 1e-12);
+}
+
+#[test]
+fn composition_dependent_strategy() {
+    let args = ["-n", "100k", "-j4", "--shoe-file", "rc/shoe_1M"];
+    let basic = run_freebj(&args);
+    let json = run_freebj(&[&args[..], &["--composition", "single-deck"]]
+                          .concat());
+
+    // The rules are recorded, so that the run can be replayed
+    let composition = json["config"]["strategy"]["composition"]
+        .as_array().unwrap();
+    assert!(composition.contains(&"16(3+)vs10:=".into()));
+    assert!(composition.contains(&"10-2vs4:+".into()));
+    assert_ne!(json["ev"].as_f64(), basic["ev"].as_f64());
+}
+
+#[test]
+fn strategy_files() {
+    let mut path = std::env::temp_dir();
+    path.push(format!("freebj-strategy-{}.json", std::process::id()));
+    let path = path.to_str().unwrap();
+    let args = ["-n", "100k", "-j4", "--shoe-file", "rc/shoe_1M"];
+    let basic = run_freebj(&args);
+
+    // The built-in strategy played from its file is the same
+    assert!(run_freebj_status(&["--export-strategy", path]).success());
+    let json = run_freebj(&[&args[..], &["--strategy", path]].concat());
+    assert_eq!(json["config"]["strategy"]["file"], path);
+    assert_f64_eq(json["ev"].as_f64().unwrap(),
+                  basic["ev"].as_f64().unwrap(), 1e-12);
+
+    // Standing on all the stiff hands plays another strategy
+    let mut file: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(path).unwrap()
+    ).unwrap();
+    for total in 12..=16 {
+        file["hard"][total.to_string()] = "==========".into();
+    }
+    std::fs::write(path, file.to_string()).unwrap();
+    let json = run_freebj(&[&args[..], &["--strategy", path]].concat());
+    assert!(json["ev"].as_f64().unwrap() < basic["ev"].as_f64().unwrap());
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn exact_strategy() {
+    let mut path = std::env::temp_dir();
+    path.push(format!("freebj-exact-strategy-{}.json", std::process::id()));
+    let path = path.to_str().unwrap();
+    assert!(run_freebj_status(&["-d1", "--h17", "--exact-strategy",
+                                "--export-strategy", path]).success());
+
+    let file: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(path).unwrap()
+    ).unwrap();
+    assert_eq!(file["hard"]["11"], "DDDDDDDDDD");
+    assert_eq!(file["hard"]["9"], "+DDDDD++++");
+    assert_eq!(file["soft"]["A8"], "=====d====");
+    assert_eq!(file["pairs"]["8/8"], "VVVVVVVVVV");
+    assert_eq!(file["deviations"], serde_json::json!([]));
+    std::fs::remove_file(path).unwrap();
+}
@@ -1,11 +1,15 @@
-use std::process::Command;
+use std::process::{Command, ExitStatus};
 use serde_json::Value;
 
-pub fn run_freebj(args: &[&str]) -> Value {
+fn freebj() -> Command {
     let mut bin = std::env::current_dir().unwrap();
     bin.push("target/debug/freebj");
 
-    let proc = Command::new(bin)
+    Command::new(bin)
+}
+
+pub fn run_freebj(args: &[&str]) -> Value {
+    let proc = freebj()
         .args(args)
         .output().expect("Couldn't launch freebj");
 
@@ -15,3 +19,11 @@ pub fn run_freebj(args: &[&str]) -> Value {
 
     serde_json::from_slice(&proc.stdout).expect("Couldn't parse JSON")
 }
+
+/// Runs freebj for its side effects only, returns its exit status.
+pub fn run_freebj_status(args: &[&str]) -> ExitStatus {
+    freebj()
+        .args(args)
+        .output().expect("Couldn't launch freebj")
+        .status
+}